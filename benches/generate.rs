@@ -0,0 +1,91 @@
+//! パスワード生成のベンチマーク
+//!
+//! `cargo bench --bench generate`で実行します。実装を変更するときは、変更前のコミットで
+//! `cargo bench --bench generate -- --save-baseline before`を実行しておき、変更後に
+//! `cargo bench --bench generate -- --baseline before`で差を確認します。
+//!
+//! `choose_per_char`は、1文字ごとに`SliceRandom::choose`で乱数を引く以前の方式を
+//! 同じ文字集合で再現した基準です。過去のコミットに戻らなくても比較できます。
+//! 必須の文字種などの条件は確かめないため、乱数を引く部分だけの比較になります。
+
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion, Throughput};
+use rand::seq::SliceRandom;
+use rand::SeedableRng;
+use rand_chacha::ChaCha20Rng;
+use rpg::charset::UnicodeSet;
+use rpg::{PasswordConfig, PasswordGenerator};
+use std::hint::black_box;
+
+/// 1回の計測で生成する個数
+const BATCH: usize = 1000;
+
+fn configs() -> Vec<(&'static str, PasswordConfig)> {
+    vec![
+        ("default-16", PasswordConfig::default()),
+        (
+            "default-64",
+            PasswordConfig {
+                length: 64,
+                ..Default::default()
+            },
+        ),
+        (
+            "greek-cyrillic-20",
+            PasswordConfig {
+                length: 20,
+                unicode: vec![UnicodeSet::Greek, UnicodeSet::Cyrillic],
+                ..Default::default()
+            },
+        ),
+    ]
+}
+
+fn bench_generate(c: &mut Criterion) {
+    let mut group = c.benchmark_group("password");
+    group.throughput(Throughput::Elements(BATCH as u64));
+
+    for (name, config) in configs() {
+        let charset: Vec<char> = rpg::charset::build_class_charsets(&config)
+            .into_iter()
+            .flat_map(|class| class.chars)
+            .collect();
+        let length = config.length;
+        let generator = PasswordGenerator::new(config).unwrap();
+        let mut rng = ChaCha20Rng::seed_from_u64(0);
+
+        group.bench_function(BenchmarkId::new("choose_per_char", name), |b| {
+            b.iter(|| {
+                for _ in 0..BATCH {
+                    let password: String = (0..length)
+                        .map(|_| *charset.choose(&mut rng).unwrap())
+                        .collect();
+                    black_box(password);
+                }
+            })
+        });
+
+        group.bench_function(BenchmarkId::new("generate_with", name), |b| {
+            b.iter(|| {
+                for _ in 0..BATCH {
+                    black_box(generator.generate_with(&mut rng));
+                }
+            })
+        });
+
+        let mut buf = String::new();
+        group.bench_function(BenchmarkId::new("generate_into", name), |b| {
+            b.iter(|| {
+                for _ in 0..BATCH {
+                    buf.clear();
+                    generator.generate_into_with(&mut buf, &mut rng);
+                    black_box(&buf);
+                }
+            })
+        });
+    }
+
+    group.finish();
+}
+
+criterion_group!(benches, bench_generate);
+criterion_main!(benches);
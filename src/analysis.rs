@@ -0,0 +1,633 @@
+//! パスワード強度分析モジュール
+//!
+//! zxcvbnと同様に、パスワードを辞書の単語・繰り返し・連続文字・キーボード配列の
+//! パターンに分解し、最も推測しやすい分解のエントロピーを強度の推定値とします。
+
+use crate::charset::{CharClass, CharacterSets};
+use crate::entropy::Strength;
+use crate::error::{Result, RpgError};
+use crate::wordlist::{COMMON_PASSWORDS, WORDLIST};
+use std::fs;
+use std::ops::Range;
+use std::path::Path;
+
+/// 辞書照合の対象とする最小の単語長
+const MIN_WORD_LENGTH: usize = 3;
+
+/// パターンとみなす連続文字・キーボード配列の最小長
+const MIN_RUN_LENGTH: usize = 3;
+
+/// 分析する文字数の上限
+///
+/// zxcvbnと同様に、長い入力では先頭だけを分析します（分析にかかる時間を抑えるため）。
+const MAX_ANALYZED_LENGTH: usize = 100;
+
+/// 推奨するパスワードの最小長
+const RECOMMENDED_LENGTH: usize = 12;
+
+/// よく使われる文字置換（leet）
+const LEET_SUBSTITUTIONS: &[(char, char)] = &[
+    ('4', 'a'),
+    ('@', 'a'),
+    ('8', 'b'),
+    ('(', 'c'),
+    ('3', 'e'),
+    ('6', 'g'),
+    ('1', 'i'),
+    ('!', 'i'),
+    ('|', 'l'),
+    ('0', 'o'),
+    ('$', 's'),
+    ('5', 's'),
+    ('7', 't'),
+    ('+', 't'),
+    ('2', 'z'),
+];
+
+/// キーボード（QWERTY）の各行と、行頭の横方向のずれ
+const KEYBOARD_ROWS: &[(&str, f64)] = &[
+    ("1234567890-=", 0.0),
+    ("qwertyuiop[]", 0.5),
+    ("asdfghjkl;'", 0.75),
+    ("zxcvbnm,./", 1.25),
+];
+
+/// 検出されたパターンの種類
+#[derive(Debug, Clone, PartialEq)]
+pub enum MatchKind {
+    /// 単語リストの単語（`word`は照合された元の単語）
+    Dictionary { word: String },
+    /// 同じ文字・文字列の繰り返し
+    Repeat,
+    /// abc・321のような連続文字
+    Sequence,
+    /// qwerty・asdfのようなキーボード上の隣接キー
+    KeyboardWalk,
+}
+
+/// パスワード中で検出されたパターン
+#[derive(Debug, Clone, PartialEq)]
+pub struct PatternMatch {
+    pub kind: MatchKind,
+    /// 開始位置（文字単位、0始まり）
+    pub start: usize,
+    /// 終了位置（文字単位、この位置を含まない）
+    pub end: usize,
+    /// 該当部分の文字列
+    pub token: String,
+    /// この部分を推測するのに必要なエントロピー（ビット）
+    pub entropy_bits: f64,
+}
+
+/// パターンの候補（文字列は最小エントロピーの分解に使われたものだけ作る）
+struct Candidate {
+    kind: MatchKind,
+    range: Range<usize>,
+    entropy_bits: f64,
+}
+
+/// パスワードの分析結果
+#[derive(Debug, Clone, PartialEq)]
+pub struct Analysis {
+    /// 推定エントロピー（ビット）
+    pub entropy_bits: f64,
+    pub strength: Strength,
+    /// 含まれる文字種
+    pub classes: Vec<CharClass>,
+    /// 推定に使われた（弱い部分を構成する）パターン
+    pub matches: Vec<PatternMatch>,
+    /// 改善のための助言
+    pub feedback: Vec<String>,
+}
+
+impl Analysis {
+    /// 0〜4のスコア（強度の段階）
+    pub fn score(&self) -> u8 {
+        self.strength as u8
+    }
+
+    /// 弱い部分の文字範囲
+    pub fn weak_ranges(&self) -> Vec<(usize, usize)> {
+        self.matches.iter().map(|m| (m.start, m.end)).collect()
+    }
+}
+
+/// 単語リストファイル（1行1単語）を読み込む
+pub fn load_wordlist(path: &Path) -> Result<Vec<String>> {
+    let content = fs::read_to_string(path).map_err(|e| RpgError::FileRead {
+        path: path.display().to_string(),
+        message: e.to_string(),
+    })?;
+    Ok(content
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty() && !line.starts_with('#'))
+        .map(str::to_string)
+        .collect())
+}
+
+/// パスワードを分析
+///
+/// `extra_words`は組み込みの単語リストに加えて照合する単語です。
+/// 先頭`MAX_ANALYZED_LENGTH`文字を超える部分は分析せず、エントロピーにも数えません。
+pub fn analyze(password: &str, extra_words: &[String]) -> Result<Analysis> {
+    let chars: Vec<char> = password.chars().take(MAX_ANALYZED_LENGTH).collect();
+    if chars.is_empty() {
+        return Err(RpgError::InvalidLength(0));
+    }
+
+    let classes: Vec<CharClass> = CharClass::ALL
+        .into_iter()
+        .filter(|&class| chars.iter().any(|&c| CharClass::of(c) == class))
+        .collect();
+    let cardinality = bruteforce_cardinality(&chars);
+
+    let mut candidates = Vec::new();
+    candidates.extend(dictionary_matches(&chars, extra_words));
+    candidates.extend(repeat_matches(&chars));
+    candidates.extend(sequence_matches(&chars));
+    candidates.extend(keyboard_matches(&chars));
+
+    let (entropy_bits, matches) = minimum_entropy_cover(&chars, cardinality, candidates);
+    let strength = Strength::from_bits(entropy_bits);
+    let feedback = build_feedback(chars.len(), &classes, &matches, strength);
+
+    Ok(Analysis {
+        entropy_bits,
+        strength,
+        classes,
+        matches,
+        feedback,
+    })
+}
+
+/// 総当たりで推測する場合の1文字あたりの候補数（含まれる文字種の大きさの和）
+fn bruteforce_cardinality(chars: &[char]) -> f64 {
+    let mut cardinality = 0;
+    for class in CharClass::ALL {
+        if chars.iter().any(|&c| CharClass::of(c) == class) {
+            cardinality += class.chars().len();
+        }
+    }
+    // 記号セット外の文字（空白やUnicode）も最低限の候補数として数える
+    if chars.iter().any(|&c| {
+        !c.is_ascii()
+            || (CharClass::of(c) == CharClass::Symbol && !CharacterSets::SYMBOLS.contains(c))
+    }) {
+        cardinality += 33;
+    }
+    cardinality as f64
+}
+
+/// パスワード全体を最小のエントロピーで覆うパターンの組み合わせを求める
+///
+/// パターンに覆われない文字は総当たり（1文字あたり`log2(cardinality)`ビット）とします。
+fn minimum_entropy_cover(
+    chars: &[char],
+    cardinality: f64,
+    candidates: Vec<Candidate>,
+) -> (f64, Vec<PatternMatch>) {
+    let length = chars.len();
+    let char_bits = cardinality.log2();
+    // 各位置から始まる候補の添字（位置ごとに候補全体を見直さないため）
+    let mut starting_at: Vec<Vec<usize>> = vec![Vec::new(); length];
+    for (index, candidate) in candidates.iter().enumerate() {
+        starting_at[candidate.range.start].push(index);
+    }
+
+    // best[i]: 先頭i文字を覆う最小エントロピー、back[i]: そのとき最後に使ったパターン
+    let mut best = vec![f64::INFINITY; length + 1];
+    let mut back: Vec<Option<usize>> = vec![None; length + 1];
+    best[0] = 0.0;
+
+    for i in 0..length {
+        if best[i] + char_bits < best[i + 1] {
+            best[i + 1] = best[i] + char_bits;
+            back[i + 1] = None;
+        }
+        for &index in &starting_at[i] {
+            let candidate = &candidates[index];
+            let end = candidate.range.end;
+            if best[i] + candidate.entropy_bits < best[end] {
+                best[end] = best[i] + candidate.entropy_bits;
+                back[end] = Some(index);
+            }
+        }
+    }
+
+    let mut used = Vec::new();
+    let mut position = length;
+    while position > 0 {
+        match back[position] {
+            Some(index) => {
+                used.push(index);
+                position = candidates[index].range.start;
+            }
+            None => position -= 1,
+        }
+    }
+    used.reverse();
+
+    let matches = used
+        .into_iter()
+        .map(|index| {
+            let candidate = &candidates[index];
+            PatternMatch {
+                kind: candidate.kind.clone(),
+                start: candidate.range.start,
+                end: candidate.range.end,
+                token: chars[candidate.range.clone()].iter().collect(),
+                entropy_bits: candidate.entropy_bits,
+            }
+        })
+        .collect();
+    (best[length], matches)
+}
+
+/// 大文字・小文字の混在による追加エントロピー
+fn case_variation_bits(token: &[char]) -> f64 {
+    let upper = token.iter().filter(|c| c.is_uppercase()).count();
+    let lower = token.iter().filter(|c| c.is_lowercase()).count();
+    if upper == 0 {
+        return 0.0;
+    }
+    // 先頭だけ大文字、またはすべて大文字はよくある変形
+    let first_only = upper == 1 && token.first().is_some_and(|c| c.is_uppercase());
+    if first_only || lower == 0 {
+        return 1.0;
+    }
+    let n = upper + lower;
+    let variations: f64 = (1..=upper.min(lower)).map(|k| binomial(n, k)).sum();
+    variations.log2()
+}
+
+fn binomial(n: usize, k: usize) -> f64 {
+    (0..k).fold(1.0, |acc, i| acc * (n - i) as f64 / (i + 1) as f64)
+}
+
+/// 大文字小文字とleet置換を正規化した文字
+fn normalize(c: char) -> char {
+    let lower = c.to_ascii_lowercase();
+    LEET_SUBSTITUTIONS
+        .iter()
+        .find(|(from, _)| *from == lower)
+        .map(|&(_, to)| to)
+        .unwrap_or(lower)
+}
+
+/// 単語リスト・よく使われるパスワードとの照合（大文字小文字とleet置換を正規化）
+fn dictionary_matches(chars: &[char], extra_words: &[String]) -> Vec<Candidate> {
+    let normalized: Vec<char> = chars.iter().map(|&c| normalize(c)).collect();
+
+    let common: Vec<String> = COMMON_PASSWORDS.iter().map(|p| p.to_string()).collect();
+    let words: Vec<String> = WORDLIST
+        .iter()
+        .map(|w| w.to_string())
+        .chain(extra_words.iter().map(|w| w.trim().to_lowercase()))
+        .filter(|w| w.chars().count() >= MIN_WORD_LENGTH)
+        .collect();
+
+    let mut matches = Vec::new();
+    for dictionary in [&common, &words] {
+        let dictionary_bits = (dictionary.len().max(1) as f64).log2();
+        for word in dictionary {
+            let word_chars: Vec<char> = word.chars().collect();
+            if word_chars.len() > normalized.len() {
+                continue;
+            }
+            // 数字を含む単語（123456など）も同じ正規化をして比較する
+            let normalized_word: Vec<char> = word_chars.iter().map(|&c| normalize(c)).collect();
+            for start in 0..=normalized.len() - word_chars.len() {
+                let end = start + word_chars.len();
+                if normalized[start..end] != normalized_word[..] {
+                    continue;
+                }
+                let original = &chars[start..end];
+                let substitutions = original
+                    .iter()
+                    .zip(&word_chars)
+                    .filter(|(o, w)| o.to_ascii_lowercase() != **w)
+                    .count();
+                matches.push(Candidate {
+                    kind: MatchKind::Dictionary { word: word.clone() },
+                    range: start..end,
+                    entropy_bits: dictionary_bits
+                        + case_variation_bits(original)
+                        + substitutions as f64,
+                });
+            }
+        }
+    }
+    matches
+}
+
+/// 同じ文字・文字列の繰り返し（aaa、abcabc）
+///
+/// 開始位置と単位の組ごとに、できるだけ長く伸ばした繰り返しだけを候補にします。
+/// 単位がさらに短い単位の繰り返しの場合（aaaaの単位aa）や、同じ単位の繰り返しが
+/// 手前から続いている場合（aaaの2文字目から）は、より良い候補があるので除きます。
+fn repeat_matches(chars: &[char]) -> Vec<Candidate> {
+    let mut matches = Vec::new();
+    for start in 0..chars.len() {
+        for base_len in 1..=(chars.len() - start) / 2 {
+            let base = &chars[start..start + base_len];
+            let continues_from_left = start >= base_len && chars[start - base_len..start] == *base;
+            if continues_from_left || has_shorter_period(base) {
+                continue;
+            }
+            let mut repeats = 1;
+            while start + (repeats + 1) * base_len <= chars.len()
+                && &chars[start + repeats * base_len..start + (repeats + 1) * base_len] == base
+            {
+                repeats += 1;
+            }
+            let end = start + repeats * base_len;
+            // 1文字の繰り返しは3回以上、文字列の繰り返しは2回以上
+            if repeats < 2 || end - start < MIN_RUN_LENGTH {
+                continue;
+            }
+            let base_bits = base_len as f64 * bruteforce_cardinality(base).log2();
+            matches.push(Candidate {
+                kind: MatchKind::Repeat,
+                range: start..end,
+                entropy_bits: base_bits + (repeats as f64).log2(),
+            });
+        }
+    }
+    matches
+}
+
+/// 文字列がより短い単位の繰り返しか（abab、aaa）
+fn has_shorter_period(base: &[char]) -> bool {
+    (1..base.len())
+        .filter(|&period| base.len().is_multiple_of(period))
+        .any(|period| base[period..] == base[..base.len() - period])
+}
+
+/// 連続文字（abc、321、XYZ）
+fn sequence_matches(chars: &[char]) -> Vec<Candidate> {
+    let same_sequence_class = |a: char, b: char| {
+        let class = CharClass::of(a);
+        class != CharClass::Symbol && class == CharClass::of(b)
+    };
+
+    let mut matches = Vec::new();
+    let mut start = 0;
+    while start + 1 < chars.len() {
+        let delta = chars[start + 1] as i64 - chars[start] as i64;
+        if delta.abs() != 1 || !same_sequence_class(chars[start], chars[start + 1]) {
+            start += 1;
+            continue;
+        }
+        let mut end = start + 2;
+        while end < chars.len()
+            && chars[end] as i64 - chars[end - 1] as i64 == delta
+            && same_sequence_class(chars[end - 1], chars[end])
+        {
+            end += 1;
+        }
+        if end - start >= MIN_RUN_LENGTH {
+            let first = chars[start];
+            let start_bits = if "aAzZ019".contains(first) {
+                2.0
+            } else if first.is_ascii_digit() {
+                10f64.log2()
+            } else {
+                26f64.log2() + first.is_ascii_uppercase() as u8 as f64
+            };
+            let descending_bits = if delta < 0 { 1.0 } else { 0.0 };
+            matches.push(Candidate {
+                kind: MatchKind::Sequence,
+                range: start..end,
+                entropy_bits: start_bits + ((end - start) as f64).log2() + descending_bits,
+            });
+        }
+        start = end - 1;
+    }
+    matches
+}
+
+/// キーボード上の位置（行, 横位置）
+fn key_position(c: char) -> Option<(i32, f64)> {
+    let lower = c.to_ascii_lowercase();
+    KEYBOARD_ROWS
+        .iter()
+        .enumerate()
+        .find_map(|(row, (keys, offset))| {
+            keys.chars()
+                .position(|k| k == lower)
+                .map(|col| (row as i32, col as f64 + offset))
+        })
+}
+
+/// 隣接キーなら移動方向（行の差, 横方向の符号）を返す
+fn key_step(a: char, b: char) -> Option<(i32, i32)> {
+    let (row_a, x_a) = key_position(a)?;
+    let (row_b, x_b) = key_position(b)?;
+    let dy = row_b - row_a;
+    let dx = x_b - x_a;
+    let adjacent = match dy.abs() {
+        0 => (dx.abs() - 1.0).abs() < f64::EPSILON,
+        1 => dx.abs() <= 1.0,
+        _ => false,
+    };
+    adjacent.then_some((dy, dx.signum() as i32))
+}
+
+/// キーボード配列上の隣接キーの並び（qwerty、asdf、1qaz）
+fn keyboard_matches(chars: &[char]) -> Vec<Candidate> {
+    // 始点となるキーの数と、1キーあたりの平均的な隣接キー数
+    let key_count: usize = KEYBOARD_ROWS.iter().map(|(keys, _)| keys.len()).sum();
+    const AVERAGE_DEGREE: f64 = 4.5;
+
+    let mut matches = Vec::new();
+    let mut start = 0;
+    while start + 1 < chars.len() {
+        if key_step(chars[start], chars[start + 1]).is_none() {
+            start += 1;
+            continue;
+        }
+        let mut end = start + 1;
+        let mut turns = 0;
+        let mut direction = None;
+        while end < chars.len() {
+            match key_step(chars[end - 1], chars[end]) {
+                Some(step) => {
+                    if direction.is_some_and(|d| d != step) {
+                        turns += 1;
+                    }
+                    direction = Some(step);
+                    end += 1;
+                }
+                None => break,
+            }
+        }
+        if end - start >= MIN_RUN_LENGTH {
+            let original = &chars[start..end];
+            matches.push(Candidate {
+                kind: MatchKind::KeyboardWalk,
+                range: start..end,
+                entropy_bits: (key_count as f64).log2()
+                    + ((end - start) as f64).log2()
+                    + turns as f64 * AVERAGE_DEGREE.log2()
+                    + case_variation_bits(original),
+            });
+        }
+        start = end - 1;
+    }
+    matches
+}
+
+fn build_feedback(
+    length: usize,
+    classes: &[CharClass],
+    matches: &[PatternMatch],
+    strength: Strength,
+) -> Vec<String> {
+    let mut feedback = Vec::new();
+    let mut push = |message: String| {
+        if !feedback.contains(&message) {
+            feedback.push(message);
+        }
+    };
+
+    for m in matches {
+        match &m.kind {
+            MatchKind::Dictionary { word } => push(format!(
+                "Avoid dictionary words such as \"{}\", even with substitutions",
+                word
+            )),
+            MatchKind::Repeat => {
+                push("Avoid repeated characters or chunks like \"aaa\" or \"abcabc\"".to_string())
+            }
+            MatchKind::Sequence => push("Avoid sequences like \"abc\" or \"321\"".to_string()),
+            MatchKind::KeyboardWalk => {
+                push("Avoid keyboard patterns like \"qwerty\" or \"asdf\"".to_string())
+            }
+        }
+    }
+    if length < RECOMMENDED_LENGTH {
+        push(format!(
+            "Use a longer password (at least {} characters)",
+            RECOMMENDED_LENGTH
+        ));
+    }
+    if classes.len() < 3 && strength < Strength::Strong {
+        push("Mix uppercase, lowercase, digits and symbols".to_string());
+    }
+
+    feedback
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn kinds(analysis: &Analysis) -> Vec<MatchKind> {
+        analysis.matches.iter().map(|m| m.kind.clone()).collect()
+    }
+
+    #[test]
+    fn test_analyze_empty_password() {
+        assert_eq!(analyze("", &[]), Err(RpgError::InvalidLength(0)));
+    }
+
+    #[test]
+    fn test_analyze_detects_dictionary_word_with_leet() {
+        let analysis = analyze("D4rk!", &[]).unwrap();
+        assert!(kinds(&analysis).contains(&MatchKind::Dictionary {
+            word: "dark".to_string()
+        }));
+    }
+
+    #[test]
+    fn test_analyze_detects_common_password() {
+        let analysis = analyze("password123", &[]).unwrap();
+        assert!(analysis.matches.iter().any(|m| matches!(
+            &m.kind,
+            MatchKind::Dictionary { word } if word.starts_with("password")
+        )));
+        assert!(analysis.strength < Strength::Reasonable);
+
+        let analysis = analyze("Dr4gon", &[]).unwrap();
+        assert_eq!(
+            kinds(&analysis),
+            vec![MatchKind::Dictionary {
+                word: "dragon".to_string()
+            }]
+        );
+    }
+
+    #[test]
+    fn test_analyze_uses_extra_words() {
+        let analysis = analyze("xyzzyplugh", &["plugh".to_string()]).unwrap();
+        assert!(kinds(&analysis).contains(&MatchKind::Dictionary {
+            word: "plugh".to_string()
+        }));
+    }
+
+    #[test]
+    fn test_analyze_detects_sequence_and_repeat() {
+        let analysis = analyze("abcdef777777", &[]).unwrap();
+        let kinds = kinds(&analysis);
+        assert!(kinds.contains(&MatchKind::Sequence));
+        assert!(kinds.contains(&MatchKind::Repeat));
+        assert_eq!(analysis.weak_ranges(), vec![(0, 6), (6, 12)]);
+    }
+
+    #[test]
+    fn test_analyze_detects_descending_digits() {
+        let analysis = analyze("9876", &[]).unwrap();
+        assert_eq!(kinds(&analysis), vec![MatchKind::Sequence]);
+    }
+
+    #[test]
+    fn test_analyze_detects_keyboard_walk() {
+        let analysis = analyze("qwertyuiop", &[]).unwrap();
+        assert_eq!(kinds(&analysis), vec![MatchKind::KeyboardWalk]);
+        assert_eq!(analysis.score(), 0);
+    }
+
+    #[test]
+    fn test_analyze_random_password_is_strong() {
+        let analysis = analyze("T9#vQ2!mZr8&Lw4^", &[]).unwrap();
+        assert!(analysis.matches.is_empty());
+        assert!(analysis.score() >= 3);
+        assert_eq!(analysis.classes, CharClass::ALL.to_vec());
+        assert!(analysis.feedback.is_empty());
+    }
+
+    #[test]
+    fn test_analyze_weak_password_is_weaker_than_bruteforce() {
+        let analysis = analyze("password123", &[]).unwrap();
+        let bruteforce = 11.0 * 36f64.log2();
+        assert!(analysis.entropy_bits < bruteforce);
+        assert!(!analysis.feedback.is_empty());
+    }
+
+    #[test]
+    fn test_analyze_long_repetitive_input_is_fast() {
+        // 繰り返しの候補が入力長の2乗に増えないこと、長さの上限で打ち切られること
+        let started = std::time::Instant::now();
+        for password in ["a".repeat(100_000), "ab".repeat(50_000), "abc".repeat(40)] {
+            let analysis = analyze(&password, &[]).unwrap();
+            assert_eq!(kinds(&analysis), vec![MatchKind::Repeat]);
+            assert!(analysis.matches[0].end <= MAX_ANALYZED_LENGTH);
+            assert!(analysis.strength <= Strength::Weak);
+        }
+        assert!(
+            started.elapsed() < std::time::Duration::from_secs(2),
+            "elapsed = {:?}",
+            started.elapsed()
+        );
+    }
+
+    #[test]
+    fn test_case_variation_bits() {
+        let chars = |s: &str| s.chars().collect::<Vec<char>>();
+        assert_eq!(case_variation_bits(&chars("dark")), 0.0);
+        assert_eq!(case_variation_bits(&chars("Dark")), 1.0);
+        assert_eq!(case_variation_bits(&chars("DARK")), 1.0);
+        assert!(case_variation_bits(&chars("dArK")) > 1.0);
+    }
+}
@@ -0,0 +1,343 @@
+//! プレフィックスとチェックサム付きのAPIキー生成・検証モジュール
+//!
+//! キーは`<prefix>_<本体><チェックサム>`の形式です（GitHubのトークンと同様）。
+//!
+//! - 本体: base62（`0-9A-Za-z`）のランダムな文字列
+//! - チェックサム: `<prefix>_<本体>`のCRC32（またはCRC32C）をbase62で6文字にしたもの
+//!
+//! プレフィックスでシークレットスキャナーがキーを見分けられ、チェックサムで
+//! データベースを引く前に打ち間違いを検出できます。
+
+use crate::error::{Result, RpgError};
+use crate::secret::SecretGenerator;
+use clap::ValueEnum;
+use rand::{CryptoRng, Rng, RngCore};
+
+/// base62のアルファベット
+const BASE62_ALPHABET: &[u8; 62] =
+    b"0123456789ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz";
+
+/// チェックサムの文字数（62^6 > 2^32）
+pub const CHECKSUM_LENGTH: usize = 6;
+
+/// プレフィックスの最大文字数
+pub const MAX_PREFIX_LENGTH: usize = 32;
+
+/// 本体の最大文字数
+pub const MAX_BODY_LENGTH: usize = 256;
+
+/// キーのチェックサムの計算方法
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum KeyChecksum {
+    /// CRC-32（IEEE 802.3、zlibと同じ）
+    Crc32,
+    /// CRC-32C（Castagnoli）
+    Crc32c,
+}
+
+impl KeyChecksum {
+    /// データのチェックサム
+    pub fn compute(self, data: &[u8]) -> u32 {
+        let polynomial = match self {
+            KeyChecksum::Crc32 => 0xEDB8_8320,
+            KeyChecksum::Crc32c => 0x82F6_3B78,
+        };
+        let mut crc = !0u32;
+        for &byte in data {
+            crc ^= byte as u32;
+            for _ in 0..8 {
+                crc = if crc & 1 != 0 {
+                    (crc >> 1) ^ polynomial
+                } else {
+                    crc >> 1
+                };
+            }
+        }
+        !crc
+    }
+
+    /// `<prefix>_<本体>`に付けるチェックサムの文字列
+    fn suffix(self, prefixed_body: &str) -> String {
+        let mut value = self.compute(prefixed_body.as_bytes());
+        let mut digits = [b'0'; CHECKSUM_LENGTH];
+        for digit in digits.iter_mut().rev() {
+            *digit = BASE62_ALPHABET[(value % 62) as usize];
+            value /= 62;
+        }
+        String::from_utf8(digits.to_vec()).unwrap()
+    }
+}
+
+/// プレフィックスがASCII英数字1〜32文字か検証
+fn validate_prefix(prefix: &str) -> Result<()> {
+    if prefix.is_empty()
+        || prefix.len() > MAX_PREFIX_LENGTH
+        || !prefix.chars().all(|c| c.is_ascii_alphanumeric())
+    {
+        return Err(RpgError::InvalidKeyFormat(format!(
+            "prefix '{}' must be 1-{} ASCII letters or digits",
+            prefix, MAX_PREFIX_LENGTH
+        )));
+    }
+    Ok(())
+}
+
+/// 本体の文字数が範囲内か検証
+fn validate_body_length(body_length: usize) -> Result<()> {
+    if !(1..=MAX_BODY_LENGTH).contains(&body_length) {
+        return Err(RpgError::InvalidKeyFormat(format!(
+            "body length {} must be between 1 and {}",
+            body_length, MAX_BODY_LENGTH
+        )));
+    }
+    Ok(())
+}
+
+/// キーの形式
+#[derive(Debug, Clone, PartialEq)]
+pub struct KeyConfig {
+    /// プレフィックス（`_`は含めない）
+    pub prefix: String,
+    /// ランダムな本体の文字数
+    pub body_length: usize,
+    /// チェックサムの計算方法
+    pub checksum: KeyChecksum,
+}
+
+impl KeyConfig {
+    /// 設定の妥当性を検証
+    pub fn validate(&self) -> Result<()> {
+        validate_prefix(&self.prefix)?;
+        validate_body_length(self.body_length)
+    }
+}
+
+/// APIキーのジェネレータ
+pub struct KeyGenerator {
+    config: KeyConfig,
+}
+
+impl KeyGenerator {
+    pub fn new(config: KeyConfig) -> Result<Self> {
+        config.validate()?;
+        Ok(Self { config })
+    }
+
+    /// 生成されるキーのエントロピー（ビット）
+    ///
+    /// プレフィックスとチェックサムは固定・導出されるため、本体の文字数×log2(62)です。
+    pub fn entropy_bits(&self) -> f64 {
+        self.config.body_length as f64 * 62f64.log2()
+    }
+
+    pub fn generate(&self) -> String {
+        self.generate_with(&mut rand::thread_rng())
+    }
+
+    /// 指定された乱数生成器でキーを生成
+    pub fn generate_with<R: RngCore + CryptoRng>(&self, rng: &mut R) -> String {
+        let mut key = String::with_capacity(
+            self.config.prefix.len() + 1 + self.config.body_length + CHECKSUM_LENGTH,
+        );
+        key.push_str(&self.config.prefix);
+        key.push('_');
+        for _ in 0..self.config.body_length {
+            key.push(BASE62_ALPHABET[rng.gen_range(0..BASE62_ALPHABET.len())] as char);
+        }
+        let suffix = self.config.checksum.suffix(&key);
+        key.push_str(&suffix);
+        key
+    }
+}
+
+impl SecretGenerator for KeyGenerator {
+    fn entropy_bits(&self) -> f64 {
+        KeyGenerator::entropy_bits(self)
+    }
+
+    fn generate_with<R: RngCore + CryptoRng>(&self, rng: &mut R) -> String {
+        KeyGenerator::generate_with(self, rng)
+    }
+}
+
+/// 検証に成功したキーの内訳
+#[derive(Debug, Clone, PartialEq)]
+pub struct VerifiedKey {
+    pub prefix: String,
+    pub body_length: usize,
+}
+
+/// APIキーの検証条件
+///
+/// プレフィックスと本体の文字数は、指定されていれば一致することも確認します。
+#[derive(Debug, Clone, PartialEq)]
+pub struct KeyVerifier {
+    pub checksum: KeyChecksum,
+    pub prefix: Option<String>,
+    pub body_length: Option<usize>,
+}
+
+impl KeyVerifier {
+    /// キーの形式とチェックサムを検証
+    pub fn verify(&self, key: &str) -> Result<VerifiedKey> {
+        let invalid = |message: String| Err(RpgError::InvalidKey(message));
+
+        let Some((prefix, rest)) = key.split_once('_') else {
+            return invalid("missing '_' after the prefix".to_string());
+        };
+        if let Some(expected) = &self.prefix {
+            if prefix != expected {
+                return invalid(format!("prefix is '{}', expected '{}'", prefix, expected));
+            }
+        }
+        if validate_prefix(prefix).is_err() {
+            return invalid(format!("malformed prefix '{}'", prefix));
+        }
+        if let Some(c) = rest.chars().find(|c| !c.is_ascii_alphanumeric()) {
+            return invalid(format!("unexpected character '{}'", c));
+        }
+        if rest.len() <= CHECKSUM_LENGTH {
+            return invalid("too short to contain a body and a checksum".to_string());
+        }
+
+        let body_length = rest.len() - CHECKSUM_LENGTH;
+        if let Some(expected) = self.body_length {
+            if body_length != expected {
+                return invalid(format!(
+                    "body is {} characters, expected {}",
+                    body_length, expected
+                ));
+            }
+        }
+
+        let split = key.len() - CHECKSUM_LENGTH;
+        if self.checksum.suffix(&key[..split]) != key[split..] {
+            return invalid("checksum mismatch".to_string());
+        }
+
+        Ok(VerifiedKey {
+            prefix: prefix.to_string(),
+            body_length,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn config(prefix: &str, body_length: usize, checksum: KeyChecksum) -> KeyConfig {
+        KeyConfig {
+            prefix: prefix.to_string(),
+            body_length,
+            checksum,
+        }
+    }
+
+    fn verifier(checksum: KeyChecksum) -> KeyVerifier {
+        KeyVerifier {
+            checksum,
+            prefix: None,
+            body_length: None,
+        }
+    }
+
+    #[test]
+    fn test_checksum_check_values() {
+        assert_eq!(KeyChecksum::Crc32.compute(b"123456789"), 0xCBF4_3926);
+        assert_eq!(KeyChecksum::Crc32c.compute(b"123456789"), 0xE306_9283);
+        assert_eq!(KeyChecksum::Crc32.compute(b""), 0);
+    }
+
+    #[test]
+    fn test_checksum_suffix_is_base62() {
+        assert_eq!(KeyChecksum::Crc32.suffix(""), "000000");
+        // 0xCBF43926 = 3421780262 = [3, 45, 35, 27, 22, 14]（62進数）
+        assert_eq!(KeyChecksum::Crc32.suffix("123456789"), "3jZRME");
+    }
+
+    #[test]
+    fn test_generate_and_verify_round_trip() {
+        for checksum in [KeyChecksum::Crc32, KeyChecksum::Crc32c] {
+            let generator = KeyGenerator::new(config("acme", 30, checksum)).unwrap();
+            for _ in 0..20 {
+                let key = generator.generate();
+                assert!(key.starts_with("acme_"));
+                assert_eq!(key.len(), 5 + 30 + CHECKSUM_LENGTH);
+                assert_eq!(
+                    verifier(checksum).verify(&key),
+                    Ok(VerifiedKey {
+                        prefix: "acme".to_string(),
+                        body_length: 30,
+                    })
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn test_verify_detects_typos() {
+        let generator = KeyGenerator::new(config("acme", 30, KeyChecksum::Crc32)).unwrap();
+        let key = generator.generate();
+
+        // 本体の1文字を変えるとチェックサムが合わなくなる
+        let mut chars: Vec<char> = key.chars().collect();
+        chars[10] = if chars[10] == 'a' { 'b' } else { 'a' };
+        let typo: String = chars.into_iter().collect();
+        assert_eq!(
+            verifier(KeyChecksum::Crc32).verify(&typo),
+            Err(RpgError::InvalidKey("checksum mismatch".to_string()))
+        );
+
+        // チェックサムの計算方法が違う
+        assert!(verifier(KeyChecksum::Crc32c).verify(&key).is_err());
+
+        // 形式の誤り
+        for bad in ["acme", "acme_abc", "acme_abc-def123456", "_abcdefghij"] {
+            assert!(verifier(KeyChecksum::Crc32).verify(bad).is_err(), "{}", bad);
+        }
+    }
+
+    #[test]
+    fn test_verify_expected_prefix_and_length() {
+        let generator = KeyGenerator::new(config("live", 24, KeyChecksum::Crc32)).unwrap();
+        let key = generator.generate();
+
+        let expecting = |prefix: &str, body_length| KeyVerifier {
+            checksum: KeyChecksum::Crc32,
+            prefix: Some(prefix.to_string()),
+            body_length: Some(body_length),
+        };
+        assert!(expecting("live", 24).verify(&key).is_ok());
+        assert_eq!(
+            expecting("test", 24).verify(&key),
+            Err(RpgError::InvalidKey(
+                "prefix is 'live', expected 'test'".to_string()
+            ))
+        );
+        assert_eq!(
+            expecting("live", 30).verify(&key),
+            Err(RpgError::InvalidKey(
+                "body is 24 characters, expected 30".to_string()
+            ))
+        );
+    }
+
+    #[test]
+    fn test_key_entropy_and_invalid_formats() {
+        let generator = KeyGenerator::new(config("k", 30, KeyChecksum::Crc32)).unwrap();
+        assert!((generator.entropy_bits() - 30.0 * 62f64.log2()).abs() < 1e-9);
+
+        for bad in [
+            config("", 30, KeyChecksum::Crc32),
+            config("my_co", 30, KeyChecksum::Crc32),
+            config("acme", 0, KeyChecksum::Crc32),
+            config("acme", 300, KeyChecksum::Crc32),
+        ] {
+            assert!(matches!(
+                KeyGenerator::new(bad),
+                Err(RpgError::InvalidKeyFormat(_))
+            ));
+        }
+    }
+}
@@ -1,4 +1,6 @@
 use crate::config::PasswordConfig;
+use clap::ValueEnum;
+use std::fmt;
 
 /// 文字セット定義
 pub struct CharacterSets;
@@ -8,26 +10,293 @@ impl CharacterSets {
     pub const LOWERCASE: &'static str = "abcdefghijklmnopqrstuvwxyz";
     pub const DIGITS: &'static str = "0123456789";
     pub const SYMBOLS: &'static str = "!@#$%^&*()_+-=[]{}|;:,.<>?";
+    /// 小文字の母音
+    pub const VOWELS: &'static str = "aeiou";
+    /// 小文字の子音
+    pub const CONSONANTS: &'static str = "bcdfghjklmnpqrstvwxyz";
+    /// 見間違えやすい文字（`--no-ambiguous`で除外）
+    pub const AMBIGUOUS: &'static str = "0Oo1lI|5S`'\"";
+    /// ラテン文字や互いと見分けにくいUnicode文字（`--no-ambiguous`で除外）
+    pub const AMBIGUOUS_UNICODE: &'static str =
+        "ΑΒΕΖΗΙΚΜΝΟΡΤΥΧικνορυАВЕЁЗКМНОРСТХаеёорсухへべぺヘベペ";
 }
 
-/// 設定に基づいて使用する文字セットを構築
-pub fn build_charset(config: &PasswordConfig) -> String {
-    let mut charset = String::new();
+/// 文字種
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CharClass {
+    Uppercase,
+    Lowercase,
+    Digit,
+    Symbol,
+}
+
+impl CharClass {
+    /// すべての文字種（文字セット構築時の順序）
+    pub const ALL: [CharClass; 4] = [
+        CharClass::Uppercase,
+        CharClass::Lowercase,
+        CharClass::Digit,
+        CharClass::Symbol,
+    ];
+
+    /// 文字種に対応する文字セット
+    pub fn chars(self) -> &'static str {
+        match self {
+            CharClass::Uppercase => CharacterSets::UPPERCASE,
+            CharClass::Lowercase => CharacterSets::LOWERCASE,
+            CharClass::Digit => CharacterSets::DIGITS,
+            CharClass::Symbol => CharacterSets::SYMBOLS,
+        }
+    }
 
-    if config.use_uppercase {
-        charset.push_str(CharacterSets::UPPERCASE);
+    /// 文字がどの文字種に分類されるか
+    ///
+    /// 英字はUnicodeの大文字・小文字で分類し、大文字・小文字の区別がない文字（かななど）は
+    /// 小文字として扱います。数字はASCIIの0-9のみで、それ以外はすべて記号です。
+    pub fn of(c: char) -> CharClass {
+        if c.is_uppercase() {
+            CharClass::Uppercase
+        } else if c.is_lowercase() {
+            CharClass::Lowercase
+        } else if c.is_ascii_digit() {
+            CharClass::Digit
+        } else if c.is_alphabetic() {
+            CharClass::Lowercase
+        } else {
+            CharClass::Symbol
+        }
     }
-    if config.use_lowercase {
-        charset.push_str(CharacterSets::LOWERCASE);
+
+    /// 文字種の名前を解析（`letters`や`alnum`のような文字種のまとまりも受け付ける）
+    pub fn parse_group(name: &str) -> Option<Vec<CharClass>> {
+        use CharClass::*;
+        let classes = match name.to_ascii_lowercase().as_str() {
+            "upper" | "uppercase" => vec![Uppercase],
+            "lower" | "lowercase" => vec![Lowercase],
+            "digit" | "digits" => vec![Digit],
+            "symbol" | "symbols" | "special" => vec![Symbol],
+            "letter" | "letters" | "alpha" => vec![Uppercase, Lowercase],
+            "alnum" => vec![Uppercase, Lowercase, Digit],
+            "any" => CharClass::ALL.to_vec(),
+            _ => return None,
+        };
+        Some(classes)
     }
-    if config.use_digits {
-        charset.push_str(CharacterSets::DIGITS);
+
+    /// 設定でこの文字種が有効かどうか
+    pub fn is_enabled(self, config: &PasswordConfig) -> bool {
+        match self {
+            CharClass::Uppercase => config.use_uppercase,
+            CharClass::Lowercase => config.use_lowercase,
+            CharClass::Digit => config.use_digits,
+            CharClass::Symbol => config.use_symbols,
+        }
+    }
+}
+
+impl fmt::Display for CharClass {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            CharClass::Uppercase => "uppercase",
+            CharClass::Lowercase => "lowercase",
+            CharClass::Digit => "digits",
+            CharClass::Symbol => "symbols",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+/// 英字に追加できるUnicodeの文字セット
+///
+/// 各文字は1つのコードポイントで表される合成済みの文字なので、
+/// パスワード長（文字数）は書記素の数と一致します。
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum UnicodeSet {
+    /// Latin-1補助のアクセント付き英字（À-þ、ß、ÿ）
+    Latin1,
+    /// ギリシャ文字（Α-Ω、α-ω）
+    Greek,
+    /// キリル文字（А-Я、а-я、Ё、ё）
+    Cyrillic,
+    /// ひらがなとカタカナ
+    Kana,
+}
+
+impl UnicodeSet {
+    /// 文字セットに含まれる文字
+    pub fn chars(self) -> Vec<char> {
+        match self {
+            UnicodeSet::Latin1 => ('\u{C0}'..='\u{FF}')
+                .filter(|&c| c != '×' && c != '÷')
+                .collect(),
+            UnicodeSet::Greek => ('Α'..='Ω')
+                .chain('α'..='ω')
+                .filter(|c| c.is_alphabetic())
+                .collect(),
+            UnicodeSet::Cyrillic => ('А'..='я').chain(['Ё', 'ё']).collect(),
+            UnicodeSet::Kana => ('ぁ'..='ゖ').chain('ァ'..='ヺ').collect(),
+        }
+    }
+}
+
+/// 生成したパスワードを埋め込む文脈
+///
+/// 各文脈でエスケープが必要な、または誤解釈されやすい記号を文字セットから除きます。
+/// 英数字はどの文脈でも安全です。
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum SafeContext {
+    /// シェルの引数（引用符なしで渡せる）
+    Shell,
+    /// URLのユーザー情報やクエリ（パーセントエンコード不要）
+    Url,
+    /// XML/HTMLのテキストや属性値
+    Xml,
+    /// JSONの文字列
+    Json,
+    /// SQLの文字列リテラル
+    Sql,
+    /// YAMLの引用符なしの値
+    Yaml,
+    /// `.env`ファイルの値
+    Dotenv,
+}
+
+impl SafeContext {
+    /// URLでエスケープ不要な記号（RFC 3986のunreserved）
+    const URL_UNRESERVED: &'static str = "-._~";
+
+    /// この文脈で避ける記号（URLは`URL_UNRESERVED`以外のすべて）
+    fn unsafe_symbols(self) -> &'static str {
+        match self {
+            SafeContext::Shell => "!\"#$&'()*;<>?[\\]^`{|}~ ",
+            SafeContext::Url => "",
+            SafeContext::Xml => "<>&'\"",
+            SafeContext::Json => "\"\\",
+            SafeContext::Sql => "'\"`\\;",
+            SafeContext::Yaml => ":#{}[],&*!|>'\"%@`?\\",
+            SafeContext::Dotenv => "#$\"'`\\ ",
+        }
+    }
+
+    /// 文字がこの文脈でそのまま使えるか
+    pub fn is_safe(self, c: char) -> bool {
+        if c.is_ascii_alphanumeric() {
+            return true;
+        }
+        match self {
+            SafeContext::Url => Self::URL_UNRESERVED.contains(c),
+            _ => !c.is_control() && !self.unsafe_symbols().contains(c),
+        }
+    }
+}
+
+/// 文字種のリスト（CLI引数の値として1つの値に`,`区切りで書く）
+pub type ClassList = Vec<CharClass>;
+
+/// `letters,digits`のような`,`区切りの文字種のリストを解析（重複は除く）
+pub fn parse_class_list(value: &str) -> Result<ClassList, String> {
+    let mut classes = Vec::new();
+    for name in value.split(',').map(str::trim) {
+        let group = CharClass::parse_group(name).ok_or_else(|| {
+            format!(
+                "unknown character class '{}' (expected upper, lower, digit, symbol, letter, alnum or any)",
+                name
+            )
+        })?;
+        for class in group {
+            if !classes.contains(&class) {
+                classes.push(class);
+            }
+        }
     }
-    if config.use_symbols {
-        charset.push_str(CharacterSets::SYMBOLS);
+    Ok(classes)
+}
+
+/// 設定で有効な文字種を列挙
+pub fn enabled_classes(config: &PasswordConfig) -> Vec<CharClass> {
+    CharClass::ALL
+        .into_iter()
+        .filter(|class| class.is_enabled(config))
+        .collect()
+}
+
+/// 文字種ごとに分類された文字セット
+#[derive(Debug, Clone, PartialEq)]
+pub struct ClassCharset {
+    pub class: CharClass,
+    pub chars: Vec<char>,
+    /// 有効化された文字種として最低1文字含める必要があるか
+    pub required: bool,
+}
+
+/// 設定に基づいて文字種ごとの文字セットを構築
+///
+/// 返り値は常に`CharClass::ALL`の順で4要素（文字が無い文字種は空）。
+/// 各文字は重複なく、分類上の文字種のいずれか1つにだけ属します。
+///
+/// - `custom_charset`が指定されていれば文字種の有効/無効を無視してそれを使用
+/// - `custom_symbols`が指定されていれば記号の文字セットを置き換え
+/// - `unicode`の文字セットのうち、有効な文字種の文字を追加
+/// - `include_chars`を追加し、`exclude_chars`と（`avoid_ambiguous`時は）見間違えやすい文字を除外
+/// - `safe_for`の文脈で安全でない文字を除外
+pub fn build_class_charsets(config: &PasswordConfig) -> Vec<ClassCharset> {
+    let base: String = match &config.custom_charset {
+        Some(charset) => charset.clone(),
+        None => {
+            let mut base: String = enabled_classes(config)
+                .into_iter()
+                .map(|class| match (class, &config.custom_symbols) {
+                    (CharClass::Symbol, Some(symbols)) => symbols.as_str(),
+                    _ => class.chars(),
+                })
+                .collect();
+            base.extend(
+                config
+                    .unicode
+                    .iter()
+                    .flat_map(|set| set.chars())
+                    .filter(|&c| CharClass::of(c).is_enabled(config)),
+            );
+            base
+        }
+    };
+
+    let mut charsets: Vec<ClassCharset> = CharClass::ALL
+        .into_iter()
+        .map(|class| ClassCharset {
+            class,
+            chars: Vec::new(),
+            required: config.custom_charset.is_none() && class.is_enabled(config),
+        })
+        .collect();
+
+    for c in base.chars().chain(config.include_chars.chars()) {
+        if config.exclude_chars.contains(c)
+            || (config.avoid_ambiguous
+                && (CharacterSets::AMBIGUOUS.contains(c)
+                    || CharacterSets::AMBIGUOUS_UNICODE.contains(c)))
+            || config.safe_for.iter().any(|context| !context.is_safe(c))
+        {
+            continue;
+        }
+        let index = CharClass::ALL
+            .iter()
+            .position(|&class| class == CharClass::of(c))
+            .unwrap();
+        if !charsets[index].chars.contains(&c) {
+            charsets[index].chars.push(c);
+        }
     }
 
-    charset
+    charsets
+}
+
+/// 設定に基づいて使用する文字セットを構築（重複なし）
+pub fn build_charset(config: &PasswordConfig) -> String {
+    build_class_charsets(config)
+        .into_iter()
+        .flat_map(|charset| charset.chars)
+        .collect()
 }
 
 #[cfg(test)]
@@ -42,6 +311,7 @@ mod tests {
             use_lowercase: true,
             use_digits: true,
             use_symbols: true,
+            ..Default::default()
         };
 
         let charset = build_charset(&config);
@@ -59,6 +329,7 @@ mod tests {
             use_lowercase: true,
             use_digits: false,
             use_symbols: false,
+            ..Default::default()
         };
 
         let charset = build_charset(&config);
@@ -73,6 +344,7 @@ mod tests {
             use_lowercase: false,
             use_digits: true,
             use_symbols: false,
+            ..Default::default()
         };
 
         let charset = build_charset(&config);
@@ -90,9 +362,212 @@ mod tests {
             use_lowercase: false,
             use_digits: false,
             use_symbols: false,
+            ..Default::default()
         };
 
         let charset = build_charset(&config);
         assert!(charset.is_empty());
     }
+
+    #[test]
+    fn test_build_charset_avoid_ambiguous() {
+        let config = PasswordConfig {
+            avoid_ambiguous: true,
+            ..Default::default()
+        };
+
+        let charset = build_charset(&config);
+        for c in CharacterSets::AMBIGUOUS.chars() {
+            assert!(!charset.contains(c), "ambiguous char {:?} remained", c);
+        }
+        assert!(charset.contains('A'));
+        assert!(charset.contains('2'));
+        assert!(charset.contains('!'));
+        // 26+26+10+26 から 0 O o 1 l I | 5 S を除いた数
+        assert_eq!(charset.chars().count(), 88 - 9);
+    }
+
+    #[test]
+    fn test_build_charset_custom_charset() {
+        let config = PasswordConfig {
+            use_uppercase: false,
+            custom_charset: Some("abcabc123".to_string()),
+            ..Default::default()
+        };
+
+        assert_eq!(build_charset(&config), "abc123");
+        let charsets = build_class_charsets(&config);
+        assert!(charsets.iter().all(|charset| !charset.required));
+    }
+
+    #[test]
+    fn test_build_charset_include_and_exclude() {
+        let config = PasswordConfig {
+            use_symbols: false,
+            include_chars: "~~a".to_string(),
+            exclude_chars: "xyz".to_string(),
+            ..Default::default()
+        };
+
+        let charset = build_charset(&config);
+        assert_eq!(charset.matches('~').count(), 1);
+        assert_eq!(charset.matches('a').count(), 1);
+        assert!(!charset.contains('x'));
+        assert!(!charset.contains('!'));
+
+        // 追加された記号は無効な文字種に分類され、必須にはならない
+        let symbols = &build_class_charsets(&config)[3];
+        assert_eq!(symbols.chars, vec!['~']);
+        assert!(!symbols.required);
+    }
+
+    #[test]
+    fn test_build_charset_custom_symbols() {
+        let config = PasswordConfig {
+            custom_symbols: Some("-_-.".to_string()),
+            ..Default::default()
+        };
+
+        let symbols = &build_class_charsets(&config)[3];
+        assert_eq!(symbols.chars, vec!['-', '_', '.']);
+        assert!(symbols.required);
+    }
+
+    #[test]
+    fn test_char_class_of() {
+        assert_eq!(CharClass::of('Q'), CharClass::Uppercase);
+        assert_eq!(CharClass::of('q'), CharClass::Lowercase);
+        assert_eq!(CharClass::of('7'), CharClass::Digit);
+        assert_eq!(CharClass::of('~'), CharClass::Symbol);
+        assert_eq!(CharClass::of('Ж'), CharClass::Uppercase);
+        assert_eq!(CharClass::of('ß'), CharClass::Lowercase);
+        assert_eq!(CharClass::of('か'), CharClass::Lowercase);
+        assert_eq!(CharClass::of('٣'), CharClass::Symbol);
+        assert_eq!(CharClass::of('€'), CharClass::Symbol);
+    }
+
+    #[test]
+    fn test_unicode_sets() {
+        let latin1 = UnicodeSet::Latin1.chars();
+        assert_eq!(latin1.len(), 62);
+        assert!(latin1.iter().all(|c| c.is_alphabetic()));
+
+        let greek = UnicodeSet::Greek.chars();
+        assert_eq!(greek.len(), 24 + 25);
+        assert!(!greek.contains(&'\u{3A2}'));
+
+        let cyrillic = UnicodeSet::Cyrillic.chars();
+        assert_eq!(cyrillic.len(), 66);
+
+        let kana = UnicodeSet::Kana.chars();
+        assert!(kana.contains(&'あ') && kana.contains(&'ン') && kana.contains(&'ゔ'));
+        assert!(!kana.contains(&'・') && !kana.contains(&'ー'));
+    }
+
+    #[test]
+    fn test_build_charset_unicode() {
+        let config = PasswordConfig {
+            use_uppercase: false,
+            unicode: vec![UnicodeSet::Greek, UnicodeSet::Kana],
+            ..Default::default()
+        };
+        let charsets = build_class_charsets(&config);
+
+        // 無効な大文字のギリシャ文字は加わらず、かなは小文字として加わる
+        assert!(charsets[0].chars.is_empty());
+        assert!(charsets[1].chars.contains(&'λ'));
+        assert!(charsets[1].chars.contains(&'カ'));
+        assert_eq!(
+            charsets[1].chars.len(),
+            26 + 25 + UnicodeSet::Kana.chars().len()
+        );
+
+        let config = PasswordConfig {
+            avoid_ambiguous: true,
+            unicode: vec![UnicodeSet::Cyrillic],
+            ..Default::default()
+        };
+        let charset = build_charset(&config);
+        assert!(charset.contains('Ж'));
+        assert!(!charset.contains('А') && !charset.contains('о'));
+    }
+
+    #[test]
+    fn test_char_class_parse_group() {
+        assert_eq!(
+            CharClass::parse_group("Digits"),
+            Some(vec![CharClass::Digit])
+        );
+        assert_eq!(
+            CharClass::parse_group("letters"),
+            Some(vec![CharClass::Uppercase, CharClass::Lowercase])
+        );
+        assert_eq!(CharClass::parse_group("alnum").unwrap().len(), 3);
+        assert_eq!(CharClass::parse_group("emoji"), None);
+    }
+
+    #[test]
+    fn test_build_charset_safe_for() {
+        let symbols = |contexts: Vec<SafeContext>| -> String {
+            let config = PasswordConfig {
+                safe_for: contexts,
+                ..Default::default()
+            };
+            build_class_charsets(&config)[3].chars.iter().collect()
+        };
+
+        assert_eq!(symbols(vec![SafeContext::Url]), "_-.");
+        assert_eq!(symbols(vec![SafeContext::Shell]), "@%_+-=:,.");
+        assert!(!symbols(vec![SafeContext::Xml]).contains(['<', '>', '&']));
+        assert_eq!(
+            symbols(vec![SafeContext::Yaml, SafeContext::Dotenv]),
+            "^()_+-=;.<"
+        );
+        assert_eq!(symbols(vec![SafeContext::Json]), CharacterSets::SYMBOLS);
+
+        // 英数字は常に安全
+        let config = PasswordConfig {
+            safe_for: vec![SafeContext::Url],
+            ..Default::default()
+        };
+        assert_eq!(build_charset(&config).len(), 26 + 26 + 10 + 3);
+    }
+
+    #[test]
+    fn test_safe_context_rejects_added_quotes() {
+        for context in SafeContext::value_variants() {
+            assert!(context.is_safe('a') && context.is_safe('7'));
+            assert!(!context.is_safe('\n'));
+        }
+        assert!(!SafeContext::Json.is_safe('"'));
+        assert!(!SafeContext::Sql.is_safe('\''));
+        assert!(!SafeContext::Shell.is_safe(' '));
+    }
+
+    #[test]
+    fn test_parse_class_list() {
+        assert_eq!(
+            parse_class_list("digit, letter"),
+            Ok(vec![
+                CharClass::Digit,
+                CharClass::Uppercase,
+                CharClass::Lowercase
+            ])
+        );
+        assert_eq!(parse_class_list("alnum,digits").unwrap().len(), 3);
+        assert!(parse_class_list("letter,emoji").is_err());
+    }
+
+    #[test]
+    fn test_enabled_classes_order() {
+        let config = PasswordConfig {
+            use_lowercase: false,
+            ..Default::default()
+        };
+
+        assert_eq!(
+            enabled_classes(&config),
+            vec![CharClass::Uppercase, CharClass::Digit, CharClass::Symbol]
+        );
+    }
 }
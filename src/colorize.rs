@@ -7,9 +7,25 @@ use colored::*;
 /// パスフレーズの単語に使用する色の順序
 const PASSPHRASE_COLORS: [Color; 4] = [Color::Cyan, Color::Magenta, Color::Yellow, Color::Green];
 
-/// 文字種ごとに色分けしてパスワードを表示
+/// 文字のUnicodeの分類に対応する色
 ///
-/// 大文字=青、小文字=緑、数字=黄、記号=赤
+/// 大文字=青、小文字=緑、大文字・小文字の区別がない文字（かななど）=シアン、
+/// 数字=黄、それ以外（記号・空白など）=赤
+fn char_color(c: char) -> Color {
+    if c.is_uppercase() {
+        Color::Blue
+    } else if c.is_lowercase() {
+        Color::Green
+    } else if c.is_numeric() {
+        Color::Yellow
+    } else if c.is_alphabetic() {
+        Color::Cyan
+    } else {
+        Color::Red
+    }
+}
+
+/// 文字種ごとに色分けしてパスワードを表示（色は`char_color`を参照）
 pub fn colorize_password(password: &str, enable_color: bool) -> String {
     if !enable_color {
         return password.to_string();
@@ -17,11 +33,36 @@ pub fn colorize_password(password: &str, enable_color: bool) -> String {
 
     password
         .chars()
-        .map(|c| match c {
-            'A'..='Z' => c.to_string().blue().to_string(),
-            'a'..='z' => c.to_string().green().to_string(),
-            '0'..='9' => c.to_string().yellow().to_string(),
-            _ => c.to_string().red().to_string(), // 記号
+        .map(|c| c.to_string().color(char_color(c)).to_string())
+        .collect()
+}
+
+/// 弱い部分を強調してパスワードを表示
+///
+/// `weak_ranges`（文字単位の範囲）に含まれる文字は赤背景で、
+/// それ以外は`colorize_password`と同じ文字種ごとの色で表示します。
+pub fn colorize_weak_segments(
+    password: &str,
+    weak_ranges: &[(usize, usize)],
+    enable_color: bool,
+) -> String {
+    if !enable_color {
+        return password.to_string();
+    }
+
+    password
+        .chars()
+        .enumerate()
+        .map(|(i, c)| {
+            let s = c.to_string();
+            if weak_ranges
+                .iter()
+                .any(|&(start, end)| (start..end).contains(&i))
+            {
+                s.white().on_red().to_string()
+            } else {
+                colorize_password(&s, true)
+            }
         })
         .collect()
 }
@@ -86,6 +127,38 @@ mod tests {
         assert!(result.len() >= password.len());
     }
 
+    #[test]
+    fn test_char_color_by_unicode_category() {
+        assert_eq!(char_color('A'), Color::Blue);
+        assert_eq!(char_color('Ж'), Color::Blue);
+        assert_eq!(char_color('ß'), Color::Green);
+        assert_eq!(char_color('λ'), Color::Green);
+        assert_eq!(char_color('か'), Color::Cyan);
+        assert_eq!(char_color('7'), Color::Yellow);
+        assert_eq!(char_color('€'), Color::Red);
+    }
+
+    #[test]
+    fn test_colorize_password_unicode() {
+        let password = "Żółw-ねこ";
+        assert_eq!(colorize_password(password, false), password);
+        let result = colorize_password(password, true);
+        assert!(result.contains('Ż') && result.contains('ね'));
+    }
+
+    #[test]
+    fn test_colorize_weak_segments_disabled() {
+        let password = "abc123XYZ";
+        assert_eq!(colorize_weak_segments(password, &[(0, 3)], false), password);
+    }
+
+    #[test]
+    fn test_colorize_weak_segments_enabled() {
+        let result = colorize_weak_segments("abc123", &[(3, 6)], true);
+        assert!(result.contains('a'));
+        assert!(result.contains('3'));
+    }
+
     #[test]
     fn test_colorize_passphrase_enabled() {
         let passphrase = "correct-horse";
@@ -1,10 +1,21 @@
+use crate::apikey::KeyChecksum;
+use crate::charset::{
+    build_class_charsets, parse_class_list, CharClass, ClassCharset, ClassList, SafeContext,
+    UnicodeSet,
+};
 use crate::error::{Result, RpgError};
-use clap::Parser;
+use crate::generator::{log_required_sets_space_size, log_space_size, Candidates, PasswordSpace};
+use crate::token::TokenEncoding;
+use clap::{ArgGroup, Args, Parser, Subcommand};
+use std::path::PathBuf;
+
+/// パスワードの最大長（メモリ安全性のため）
+pub const MAX_LENGTH: usize = 1024;
 
 /// パスワード生成の設定
 #[derive(Debug, Clone, PartialEq)]
 pub struct PasswordConfig {
-    /// パスワードの長さ
+    /// パスワードの長さ（文字数）
     pub length: usize,
     /// 大文字を含む
     pub use_uppercase: bool,
@@ -14,6 +25,50 @@ pub struct PasswordConfig {
     pub use_digits: bool,
     /// 記号を含む
     pub use_symbols: bool,
+    /// 大文字の最小文字数
+    pub min_uppercase: usize,
+    /// 小文字の最小文字数
+    pub min_lowercase: usize,
+    /// 数字の最小文字数
+    pub min_digits: usize,
+    /// 記号の最小文字数
+    pub min_symbols: usize,
+    /// 大文字の最大文字数（Noneは無制限）
+    pub max_uppercase: Option<usize>,
+    /// 小文字の最大文字数（Noneは無制限）
+    pub max_lowercase: Option<usize>,
+    /// 数字の最大文字数（Noneは無制限）
+    pub max_digits: Option<usize>,
+    /// 記号の最大文字数（Noneは無制限）
+    pub max_symbols: Option<usize>,
+    /// 見間違えやすい文字（0/O、1/l/Iなど）を除外
+    pub avoid_ambiguous: bool,
+    /// 文字種の代わりに使用する文字セット
+    pub custom_charset: Option<String>,
+    /// 記号の文字セットの置き換え
+    pub custom_symbols: Option<String>,
+    /// 追加で使用する文字
+    pub include_chars: String,
+    /// 除外する文字
+    pub exclude_chars: String,
+    /// 先頭の文字に使える文字種（Noneは制限なし）
+    pub start_with: Option<Vec<CharClass>>,
+    /// 末尾の文字に使える文字種（Noneは制限なし）
+    pub end_with: Option<Vec<CharClass>>,
+    /// それぞれから最低1文字含める文字の集合
+    pub required_sets: Vec<String>,
+    /// 同じ文字が連続してよい最大数（1なら同じ文字が隣り合わない）
+    pub max_consecutive: Option<usize>,
+    /// 同じ文字が現れてよい最大回数
+    pub max_occurrences: Option<usize>,
+    /// abcや321のような3文字以上の連続を禁止
+    pub no_sequences: bool,
+    /// 埋め込む文脈（その文脈で安全でない文字を除外）
+    pub safe_for: Vec<SafeContext>,
+    /// 英字に追加するUnicodeの文字セット
+    pub unicode: Vec<UnicodeSet>,
+    /// UTF-8でのバイト数の上限
+    pub max_bytes: Option<usize>,
 }
 
 impl Default for PasswordConfig {
@@ -24,6 +79,28 @@ impl Default for PasswordConfig {
             use_lowercase: true,
             use_digits: true,
             use_symbols: true,
+            min_uppercase: 0,
+            min_lowercase: 0,
+            min_digits: 0,
+            min_symbols: 0,
+            max_uppercase: None,
+            max_lowercase: None,
+            max_digits: None,
+            max_symbols: None,
+            avoid_ambiguous: false,
+            custom_charset: None,
+            custom_symbols: None,
+            include_chars: String::new(),
+            exclude_chars: String::new(),
+            start_with: None,
+            end_with: None,
+            required_sets: Vec::new(),
+            max_consecutive: None,
+            max_occurrences: None,
+            no_sequences: false,
+            safe_for: Vec::new(),
+            unicode: Vec::new(),
+            max_bytes: None,
         }
     }
 }
@@ -31,22 +108,353 @@ impl Default for PasswordConfig {
 impl PasswordConfig {
     /// 設定の妥当性を検証
     pub fn validate(&self) -> Result<()> {
+        self.validated_candidates().map(|_| ())
+    }
+
+    /// 設定を検証し、パスワードの候補の選び方を返す
+    ///
+    /// 条件を満たす割合の推定（標本の抽出）は、検証を通った設定で1回だけ行います。
+    pub(crate) fn validated_candidates(&self) -> Result<Candidates> {
         // 長さのチェック
         if self.length == 0 {
             return Err(RpgError::InvalidLength(self.length));
         }
 
         // 最大長のチェック（メモリ安全性）
-        const MAX_LENGTH: usize = 1024;
         if self.length > MAX_LENGTH {
             return Err(RpgError::LengthTooLarge(self.length));
         }
 
-        // 少なくとも1つの文字種が有効かチェック
-        if !self.use_uppercase && !self.use_lowercase && !self.use_digits && !self.use_symbols {
+        // 使用できる文字が少なくとも1つあるかチェック
+        let charsets = build_class_charsets(self);
+        if charsets.iter().all(|charset| charset.chars.is_empty()) {
             return Err(RpgError::NoCharacterSetsEnabled);
         }
 
+        // 有効な文字種が除外によって空になっていないかチェック
+        if let Some(charset) = charsets
+            .iter()
+            .find(|charset| charset.required && charset.chars.is_empty())
+        {
+            return Err(RpgError::EmptyCharacterSet(charset.class));
+        }
+
+        // 有効な文字種をそれぞれ1文字以上含められる長さかチェック
+        let required = charsets.iter().filter(|charset| charset.required).count();
+        if self.length < required {
+            return Err(RpgError::LengthTooShortForClasses {
+                length: self.length,
+                required,
+            });
+        }
+
+        self.validate_class_counts(&charsets)?;
+
+        self.validate_position_rules()?;
+
+        self.validate_required_sets()?;
+
+        self.validate_byte_limit(&charsets)?;
+
+        self.validate_run_rules(&charsets)?;
+
+        Candidates::from_config(self).ok_or_else(|| self.rejection_error())
+    }
+
+    /// 候補が1つも条件を満たさない場合に、原因となった制約のエラー
+    fn rejection_error(&self) -> RpgError {
+        // 繰り返し・連続文字の制約を外しても満たせなければ、バイト数の上限が原因
+        if let Some(max_bytes) = self.max_bytes {
+            let bytes_only = Self {
+                max_consecutive: None,
+                max_occurrences: None,
+                no_sequences: false,
+                ..self.clone()
+            };
+            if !self.has_run_rules() || Candidates::from_config(&bytes_only).is_none() {
+                return RpgError::ByteLimitTooSmall {
+                    length: self.length,
+                    max_bytes,
+                };
+            }
+        }
+        RpgError::RunRulesTooRestrictive
+    }
+
+    /// 繰り返し・連続文字の制約があるか
+    pub fn has_run_rules(&self) -> bool {
+        self.max_consecutive.is_some() || self.max_occurrences.is_some() || self.no_sequences
+    }
+
+    /// 候補を引き直して満たす制約（繰り返し・連続文字、バイト数の上限）があるか
+    pub(crate) fn has_rejection_rules(&self) -> bool {
+        self.has_run_rules() || self.max_bytes.is_some()
+    }
+
+    /// 生成されるパスワードのエントロピー（ビット）
+    ///
+    /// `PasswordGenerator`は制約を満たすパスワード全体から一様に選ぶため、
+    /// その総数の対数になります（文字セットの大きさ・長さ・最小/最大文字数を反映）。
+    pub fn entropy_bits(&self) -> f64 {
+        log_space_size(self).map_or(0.0, |log_size| log_size / std::f64::consts::LN_2)
+    }
+
+    /// 目標エントロピーに届く最小のパスワード長を設定した設定を返す
+    ///
+    /// 長さ以外の設定はそのまま使います。`MAX_LENGTH`でも届かない場合は
+    /// `RpgError::EntropyUnreachable`を返します。
+    pub fn with_target_entropy(self, target_bits: f64) -> Result<Self> {
+        if !target_bits.is_finite() || target_bits < 0.0 {
+            return Err(RpgError::InvalidEntropyTarget(target_bits));
+        }
+
+        // 最大文字数・最大出現回数の指定があれば、それより長いパスワードは作れない
+        let mut upper = build_class_charsets(&self)
+            .iter()
+            .filter(|charset| !charset.chars.is_empty())
+            .map(|charset| {
+                let occurrences = self
+                    .max_occurrences
+                    .map_or(MAX_LENGTH, |max| charset.chars.len().saturating_mul(max));
+                self.max_count(charset.class)
+                    .unwrap_or(MAX_LENGTH)
+                    .min(occurrences)
+            })
+            .fold(0, usize::saturating_add)
+            .clamp(1, MAX_LENGTH);
+
+        let with_length = |length: usize| Self {
+            length,
+            ..self.clone()
+        };
+
+        // 繰り返し・連続文字の制約やバイト数の上限は長いほど厳しくなるため、
+        // 満たせる最長の長さまでに絞る
+        if self.has_rejection_rules() {
+            let satisfiable = |length: usize| {
+                !matches!(
+                    with_length(length).validate(),
+                    Err(RpgError::RunRulesTooRestrictive | RpgError::ByteLimitTooSmall { .. })
+                )
+            };
+            let (mut low, mut high) = (1, upper);
+            while low < high {
+                let mid = low + (high - low).div_ceil(2);
+                if satisfiable(mid) {
+                    low = mid;
+                } else {
+                    high = mid - 1;
+                }
+            }
+            upper = low;
+        }
+        let reaches = |config: &Self| {
+            config
+                .validated_candidates()
+                .is_ok_and(|candidates| candidates.entropy_bits() >= target_bits)
+        };
+
+        let longest = with_length(upper);
+        longest.validate()?;
+        if !reaches(&longest) {
+            return Err(RpgError::EntropyUnreachable {
+                target: target_bits,
+                max: longest.entropy_bits(),
+            });
+        }
+
+        // 有効な長さの範囲ではエントロピーは長さに対して単調増加
+        let (mut low, mut high) = (1, upper);
+        while low < high {
+            let mid = low + (high - low) / 2;
+            if reaches(&with_length(mid)) {
+                high = mid;
+            } else {
+                low = mid + 1;
+            }
+        }
+
+        Ok(with_length(low))
+    }
+
+    /// 指定された文字種の最小文字数
+    pub fn min_count(&self, class: CharClass) -> usize {
+        match class {
+            CharClass::Uppercase => self.min_uppercase,
+            CharClass::Lowercase => self.min_lowercase,
+            CharClass::Digit => self.min_digits,
+            CharClass::Symbol => self.min_symbols,
+        }
+    }
+
+    /// 指定された文字種の最大文字数（Noneは無制限）
+    pub fn max_count(&self, class: CharClass) -> Option<usize> {
+        match class {
+            CharClass::Uppercase => self.max_uppercase,
+            CharClass::Lowercase => self.max_lowercase,
+            CharClass::Digit => self.max_digits,
+            CharClass::Symbol => self.max_symbols,
+        }
+    }
+
+    /// 文字種の実際の文字数範囲
+    ///
+    /// 有効な文字種は最低1文字、上限はパスワード長。文字が無い文字種は0文字。
+    pub fn count_range(&self, charset: &ClassCharset) -> (usize, usize) {
+        if charset.chars.is_empty() {
+            return (0, 0);
+        }
+        let min = self.min_count(charset.class).max(charset.required as usize);
+        let max = self
+            .max_count(charset.class)
+            .unwrap_or(self.length)
+            .min(self.length);
+        (min, max)
+    }
+
+    /// 先頭・末尾の文字種の指定が他の制約と両立するかを検証
+    fn validate_position_rules(&self) -> Result<()> {
+        if self.start_with.is_none() && self.end_with.is_none() {
+            return Ok(());
+        }
+
+        // どちらの指定が原因かを示すため、片方ずつ確認してから両方を確認する
+        let start_only = Self {
+            end_with: None,
+            ..self.clone()
+        };
+        if !PasswordSpace::from_config(&start_only).is_feasible() {
+            return Err(RpgError::UnsatisfiablePositionRule("first".to_string()));
+        }
+        let end_only = Self {
+            start_with: None,
+            ..self.clone()
+        };
+        if !PasswordSpace::from_config(&end_only).is_feasible() {
+            return Err(RpgError::UnsatisfiablePositionRule("last".to_string()));
+        }
+        if !PasswordSpace::from_config(self).is_feasible() {
+            return Err(RpgError::UnsatisfiablePositionRule(
+                "first and last".to_string(),
+            ));
+        }
+
+        Ok(())
+    }
+
+    /// 必須の文字集合がそれぞれ、またすべて同時に満たせるかを検証
+    fn validate_required_sets(&self) -> Result<()> {
+        if self.required_sets.is_empty() {
+            return Ok(());
+        }
+
+        for set in &self.required_sets {
+            let single = Self {
+                required_sets: vec![set.clone()],
+                ..self.clone()
+            };
+            if log_required_sets_space_size(&single).is_none() {
+                return Err(RpgError::UnsatisfiableRequiredSet(set.clone()));
+            }
+        }
+        if log_required_sets_space_size(self).is_none() {
+            return Err(RpgError::UnsatisfiableRequiredSet(
+                self.required_sets.join(" "),
+            ));
+        }
+
+        Ok(())
+    }
+
+    /// UTF-8でのバイト数の上限が、最も短い文字だけを使っても足りないかを検証
+    ///
+    /// 文字種ごとの文字数などとの組み合わせで満たせない場合は、
+    /// 候補を数える段階（`rejection_error`）で検出します。
+    fn validate_byte_limit(&self, charsets: &[ClassCharset]) -> Result<()> {
+        let Some(max_bytes) = self.max_bytes else {
+            return Ok(());
+        };
+        let shortest = charsets
+            .iter()
+            .flat_map(|charset| &charset.chars)
+            .map(|c| c.len_utf8())
+            .min()
+            .unwrap_or(1);
+        if max_bytes < self.length.saturating_mul(shortest) {
+            return Err(RpgError::ByteLimitTooSmall {
+                length: self.length,
+                max_bytes,
+            });
+        }
+        Ok(())
+    }
+
+    /// 繰り返し・連続文字の制約の値と、明らかに満たせない組み合わせを検証
+    fn validate_run_rules(&self, charsets: &[ClassCharset]) -> Result<()> {
+        if !self.has_run_rules() {
+            return Ok(());
+        }
+        if self.max_consecutive == Some(0) {
+            return Err(RpgError::InvalidRepeatLimit(
+                "consecutive repeats".to_string(),
+            ));
+        }
+        if self.max_occurrences == Some(0) {
+            return Err(RpgError::InvalidRepeatLimit(
+                "occurrences of a character".to_string(),
+            ));
+        }
+
+        // 各文字をmax_occurrences回ずつ使っても長さに届かない
+        let distinct: usize = charsets.iter().map(|charset| charset.chars.len()).sum();
+        if let Some(max) = self.max_occurrences {
+            if distinct.saturating_mul(max) < self.length {
+                return Err(RpgError::RunRulesTooRestrictive);
+            }
+        }
+        Ok(())
+    }
+
+    /// 文字種ごとの最小・最大文字数の組み合わせが実現可能かを検証
+    fn validate_class_counts(&self, charsets: &[ClassCharset]) -> Result<()> {
+        for charset in charsets {
+            let class = charset.class;
+            if charset.chars.is_empty() {
+                if self.min_count(class) > 0 {
+                    return Err(RpgError::MinimumForDisabledClass(class));
+                }
+                continue;
+            }
+            if charset.required && self.max_count(class) == Some(0) {
+                return Err(RpgError::ZeroMaximumForEnabledClass(class));
+            }
+            if let Some(max) = self.max_count(class) {
+                if self.min_count(class) > max {
+                    return Err(RpgError::MinimumExceedsMaximum {
+                        class,
+                        min: self.min_count(class),
+                        max,
+                    });
+                }
+            }
+        }
+
+        let min_total: usize = charsets.iter().map(|c| self.count_range(c).0).sum();
+        if min_total > self.length {
+            return Err(RpgError::MinimumsExceedLength {
+                total: min_total,
+                length: self.length,
+            });
+        }
+
+        let max_total: usize = charsets.iter().map(|c| self.count_range(c).1).sum();
+        if max_total < self.length {
+            return Err(RpgError::MaximumsBelowLength {
+                total: max_total,
+                length: self.length,
+            });
+        }
+
         Ok(())
     }
 }
@@ -57,27 +465,32 @@ impl PasswordConfig {
     name = "rpg",
     version = "0.2.0",
     about = "A secure command-line password generator",
-    long_about = None
+    long_about = None,
+    args_conflicts_with_subcommands = true,
+    group(ArgGroup::new("password_options").multiple(true))
 )]
 pub struct CliArgs {
-    /// Length of the password
-    #[arg(short, long, default_value = "16")]
+    #[command(subcommand)]
+    pub command: Option<Commands>,
+
+    /// Length of the password in characters
+    #[arg(short, long, default_value = "16", group = "password_options")]
     pub length: usize,
 
     /// Exclude uppercase letters
-    #[arg(long)]
+    #[arg(long, group = "password_options")]
     pub no_uppercase: bool,
 
     /// Exclude lowercase letters
-    #[arg(long)]
+    #[arg(long, group = "password_options")]
     pub no_lowercase: bool,
 
     /// Exclude digits
-    #[arg(long)]
+    #[arg(long, group = "password_options")]
     pub no_digits: bool,
 
     /// Exclude symbols
-    #[arg(long)]
+    #[arg(long, group = "password_options")]
     pub no_symbols: bool,
 
     /// Copy password to clipboard
@@ -88,27 +501,456 @@ pub struct CliArgs {
     #[arg(short = 'n', long, default_value = "1")]
     pub number: usize,
 
+    /// Generate in parallel on this many threads (each with its own CSPRNG)
+    #[arg(long, default_value = "1", value_name = "N", conflicts_with = "copy")]
+    pub threads: usize,
+
+    /// Never repeat a password or passphrase within the batch
+    #[arg(long, conflicts_with = "threads")]
+    pub unique: bool,
+
+    /// Minimum number of uppercase letters
+    #[arg(
+        long,
+        default_value = "0",
+        value_name = "N",
+        group = "password_options"
+    )]
+    pub min_uppercase: usize,
+
+    /// Minimum number of lowercase letters
+    #[arg(
+        long,
+        default_value = "0",
+        value_name = "N",
+        group = "password_options"
+    )]
+    pub min_lowercase: usize,
+
+    /// Minimum number of digits
+    #[arg(
+        long,
+        default_value = "0",
+        value_name = "N",
+        group = "password_options"
+    )]
+    pub min_digits: usize,
+
+    /// Minimum number of symbols
+    #[arg(
+        long,
+        default_value = "0",
+        value_name = "N",
+        group = "password_options"
+    )]
+    pub min_symbols: usize,
+
+    /// Maximum number of uppercase letters
+    #[arg(long, value_name = "N", group = "password_options")]
+    pub max_uppercase: Option<usize>,
+
+    /// Maximum number of lowercase letters
+    #[arg(long, value_name = "N", group = "password_options")]
+    pub max_lowercase: Option<usize>,
+
+    /// Maximum number of digits
+    #[arg(long, value_name = "N", group = "password_options")]
+    pub max_digits: Option<usize>,
+
+    /// Maximum number of symbols
+    #[arg(long, value_name = "N", group = "password_options")]
+    pub max_symbols: Option<usize>,
+
+    /// Exclude visually ambiguous characters (0 O o 1 l I | 5 S and quotes)
+    #[arg(long, group = "password_options")]
+    pub no_ambiguous: bool,
+
+    /// Maximum run of the same character (1 forbids identical neighbours)
+    #[arg(long, value_name = "N", group = "password_options")]
+    pub max_consecutive: Option<usize>,
+
+    /// Maximum number of times any single character may appear
+    #[arg(long, value_name = "N", group = "password_options")]
+    pub max_occurrences: Option<usize>,
+
+    /// Forbid ascending or descending runs such as "abc" or "321"
+    #[arg(long, group = "password_options")]
+    pub no_sequences: bool,
+
+    /// Character classes allowed for the first character (e.g. "letter" or "upper,digit")
+    #[arg(
+        long,
+        value_name = "CLASSES",
+        value_parser = parse_class_list,
+        group = "password_options"
+    )]
+    pub start_with: Option<ClassList>,
+
+    /// Character classes allowed for the last character (e.g. "alnum")
+    #[arg(
+        long,
+        value_name = "CLASSES",
+        value_parser = parse_class_list,
+        group = "password_options"
+    )]
+    pub end_with: Option<ClassList>,
+
+    /// Never put a symbol at the first or last position
+    #[arg(long, group = "password_options")]
+    pub no_symbol_at_ends: bool,
+
+    /// Drop characters that need escaping in these contexts (comma-separated)
+    #[arg(
+        long,
+        value_name = "CONTEXT",
+        value_enum,
+        value_delimiter = ',',
+        group = "password_options"
+    )]
+    pub safe_for: Vec<SafeContext>,
+
+    /// Also use letters from these Unicode scripts (comma-separated)
+    #[arg(
+        long,
+        value_name = "SETS",
+        value_enum,
+        value_delimiter = ',',
+        group = "password_options"
+    )]
+    pub unicode: Vec<UnicodeSet>,
+
+    /// Maximum size of the password in bytes of UTF-8 (the length counts characters)
+    #[arg(long, value_name = "BYTES", group = "password_options")]
+    pub max_bytes: Option<usize>,
+
+    /// Use exactly these characters instead of the character set options
+    #[arg(
+        long,
+        value_name = "STRING",
+        allow_hyphen_values = true,
+        group = "password_options",
+        conflicts_with_all = ["no_uppercase", "no_lowercase", "no_digits", "no_symbols", "symbols"]
+    )]
+    pub charset: Option<String>,
+
+    /// Additional characters to include
+    #[arg(
+        long,
+        value_name = "CHARS",
+        allow_hyphen_values = true,
+        group = "password_options"
+    )]
+    pub include: Option<String>,
+
+    /// Characters to exclude
+    #[arg(
+        long,
+        value_name = "CHARS",
+        allow_hyphen_values = true,
+        group = "password_options"
+    )]
+    pub exclude: Option<String>,
+
+    /// Replace the symbol set with these characters
+    #[arg(
+        long,
+        value_name = "CHARS",
+        allow_hyphen_values = true,
+        group = "password_options"
+    )]
+    pub symbols: Option<String>,
+
+    /// Generate a password satisfying the rules in a TOML policy file
+    #[arg(
+        long,
+        value_name = "FILE",
+        conflicts_with_all = [
+            "password_options", "passphrase", "pattern", "regex", "pronounceable",
+        ]
+    )]
+    pub policy: Option<PathBuf>,
+
+    /// Generate a password for a built-in policy preset (see `rpg presets list`)
+    #[arg(
+        long,
+        value_name = "NAME",
+        conflicts_with_all = [
+            "password_options", "policy", "passphrase", "pattern", "regex", "pronounceable",
+        ]
+    )]
+    pub preset: Option<String>,
+
+    /// Generate a password satisfying a "passwordrules" string (e.g. "minlength: 12; required: lower;")
+    #[arg(
+        long,
+        value_name = "RULES",
+        conflicts_with_all = [
+            "password_options", "policy", "preset", "passphrase", "pattern", "regex",
+            "pronounceable",
+        ]
+    )]
+    pub rules: Option<String>,
+
     /// Generate passphrase instead of password
-    #[arg(long, conflicts_with_all = ["length", "no_uppercase", "no_lowercase", "no_digits", "no_symbols"])]
+    #[arg(long, conflicts_with = "password_options")]
     pub passphrase: bool,
 
     /// Number of words in passphrase (only with --passphrase)
     #[arg(long, default_value = "4", requires = "passphrase")]
     pub words: usize,
 
-    /// Disable colored output
+    /// Generate a password from a pattern (e.g. "Cvccv-d{4}-ss")
+    #[arg(
+        long,
+        value_name = "PATTERN",
+        allow_hyphen_values = true,
+        conflicts_with_all = ["password_options", "passphrase"]
+    )]
+    pub pattern: Option<String>,
+
+    /// Generate a string matching a regular expression (e.g. "[A-Z]{3}-[0-9]{4,6}")
+    #[arg(
+        long,
+        value_name = "REGEX",
+        allow_hyphen_values = true,
+        conflicts_with_all = ["password_options", "passphrase", "pattern"]
+    )]
+    pub regex: Option<String>,
+
+    /// Generate a pronounceable password (alternating consonants and vowels)
+    #[arg(
+        long,
+        conflicts_with_all = [
+            "passphrase", "pattern", "regex", "no_lowercase", "no_symbols",
+            "min_uppercase", "min_lowercase", "min_digits", "min_symbols",
+            "max_uppercase", "max_lowercase", "max_digits", "max_symbols",
+            "no_ambiguous", "charset", "include", "exclude", "symbols",
+            "max_consecutive", "max_occurrences", "no_sequences",
+            "start_with", "end_with", "no_symbol_at_ends", "safe_for", "unicode",
+            "max_bytes",
+        ]
+    )]
+    pub pronounceable: bool,
+
+    /// Seed the generator for reproducible test output (INSECURE)
+    #[arg(long, value_name = "SEED", hide = true)]
+    pub insecure_seed: Option<u64>,
+
+    /// Choose the shortest length (or fewest words) reaching this many bits of entropy
+    #[arg(
+        long,
+        value_name = "BITS",
+        conflicts_with_all = ["length", "words", "pattern", "regex", "pronounceable"]
+    )]
+    pub entropy: Option<f64>,
+
+    /// Fail if the configuration yields less than this many bits of entropy
+    #[arg(long, value_name = "BITS")]
+    pub min_entropy: Option<f64>,
+
+    /// Show entropy and a strength label next to each generated item
     #[arg(long)]
+    pub show_entropy: bool,
+
+    /// Disable colored output
+    #[arg(long, global = true)]
     pub no_color: bool,
 }
 
+/// サブコマンド
+#[derive(Subcommand, Debug, Clone)]
+pub enum Commands {
+    /// Analyze the strength of a password read from stdin (or a hidden prompt)
+    Check(CheckArgs),
+    /// List or show the built-in policy presets
+    Presets(PresetsArgs),
+    /// Generate numeric PINs, avoiding repeats, sequences, dates and common PINs
+    Pin(PinArgs),
+    /// Generate random tokens from raw bytes (hex, base64url, base32, base58 or UUIDv4)
+    Token(TokenArgs),
+    /// Generate prefixed API keys with a checksum (e.g. acme_<base62 body><checksum>)
+    Key(KeyArgs),
+    /// Check the format and checksum of an API key read from stdin (or a hidden prompt)
+    VerifyKey(VerifyKeyArgs),
+}
+
+/// `rpg check`の引数
+#[derive(Args, Debug, Clone)]
+pub struct CheckArgs {
+    /// Additional word list (one word per line) used for dictionary matching
+    #[arg(long = "wordlist", value_name = "FILE")]
+    pub wordlists: Vec<PathBuf>,
+}
+
+/// `rpg pin`の引数
+#[derive(Args, Debug, Clone)]
+pub struct PinArgs {
+    /// Number of digits (4-10)
+    #[arg(short, long, default_value = "6")]
+    pub length: usize,
+
+    /// Number of PINs to generate
+    #[arg(short = 'n', long, default_value = "1")]
+    pub number: usize,
+
+    /// Never repeat a PIN within the batch
+    #[arg(long)]
+    pub unique: bool,
+
+    /// Copy the PIN to clipboard
+    #[arg(short = 'c', long)]
+    pub copy: bool,
+
+    /// Show entropy (after excluding weak PINs) next to each PIN
+    #[arg(long)]
+    pub show_entropy: bool,
+
+    /// Seed the generator for reproducible test output (INSECURE)
+    #[arg(long, value_name = "SEED", hide = true)]
+    pub insecure_seed: Option<u64>,
+}
+
+/// `rpg token`の引数
+#[derive(Args, Debug, Clone)]
+pub struct TokenArgs {
+    /// Number of random bytes [default: 32, or 16 for uuid]
+    #[arg(short, long, value_name = "N")]
+    pub bytes: Option<usize>,
+
+    /// Output encoding
+    #[arg(short, long, value_enum, default_value = "hex")]
+    pub encoding: TokenEncoding,
+
+    /// Number of tokens to generate
+    #[arg(short = 'n', long, default_value = "1")]
+    pub number: usize,
+
+    /// Copy the token to clipboard
+    #[arg(short = 'c', long)]
+    pub copy: bool,
+
+    /// Show entropy next to each token
+    #[arg(long)]
+    pub show_entropy: bool,
+
+    /// Seed the generator for reproducible test output (INSECURE)
+    #[arg(long, value_name = "SEED", hide = true)]
+    pub insecure_seed: Option<u64>,
+}
+
+/// `rpg key`の引数
+#[derive(Args, Debug, Clone)]
+pub struct KeyArgs {
+    /// Prefix identifying the key (ASCII letters and digits, without the '_')
+    #[arg(short, long)]
+    pub prefix: String,
+
+    /// Number of random base62 characters in the body
+    #[arg(short, long, default_value = "30")]
+    pub length: usize,
+
+    /// Checksum algorithm
+    #[arg(long, value_enum, default_value = "crc32")]
+    pub checksum: KeyChecksum,
+
+    /// Number of keys to generate
+    #[arg(short = 'n', long, default_value = "1")]
+    pub number: usize,
+
+    /// Copy the key to clipboard
+    #[arg(short = 'c', long)]
+    pub copy: bool,
+
+    /// Show entropy next to each key
+    #[arg(long)]
+    pub show_entropy: bool,
+
+    /// Seed the generator for reproducible test output (INSECURE)
+    #[arg(long, value_name = "SEED", hide = true)]
+    pub insecure_seed: Option<u64>,
+}
+
+/// `rpg verify-key`の引数
+#[derive(Args, Debug, Clone)]
+pub struct VerifyKeyArgs {
+    /// Require this prefix
+    #[arg(short, long)]
+    pub prefix: Option<String>,
+
+    /// Require this many characters in the body
+    #[arg(short, long)]
+    pub length: Option<usize>,
+
+    /// Checksum algorithm
+    #[arg(long, value_enum, default_value = "crc32")]
+    pub checksum: KeyChecksum,
+}
+
+/// `rpg presets`の引数
+#[derive(Args, Debug, Clone)]
+pub struct PresetsArgs {
+    #[command(subcommand)]
+    pub command: PresetsCommand,
+}
+
+/// `rpg presets`のサブコマンド
+#[derive(Subcommand, Debug, Clone)]
+pub enum PresetsCommand {
+    /// List the available presets
+    List,
+    /// Print a preset as a policy file (usable with --policy)
+    Show {
+        /// Preset name
+        name: String,
+    },
+}
+
 impl From<CliArgs> for PasswordConfig {
     fn from(args: CliArgs) -> Self {
+        // --no-symbol-at-endsは両端の文字種から記号を除く
+        let ends = |classes: Option<ClassList>| -> Option<ClassList> {
+            if !args.no_symbol_at_ends {
+                return classes;
+            }
+            let classes = classes.unwrap_or_else(|| CharClass::ALL.to_vec());
+            Some(
+                classes
+                    .into_iter()
+                    .filter(|&class| class != CharClass::Symbol)
+                    .collect(),
+            )
+        };
+        let start_with = ends(args.start_with.clone());
+        let end_with = ends(args.end_with.clone());
+
         Self {
             length: args.length,
             use_uppercase: !args.no_uppercase,
             use_lowercase: !args.no_lowercase,
             use_digits: !args.no_digits,
             use_symbols: !args.no_symbols,
+            min_uppercase: args.min_uppercase,
+            min_lowercase: args.min_lowercase,
+            min_digits: args.min_digits,
+            min_symbols: args.min_symbols,
+            max_uppercase: args.max_uppercase,
+            max_lowercase: args.max_lowercase,
+            max_digits: args.max_digits,
+            max_symbols: args.max_symbols,
+            avoid_ambiguous: args.no_ambiguous,
+            custom_charset: args.charset,
+            custom_symbols: args.symbols,
+            include_chars: args.include.unwrap_or_default(),
+            exclude_chars: args.exclude.unwrap_or_default(),
+            start_with,
+            end_with,
+            required_sets: Vec::new(),
+            max_consecutive: args.max_consecutive,
+            max_occurrences: args.max_occurrences,
+            no_sequences: args.no_sequences,
+            safe_for: args.safe_for,
+            unicode: args.unicode,
+            max_bytes: args.max_bytes,
         }
     }
 }
@@ -116,6 +958,7 @@ impl From<CliArgs> for PasswordConfig {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::charset::CharacterSets;
 
     #[test]
     fn test_default_config() {
@@ -162,6 +1005,7 @@ mod tests {
             use_lowercase: false,
             use_digits: false,
             use_symbols: false,
+            ..Default::default()
         };
         assert!(matches!(
             config.validate(),
@@ -169,25 +1013,433 @@ mod tests {
         ));
     }
 
+    #[test]
+    fn test_validate_length_shorter_than_classes() {
+        let config = PasswordConfig {
+            length: 3,
+            ..Default::default()
+        };
+        assert!(matches!(
+            config.validate(),
+            Err(RpgError::LengthTooShortForClasses {
+                length: 3,
+                required: 4
+            })
+        ));
+    }
+
     #[test]
     fn test_cli_args_to_config() {
         let args = CliArgs {
+            command: None,
             length: 20,
             no_uppercase: true,
             no_lowercase: false,
             no_digits: false,
             no_symbols: true,
+            min_uppercase: 0,
+            min_lowercase: 0,
+            min_digits: 2,
+            min_symbols: 0,
+            max_uppercase: None,
+            max_lowercase: Some(10),
+            max_digits: None,
+            max_symbols: None,
+            no_ambiguous: true,
+            max_consecutive: Some(1),
+            max_occurrences: None,
+            no_sequences: true,
+            start_with: Some(vec![CharClass::Lowercase, CharClass::Symbol]),
+            end_with: None,
+            no_symbol_at_ends: true,
+            safe_for: vec![SafeContext::Url],
+            unicode: vec![UnicodeSet::Greek],
+            max_bytes: Some(40),
+            charset: None,
+            include: None,
+            exclude: Some("xyz".to_string()),
+            symbols: Some("-_".to_string()),
             copy: false,
             number: 1,
+            threads: 1,
+            unique: false,
+            policy: None,
+            preset: None,
+            rules: None,
             passphrase: false,
             words: 4,
+            pattern: None,
+            regex: None,
+            pronounceable: false,
+            insecure_seed: None,
+            entropy: None,
+            min_entropy: None,
+            show_entropy: false,
             no_color: false,
         };
         let config = PasswordConfig::from(args);
         assert_eq!(config.length, 20);
+        assert_eq!(config.max_consecutive, Some(1));
+        assert!(config.no_sequences);
+        assert_eq!(config.start_with, Some(vec![CharClass::Lowercase]));
+        assert_eq!(config.safe_for, vec![SafeContext::Url]);
+        assert_eq!(config.unicode, vec![UnicodeSet::Greek]);
+        assert_eq!(config.max_bytes, Some(40));
+        assert_eq!(
+            config.end_with,
+            Some(vec![
+                CharClass::Uppercase,
+                CharClass::Lowercase,
+                CharClass::Digit
+            ])
+        );
         assert!(!config.use_uppercase);
         assert!(config.use_lowercase);
         assert!(config.use_digits);
         assert!(!config.use_symbols);
+        assert_eq!(config.min_digits, 2);
+        assert_eq!(config.max_lowercase, Some(10));
+        assert!(config.avoid_ambiguous);
+        assert_eq!(config.exclude_chars, "xyz");
+        assert_eq!(config.custom_symbols.as_deref(), Some("-_"));
+    }
+
+    #[test]
+    fn test_entropy_bits_unconstrained_lowercase() {
+        let config = PasswordConfig {
+            length: 10,
+            use_uppercase: false,
+            use_digits: false,
+            use_symbols: false,
+            ..Default::default()
+        };
+        assert!((config.entropy_bits() - 10.0 * 26f64.log2()).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_entropy_bits_reflects_constraints() {
+        let config = PasswordConfig::default();
+        let uniform = 16.0 * 88f64.log2();
+        // 各文字種を必ず含める分だけ、一様選択よりわずかに小さい
+        assert!(config.entropy_bits() < uniform);
+        assert!(config.entropy_bits() > uniform - 1.0);
+
+        let reduced = PasswordConfig {
+            avoid_ambiguous: true,
+            ..Default::default()
+        };
+        assert!(reduced.entropy_bits() < config.entropy_bits());
+    }
+
+    #[test]
+    fn test_with_target_entropy_picks_minimal_length() {
+        let config = PasswordConfig {
+            use_uppercase: false,
+            use_digits: false,
+            use_symbols: false,
+            ..Default::default()
+        };
+        // 26^n >= 2^40 となる最小のnは9（26^8 ≈ 2^37.6）
+        let fitted = config.with_target_entropy(40.0).unwrap();
+        assert_eq!(fitted.length, 9);
+        assert!(fitted.entropy_bits() >= 40.0);
+    }
+
+    #[test]
+    fn test_with_target_entropy_default_classes() {
+        let fitted = PasswordConfig::default().with_target_entropy(96.0).unwrap();
+        let shorter = PasswordConfig {
+            length: fitted.length - 1,
+            ..fitted.clone()
+        };
+        assert!(fitted.entropy_bits() >= 96.0);
+        assert!(shorter.entropy_bits() < 96.0);
+    }
+
+    #[test]
+    fn test_with_target_entropy_unreachable() {
+        let config = PasswordConfig {
+            custom_charset: Some("ab".to_string()),
+            ..Default::default()
+        };
+        assert!(matches!(
+            config.with_target_entropy(2000.0),
+            Err(RpgError::EntropyUnreachable { .. })
+        ));
+
+        // 最大文字数の制約で長さの上限が決まる場合
+        let capped = PasswordConfig {
+            use_uppercase: false,
+            use_lowercase: false,
+            use_symbols: false,
+            max_digits: Some(4),
+            ..Default::default()
+        };
+        assert!(matches!(
+            capped.with_target_entropy(20.0),
+            Err(RpgError::EntropyUnreachable { .. })
+        ));
+    }
+
+    #[test]
+    fn test_with_target_entropy_invalid_target() {
+        assert!(matches!(
+            PasswordConfig::default().with_target_entropy(f64::NAN),
+            Err(RpgError::InvalidEntropyTarget(_))
+        ));
+    }
+
+    #[test]
+    fn test_validate_min_and_max_counts() {
+        let config = PasswordConfig {
+            min_digits: 2,
+            max_symbols: Some(3),
+            ..Default::default()
+        };
+        assert!(config.validate().is_ok());
+    }
+
+    #[test]
+    fn test_validate_minimums_exceed_length() {
+        let config = PasswordConfig {
+            length: 8,
+            min_digits: 4,
+            min_symbols: 3,
+            ..Default::default()
+        };
+        // 大文字・小文字の最低1文字ずつを含めて合計9文字
+        assert!(matches!(
+            config.validate(),
+            Err(RpgError::MinimumsExceedLength {
+                total: 9,
+                length: 8
+            })
+        ));
+    }
+
+    #[test]
+    fn test_validate_maximums_below_length() {
+        let config = PasswordConfig {
+            length: 10,
+            use_uppercase: false,
+            use_symbols: false,
+            max_lowercase: Some(4),
+            max_digits: Some(4),
+            ..Default::default()
+        };
+        assert!(matches!(
+            config.validate(),
+            Err(RpgError::MaximumsBelowLength {
+                total: 8,
+                length: 10
+            })
+        ));
+    }
+
+    #[test]
+    fn test_validate_zero_maximum_on_enabled_class() {
+        let config = PasswordConfig {
+            max_symbols: Some(0),
+            ..Default::default()
+        };
+        assert!(matches!(
+            config.validate(),
+            Err(RpgError::ZeroMaximumForEnabledClass(CharClass::Symbol))
+        ));
+    }
+
+    #[test]
+    fn test_validate_minimum_exceeds_maximum() {
+        let config = PasswordConfig {
+            min_digits: 5,
+            max_digits: Some(3),
+            ..Default::default()
+        };
+        assert!(matches!(
+            config.validate(),
+            Err(RpgError::MinimumExceedsMaximum {
+                class: CharClass::Digit,
+                min: 5,
+                max: 3
+            })
+        ));
+    }
+
+    #[test]
+    fn test_validate_excluded_class_is_empty() {
+        let config = PasswordConfig {
+            exclude_chars: CharacterSets::DIGITS.to_string(),
+            ..Default::default()
+        };
+        assert!(matches!(
+            config.validate(),
+            Err(RpgError::EmptyCharacterSet(CharClass::Digit))
+        ));
+    }
+
+    #[test]
+    fn test_validate_custom_charset_empty_after_exclusion() {
+        let config = PasswordConfig {
+            custom_charset: Some("abc".to_string()),
+            exclude_chars: "cba".to_string(),
+            ..Default::default()
+        };
+        assert!(matches!(
+            config.validate(),
+            Err(RpgError::NoCharacterSetsEnabled)
+        ));
+    }
+
+    #[test]
+    fn test_validate_custom_charset_ignores_class_toggles() {
+        let config = PasswordConfig {
+            length: 2,
+            custom_charset: Some("01".to_string()),
+            ..Default::default()
+        };
+        assert!(config.validate().is_ok());
+    }
+
+    #[test]
+    fn test_validate_minimum_for_disabled_class() {
+        let config = PasswordConfig {
+            use_digits: false,
+            min_digits: 1,
+            ..Default::default()
+        };
+        assert!(matches!(
+            config.validate(),
+            Err(RpgError::MinimumForDisabledClass(CharClass::Digit))
+        ));
+    }
+
+    #[test]
+    fn test_validate_unsatisfiable_position_rules() {
+        let config = PasswordConfig {
+            start_with: Some(vec![CharClass::Symbol]),
+            use_symbols: false,
+            ..Default::default()
+        };
+        assert_eq!(
+            config.validate(),
+            Err(RpgError::UnsatisfiablePositionRule("first".to_string()))
+        );
+
+        // 数字は1文字までなので先頭と末尾の両方には置けない
+        let config = PasswordConfig {
+            length: 8,
+            max_digits: Some(1),
+            start_with: Some(vec![CharClass::Digit]),
+            end_with: Some(vec![CharClass::Digit]),
+            ..Default::default()
+        };
+        assert_eq!(
+            config.validate(),
+            Err(RpgError::UnsatisfiablePositionRule(
+                "first and last".to_string()
+            ))
+        );
+    }
+
+    #[test]
+    fn test_validate_unsatisfiable_required_sets() {
+        let config = PasswordConfig {
+            use_symbols: false,
+            required_sets: vec!["-!".to_string()],
+            ..Default::default()
+        };
+        assert_eq!(
+            config.validate(),
+            Err(RpgError::UnsatisfiableRequiredSet("-!".to_string()))
+        );
+
+        let config = PasswordConfig {
+            length: 1,
+            custom_charset: Some("ab".to_string()),
+            required_sets: vec!["a".to_string(), "b".to_string()],
+            ..Default::default()
+        };
+        assert_eq!(
+            config.validate(),
+            Err(RpgError::UnsatisfiableRequiredSet("a b".to_string()))
+        );
+    }
+
+    #[test]
+    fn test_validate_run_rules() {
+        let config = PasswordConfig {
+            max_consecutive: Some(0),
+            ..Default::default()
+        };
+        assert!(matches!(
+            config.validate(),
+            Err(RpgError::InvalidRepeatLimit(_))
+        ));
+
+        let config = PasswordConfig {
+            length: 5,
+            custom_charset: Some("ab".to_string()),
+            max_occurrences: Some(2),
+            ..Default::default()
+        };
+        assert_eq!(config.validate(), Err(RpgError::RunRulesTooRestrictive));
+
+        let config = PasswordConfig {
+            length: 2,
+            custom_charset: Some("a".to_string()),
+            max_consecutive: Some(1),
+            ..Default::default()
+        };
+        assert_eq!(config.validate(), Err(RpgError::RunRulesTooRestrictive));
+
+        // 無作為な文字列ではほとんど満たさないが、数え上げれば実現可能とわかる
+        let config = PasswordConfig {
+            length: 30,
+            custom_charset: Some("abc".to_string()),
+            max_consecutive: Some(1),
+            ..Default::default()
+        };
+        assert!(config.validate().is_ok());
+    }
+
+    #[test]
+    fn test_validate_byte_limit() {
+        let config = PasswordConfig {
+            length: 10,
+            max_bytes: Some(9),
+            ..Default::default()
+        };
+        assert_eq!(
+            config.validate(),
+            Err(RpgError::ByteLimitTooSmall {
+                length: 10,
+                max_bytes: 9
+            })
+        );
+
+        // かなは3バイトなので、10文字すべてがかなでは30バイトになる
+        let config = PasswordConfig {
+            length: 10,
+            custom_charset: Some("あいうえお".to_string()),
+            max_bytes: Some(29),
+            ..Default::default()
+        };
+        assert!(matches!(
+            config.validate(),
+            Err(RpgError::ByteLimitTooSmall { .. })
+        ));
+
+        let config = PasswordConfig {
+            length: 10,
+            unicode: vec![UnicodeSet::Kana],
+            max_bytes: Some(16),
+            ..Default::default()
+        };
+        assert!(config.validate().is_ok());
+        let unlimited = PasswordConfig {
+            max_bytes: None,
+            ..config.clone()
+        };
+        assert!(config.entropy_bits() < unlimited.entropy_bits());
     }
 }
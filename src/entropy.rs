@@ -0,0 +1,94 @@
+//! エントロピーに基づく強度評価モジュール
+
+use std::fmt;
+
+/// エントロピーから判定した強度
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Strength {
+    VeryWeak,
+    Weak,
+    Reasonable,
+    Strong,
+    VeryStrong,
+}
+
+impl Strength {
+    /// エントロピー（ビット）から強度を判定
+    ///
+    /// しきい値: 28未満=very weak、36未満=weak、60未満=reasonable、128未満=strong
+    pub fn from_bits(bits: f64) -> Self {
+        if bits < 28.0 {
+            Strength::VeryWeak
+        } else if bits < 36.0 {
+            Strength::Weak
+        } else if bits < 60.0 {
+            Strength::Reasonable
+        } else if bits < 128.0 {
+            Strength::Strong
+        } else {
+            Strength::VeryStrong
+        }
+    }
+}
+
+impl fmt::Display for Strength {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let label = match self {
+            Strength::VeryWeak => "very weak",
+            Strength::Weak => "weak",
+            Strength::Reasonable => "reasonable",
+            Strength::Strong => "strong",
+            Strength::VeryStrong => "very strong",
+        };
+        write!(f, "{}", label)
+    }
+}
+
+/// 生成結果の横に表示するエントロピー
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Entropy {
+    /// 生成され得る値の数から計算した値（`103.4 bits, strong`と表示）
+    Bits(f64),
+    /// 上限にすぎない値（`≤ 1024.0 bits, upper bound`と表示）
+    ///
+    /// 曖昧な正規表現のように、同じ値を別の選び方で重複して数える場合です。
+    AtMost(f64),
+}
+
+impl fmt::Display for Entropy {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Entropy::Bits(bits) => write!(f, "{}", format_entropy(*bits)),
+            Entropy::AtMost(bits) => write!(f, "≤ {:.1} bits, upper bound", bits),
+        }
+    }
+}
+
+/// エントロピーと強度を表示用に整形（例: `103.4 bits, strong`）
+pub fn format_entropy(bits: f64) -> String {
+    format!("{:.1} bits, {}", bits, Strength::from_bits(bits))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_strength_thresholds() {
+        assert_eq!(Strength::from_bits(0.0), Strength::VeryWeak);
+        assert_eq!(Strength::from_bits(30.0), Strength::Weak);
+        assert_eq!(Strength::from_bits(40.0), Strength::Reasonable);
+        assert_eq!(Strength::from_bits(80.0), Strength::Strong);
+        assert_eq!(Strength::from_bits(128.0), Strength::VeryStrong);
+    }
+
+    #[test]
+    fn test_format_entropy() {
+        assert_eq!(format_entropy(103.44), "103.4 bits, strong");
+        assert_eq!(Entropy::Bits(103.44).to_string(), "103.4 bits, strong");
+        assert_eq!(
+            Entropy::AtMost(1024.0).to_string(),
+            "≤ 1024.0 bits, upper bound"
+        );
+    }
+}
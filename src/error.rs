@@ -1,3 +1,4 @@
+use crate::charset::CharClass;
 use std::fmt;
 
 /// rpgライブラリのエラー型
@@ -9,14 +10,78 @@ pub enum RpgError {
     InvalidLength(usize),
     /// パスワード長が大きすぎる（メモリ安全性のため）
     LengthTooLarge(usize),
+    /// 有効な文字種の文字がすべて除外されている
+    EmptyCharacterSet(CharClass),
+    /// パスワード長が必須文字種の数より短い
+    LengthTooShortForClasses { length: usize, required: usize },
+    /// 文字種ごとの最小文字数の合計がパスワード長を超える
+    MinimumsExceedLength { total: usize, length: usize },
+    /// 文字種ごとの最大文字数の合計がパスワード長に満たない
+    MaximumsBelowLength { total: usize, length: usize },
+    /// 有効な文字種の最大文字数が0
+    ZeroMaximumForEnabledClass(CharClass),
+    /// 最小文字数が最大文字数を超える
+    MinimumExceedsMaximum {
+        class: CharClass,
+        min: usize,
+        max: usize,
+    },
+    /// 無効化された文字種に最小文字数が指定されている
+    MinimumForDisabledClass(CharClass),
+    /// 先頭・末尾の文字種の指定を満たすパスワードが存在しない
+    UnsatisfiablePositionRule(String),
+    /// 必須の文字集合を満たすパスワードが存在しない
+    UnsatisfiableRequiredSet(String),
+    /// 繰り返しの上限が0
+    InvalidRepeatLimit(String),
+    /// 繰り返し・連続文字の制約を満たすパスワードがほぼ存在しない
+    RunRulesTooRestrictive,
+    /// UTF-8でのバイト数の上限に収まるパスワードがほぼ存在しない
+    ByteLimitTooSmall { length: usize, max_bytes: usize },
+    /// ポリシーファイルの構文エラー
+    PolicyParse { source: String, message: String },
+    /// ポリシーの規則が不正、または他の規則と両立しない
+    InvalidPolicyRule { rule: String, message: String },
+    /// 存在しないプリセット名
+    UnknownPreset(String),
+    /// passwordrulesの構文エラー（位置は1始まりの文字位置）
+    PasswordRulesParse { position: usize, message: String },
+    /// パターンの構文エラー（位置は1始まりの文字位置）
+    PatternParse { position: usize, message: String },
+    /// 正規表現の構文エラー、または未対応の構文（位置は1始まりの文字位置）
+    RegexParse { position: usize, message: String },
+    /// PINの桁数が範囲外
+    InvalidPinLength(usize),
+    /// トークンのバイト数が範囲外
+    InvalidTokenSize(usize),
+    /// UUIDに16以外のバイト数が指定された
+    UuidSizeFixed(usize),
+    /// APIキーの形式の設定が不正
+    InvalidKeyFormat(String),
+    /// APIキーの検証に失敗
+    InvalidKey(String),
     /// 不正なワード数（0以下）
     InvalidWordCount(usize),
     /// ワード数が大きすぎる
     WordCountTooLarge(usize),
+    /// 目標エントロピーが不正（負の値や非数）
+    InvalidEntropyTarget(f64),
+    /// 最大長・最大ワード数でも目標エントロピーに届かない
+    EntropyUnreachable { target: f64, max: f64 },
+    /// 設定のエントロピーが要求された最小値に届かない
+    InsufficientEntropy { actual: f64, required: f64 },
+    /// エントロピーが上限しか分からず、最小エントロピーを満たすか確かめられない
+    UnverifiableEntropy { upper_bound: f64, required: f64 },
+    /// ファイルの読み込みエラー
+    FileRead { path: String, message: String },
     /// クリップボード操作エラー
     ClipboardError(String),
     /// 不正な生成個数
     InvalidGenerationCount(usize),
+    /// 不正なスレッド数
+    InvalidThreadCount(usize),
+    /// 重複なしで要求された個数が、生成され得る値の総数を超える
+    NotEnoughUniqueValues { requested: usize, available: f64 },
     /// 出力するアイテムが空
     EmptyOutput,
     /// その他のエラー
@@ -35,12 +100,172 @@ impl fmt::Display for RpgError {
             RpgError::LengthTooLarge(len) => {
                 write!(f, "Error: Password length {} is too large (max: 1024)", len)
             }
+            RpgError::EmptyCharacterSet(class) => {
+                write!(f, "Error: All {} were removed by exclusions", class)
+            }
+            RpgError::LengthTooShortForClasses { length, required } => {
+                write!(
+                    f,
+                    "Error: Password length {} is too short to include all {} enabled character sets",
+                    length, required
+                )
+            }
+            RpgError::MinimumsExceedLength { total, length } => {
+                write!(
+                    f,
+                    "Error: Minimum character counts add up to {}, which exceeds the password length {}",
+                    total, length
+                )
+            }
+            RpgError::MaximumsBelowLength { total, length } => {
+                write!(
+                    f,
+                    "Error: Maximum character counts add up to {}, which cannot fill the password length {}",
+                    total, length
+                )
+            }
+            RpgError::ZeroMaximumForEnabledClass(class) => {
+                write!(
+                    f,
+                    "Error: Maximum count for {} is 0 but the character set is enabled",
+                    class
+                )
+            }
+            RpgError::MinimumExceedsMaximum { class, min, max } => {
+                write!(
+                    f,
+                    "Error: Minimum count for {} ({}) exceeds its maximum ({})",
+                    class, min, max
+                )
+            }
+            RpgError::MinimumForDisabledClass(class) => {
+                write!(
+                    f,
+                    "Error: Minimum count for {} is set but no such characters are available",
+                    class
+                )
+            }
+            RpgError::UnsatisfiablePositionRule(position) => {
+                write!(
+                    f,
+                    "Error: No password can satisfy the {} character rule with the current settings",
+                    position
+                )
+            }
+            RpgError::UnsatisfiableRequiredSet(set) => {
+                write!(
+                    f,
+                    "Error: No password can include a character from \"{}\" with the current settings",
+                    set
+                )
+            }
+            RpgError::InvalidRepeatLimit(what) => {
+                write!(f, "Error: The limit on {} must be at least 1", what)
+            }
+            RpgError::RunRulesTooRestrictive => {
+                write!(
+                    f,
+                    "Error: The repetition and sequence rules reject (almost) every password with the current settings"
+                )
+            }
+            RpgError::ByteLimitTooSmall { length, max_bytes } => {
+                write!(
+                    f,
+                    "Error: Passwords of {} characters (almost) never fit in {} bytes of UTF-8 with the current settings",
+                    length, max_bytes
+                )
+            }
+            RpgError::PolicyParse { source, message } => {
+                write!(f, "Error: Failed to parse policy {}: {}", source, message)
+            }
+            RpgError::InvalidPolicyRule { rule, message } => {
+                write!(f, "Error: Invalid policy rule '{}': {}", rule, message)
+            }
+            RpgError::UnknownPreset(name) => {
+                write!(
+                    f,
+                    "Error: Unknown preset '{}' (run 'rpg presets list' to see available presets)",
+                    name
+                )
+            }
+            RpgError::PasswordRulesParse { position, message } => {
+                write!(
+                    f,
+                    "Error: Invalid password rules at position {}: {}",
+                    position, message
+                )
+            }
+            RpgError::PatternParse { position, message } => {
+                write!(
+                    f,
+                    "Error: Invalid pattern at position {}: {}",
+                    position, message
+                )
+            }
+            RpgError::RegexParse { position, message } => {
+                write!(
+                    f,
+                    "Error: Invalid regex at position {}: {}",
+                    position, message
+                )
+            }
+            RpgError::InvalidPinLength(length) => {
+                write!(
+                    f,
+                    "Error: Invalid PIN length: {} (must be between 4 and 10)",
+                    length
+                )
+            }
+            RpgError::InvalidTokenSize(bytes) => {
+                write!(
+                    f,
+                    "Error: Invalid token size: {} bytes (must be between 1 and 1024)",
+                    bytes
+                )
+            }
+            RpgError::UuidSizeFixed(bytes) => {
+                write!(f, "Error: UUIDs are always 16 bytes (got {})", bytes)
+            }
+            RpgError::InvalidKeyFormat(message) => {
+                write!(f, "Error: Invalid key format: {}", message)
+            }
+            RpgError::InvalidKey(message) => write!(f, "Error: Invalid key: {}", message),
             RpgError::InvalidWordCount(count) => {
                 write!(f, "Error: Invalid word count: {}", count)
             }
             RpgError::WordCountTooLarge(count) => {
                 write!(f, "Error: Word count {} is too large (max: 20)", count)
             }
+            RpgError::InvalidEntropyTarget(bits) => {
+                write!(f, "Error: Invalid entropy target: {}", bits)
+            }
+            RpgError::EntropyUnreachable { target, max } => {
+                write!(
+                    f,
+                    "Error: Target entropy of {:.1} bits is unreachable (at most {:.1} bits with the current settings)",
+                    target, max
+                )
+            }
+            RpgError::InsufficientEntropy { actual, required } => {
+                write!(
+                    f,
+                    "Error: Entropy of {:.1} bits is below the required minimum of {:.1} bits",
+                    actual, required
+                )
+            }
+            RpgError::UnverifiableEntropy {
+                upper_bound,
+                required,
+            } => {
+                write!(
+                    f,
+                    "Error: Cannot guarantee the required minimum of {:.1} bits: {:.1} bits is only an upper bound in this mode",
+                    required, upper_bound
+                )
+            }
+            RpgError::FileRead { path, message } => {
+                write!(f, "Error: Failed to read {}: {}", path, message)
+            }
             RpgError::ClipboardError(msg) => {
                 write!(f, "Error: Clipboard operation failed: {}", msg)
             }
@@ -51,6 +276,19 @@ impl fmt::Display for RpgError {
                     count
                 )
             }
+            RpgError::InvalidThreadCount(count) => {
+                write!(f, "Error: Invalid thread count: {} (must be >= 1)", count)
+            }
+            RpgError::NotEnoughUniqueValues {
+                requested,
+                available,
+            } => {
+                write!(
+                    f,
+                    "Error: Cannot generate {} unique values: only {:.0} are possible with the current settings",
+                    requested, available
+                )
+            }
             RpgError::EmptyOutput => {
                 write!(f, "Error: No items to output")
             }
@@ -1,36 +1,1229 @@
-use crate::charset::build_charset;
+use crate::charset::{build_class_charsets, CharClass};
 use crate::config::PasswordConfig;
 use crate::error::Result;
-use rand::seq::SliceRandom;
+use crate::secret::SecretGenerator;
+use rand::distributions::{Distribution, WeightedIndex};
+use rand::{CryptoRng, Rng, RngCore, SeedableRng};
+use rand_chacha::ChaCha20Rng;
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::sync::Arc;
+
+/// 最大出現回数やバイト数の上限を満たす割合を推定するときの標本数
+const ACCEPTANCE_SAMPLES: usize = 2000;
+
+/// 割合を推定するときに候補を引く回数の上限（必須の文字集合をめったに満たさない場合の打ち切り）
+const MAX_ACCEPTANCE_DRAWS: usize = 100 * ACCEPTANCE_SAMPLES;
+
+/// 禁止する連続文字（abc、321）の長さ
+const SEQUENCE_LENGTH: usize = 3;
+
+/// 乱数生成器からまとめて取り出すバイト数
+const RANDOM_BLOCK_SIZE: usize = 64;
+
+/// 文字種ごとの文字数の累積確率を表にしておくパスワード長の上限（表の大きさは長さの2乗に比例）
+const MAX_TABULATED_LENGTH: usize = 128;
+
+/// 繰り返し・連続文字の制約などで候補を引き直す設定で、候補が条件を満たす確率の下限
+///
+/// これを下回る設定は、生成に時間がかかりすぎるため満たせないものとして扱います。
+const MIN_ACCEPTANCE: f64 = 1e-6;
+
+/// `RunSpace`で数え上げる表の大きさ（位置×状態）の上限
+const MAX_RUN_TABLE_SIZE: usize = 1 << 22;
+
+/// `Occupancy`で数え上げる計算量（文字数×文字列長×出現回数）の上限（すべての文字種の合計）
+const MAX_OCCUPANCY_TABLE_SIZE: usize = 1 << 22;
+
+thread_local! {
+    /// 候補の文字列を組み立てる作業領域（生成のたびに確保し直さないため、スレッドごとに使い回す）
+    static CANDIDATE: RefCell<Vec<char>> = const { RefCell::new(Vec::new()) };
+}
 
 /// パスワードジェネレータ
 ///
 /// 文字セットは初期化時にキャッシュされます。
+/// 有効な文字種はそれぞれ最低1文字（最小文字数の指定があればそれ以上）、
+/// 最大文字数の指定があればそれ以下含まれ、その条件を満たす
+/// パスワード全体の中から一様に選ばれます。
 pub struct PasswordGenerator {
     config: PasswordConfig,
-    charset: Vec<char>,
+    candidates: Candidates,
 }
 
 impl PasswordGenerator {
     pub fn new(config: PasswordConfig) -> Result<Self> {
-        config.validate()?;
-        let charset_str = build_charset(&config);
-        let charset: Vec<char> = charset_str.chars().collect();
-        Ok(Self { config, charset })
+        let candidates = config.validated_candidates()?;
+        Ok(Self { config, candidates })
+    }
+
+    /// 生成されるパスワードのエントロピー（ビット）
+    pub fn entropy_bits(&self) -> f64 {
+        self.candidates.entropy_bits()
+    }
+
+    /// `entropy_bits`が条件を満たすパスワードを正確に数えた値か
+    ///
+    /// バイト数の上限がある場合や、最大出現回数と連続回数・連続文字の制約を組み合わせた場合などは
+    /// 推定値なのでfalseです。
+    pub fn entropy_is_exact(&self) -> bool {
+        !self.candidates.estimated
     }
 
     pub fn generate(&self) -> String {
-        let mut rng = rand::thread_rng();
+        self.generate_with(&mut rand::thread_rng())
+    }
+
+    /// 指定された乱数生成器でパスワードを生成
+    pub fn generate_with<R: RngCore + CryptoRng>(&self, rng: &mut R) -> String {
+        let mut password = String::with_capacity(self.config.length);
+        self.generate_into_with(&mut password, rng);
+        password
+    }
+
+    /// パスワードを生成して`buf`の末尾に追加
+    ///
+    /// 候補を組み立てる作業領域はスレッドごとに使い回すため、`buf`も使い回せば
+    /// パスワードごとのメモリ確保は発生しません（最大出現回数を引き直しで確かめる設定を除く）。
+    pub fn generate_into(&self, buf: &mut String) {
+        self.generate_into_with(buf, &mut rand::thread_rng())
+    }
+
+    /// 指定された乱数生成器でパスワードを生成して`buf`の末尾に追加
+    pub fn generate_into_with<R: RngCore + CryptoRng>(&self, buf: &mut String, rng: &mut R) {
+        CANDIDATE.with_borrow_mut(|chars| {
+            // 条件を満たすまで候補全体を引き直す（条件付きでも一様分布のまま）。
+            // 引き直しを伴う制約では、候補が条件を満たす確率が`MIN_ACCEPTANCE`以上であることを
+            // 検証の段階で確認済み
+            loop {
+                chars.clear();
+                self.candidates.sampler.sample_into(chars, rng);
+                if self.candidates.filter.accepts(chars) {
+                    buf.extend(chars.iter());
+                    return;
+                }
+            }
+        })
+    }
+}
+
+impl SecretGenerator for PasswordGenerator {
+    fn entropy_bits(&self) -> f64 {
+        PasswordGenerator::entropy_bits(self)
+    }
+
+    fn entropy_is_exact(&self) -> bool {
+        PasswordGenerator::entropy_is_exact(self)
+    }
+
+    fn generate_with<R: RngCore + CryptoRng>(&self, rng: &mut R) -> String {
+        PasswordGenerator::generate_with(self, rng)
+    }
+}
+
+/// 候補を採用するかの判定（必須の文字集合、繰り返し・連続文字の制約、バイト数の上限）
+struct CandidateFilter {
+    required_sets: Vec<Vec<char>>,
+    max_consecutive: Option<usize>,
+    max_occurrences: Option<usize>,
+    no_sequences: bool,
+    max_bytes: Option<usize>,
+}
+
+impl CandidateFilter {
+    fn from_config(config: &PasswordConfig) -> Self {
+        Self {
+            required_sets: config
+                .required_sets
+                .iter()
+                .map(|set| set.chars().collect())
+                .collect(),
+            max_consecutive: config.max_consecutive,
+            max_occurrences: config.max_occurrences,
+            no_sequences: config.no_sequences,
+            max_bytes: config.max_bytes,
+        }
+    }
+
+    fn accepts(&self, chars: &[char]) -> bool {
+        self.satisfies_required_sets(chars) && self.satisfies_limits(chars)
+    }
+
+    /// 必須の文字集合以外の、引き直しで満たす制約
+    fn satisfies_limits(&self, chars: &[char]) -> bool {
+        self.fits_byte_limit(chars) && self.satisfies_run_rules(chars)
+    }
+
+    fn fits_byte_limit(&self, chars: &[char]) -> bool {
+        self.max_bytes
+            .is_none_or(|max| chars.iter().map(|c| c.len_utf8()).sum::<usize>() <= max)
+    }
+
+    fn satisfies_required_sets(&self, chars: &[char]) -> bool {
+        self.required_sets
+            .iter()
+            .all(|set| chars.iter().any(|c| set.contains(c)))
+    }
+
+    fn satisfies_run_rules(&self, chars: &[char]) -> bool {
+        self.satisfies_local_run_rules(chars) && self.satisfies_occurrence_limit(chars)
+    }
+
+    /// 隣り合う文字だけで決まる制約（連続回数の上限と連続文字の禁止）
+    fn satisfies_local_run_rules(&self, chars: &[char]) -> bool {
+        if let Some(max) = self.max_consecutive {
+            if chars.chunk_by(|a, b| a == b).any(|run| run.len() > max) {
+                return false;
+            }
+        }
+        !(self.no_sequences && chars.windows(SEQUENCE_LENGTH).any(is_sequence))
+    }
+
+    fn satisfies_occurrence_limit(&self, chars: &[char]) -> bool {
+        let Some(max) = self.max_occurrences else {
+            return true;
+        };
+        let mut counts: HashMap<char, usize> = HashMap::new();
+        chars.iter().all(|&c| {
+            let count = counts.entry(c).or_default();
+            *count += 1;
+            *count <= max
+        })
+    }
+}
+
+/// 同じ文字種（英字・数字）で文字コードが1ずつ増える、または減る並びか
+fn is_sequence(window: &[char]) -> bool {
+    let class = CharClass::of(window[0]);
+    if class == CharClass::Symbol || window.iter().any(|&c| CharClass::of(c) != class) {
+        return false;
+    }
+    let delta = window[1] as i64 - window[0] as i64;
+    delta.abs() == 1
+        && window
+            .windows(2)
+            .all(|pair| pair[1] as i64 - pair[0] as i64 == delta)
+}
+
+/// 制約を満たすパスワードの総数の対数（自然対数）。実現不可能な制約ではNone
+///
+/// バイト数の上限がある場合などは、それを満たす割合の推定値を
+/// 掛けた値になります（`Candidates::from_config`を参照）。
+pub(crate) fn log_space_size(config: &PasswordConfig) -> Option<f64> {
+    Candidates::from_config(config).map(|candidates| candidates.log_size)
+}
+
+/// 候補の選び方と、条件を満たすパスワードの総数
+pub(crate) struct Candidates {
+    sampler: Sampler,
+    filter: CandidateFilter,
+    /// 条件を満たすパスワードの総数の対数（自然対数）
+    log_size: f64,
+    /// `log_size`が標本から推定した値か
+    estimated: bool,
+}
+
+/// 候補の選び方
+enum Sampler {
+    /// 文字種ごとの文字数と位置の指定を満たす文字列から選ぶ
+    Classes(PasswordSpace),
+    /// 繰り返し・連続文字の制約と位置の指定を満たす文字列から選ぶ
+    Runs(RunSpace),
+}
+
+impl Sampler {
+    fn log_size(&self) -> f64 {
+        match self {
+            Sampler::Classes(space) => space.log_size(),
+            Sampler::Runs(space) => space.log_size,
+        }
+    }
+
+    fn sample_into<R: Rng + ?Sized>(&self, chars: &mut Vec<char>, rng: &mut R) {
+        match self {
+            Sampler::Classes(space) => space.sample_into(chars, rng),
+            Sampler::Runs(space) => space.sample_into(chars, rng),
+        }
+    }
+}
+
+impl Candidates {
+    /// 条件を満たすパスワードのエントロピー（ビット）
+    pub(crate) fn entropy_bits(&self) -> f64 {
+        self.log_size / std::f64::consts::LN_2
+    }
+
+    /// 候補の選び方を決め、条件を満たすパスワードの総数を数える。実現不可能ならNone
+    ///
+    /// 最大出現回数は`Occupancy`で、連続回数の上限と連続文字の禁止は、文字種ごとの文字数に
+    /// 「1文字以上」以外の指定が無ければ`RunSpace`で正確に数えます（両方ある場合は最大出現回数を
+    /// 数え上げ、残りは推定）。バイト数の上限と、正確に数えられない組み合わせは、
+    /// 条件を満たす割合を固定シードの標本から推定します（同じ設定では毎回同じ値）。
+    pub(crate) fn from_config(config: &PasswordConfig) -> Option<Self> {
+        let classes = PasswordSpace::from_config(config);
+        if !classes.is_feasible() {
+            return None;
+        }
+        let counts_occurrences = classes.counts_occurrences();
+        let mut filter = CandidateFilter::from_config(config);
+
+        let runs = if counts_occurrences {
+            None
+        } else {
+            RunSpace::from_config(config)
+        };
+        let exact_runs = runs.is_some() || !RunSpace::is_needed(config);
+        let (sampler, log_base) = match runs {
+            Some(runs) => {
+                // 文字種の「1文字以上」も必須の文字集合として包除原理で数える
+                filter.required_sets.extend(
+                    build_class_charsets(config)
+                        .into_iter()
+                        .filter(|charset| config.count_range(charset).0 == 1)
+                        .map(|charset| charset.chars),
+                );
+                let log_base = runs.log_size_with_required_sets(&filter.required_sets);
+                if log_base == f64::NEG_INFINITY {
+                    return None;
+                }
+                // 候補全体が小さいほうが、引き直しが少なく済む
+                let sampler = if runs.log_size <= classes.log_size() {
+                    Sampler::Runs(runs)
+                } else {
+                    Sampler::Classes(classes)
+                };
+                (sampler, log_base)
+            }
+            None => (
+                Sampler::Classes(classes),
+                log_required_sets_space_size(config)?,
+            ),
+        };
+
+        let estimated = !exact_runs
+            || (config.max_occurrences.is_some() && !counts_occurrences)
+            || config.max_bytes.is_some();
+        let log_size = if estimated {
+            log_base + estimate_acceptance(&sampler, &filter, exact_runs)?.ln()
+        } else {
+            log_base
+        };
+        // 生成時の引き直しの回数（の期待値）を現実的な範囲に抑える
+        if config.has_rejection_rules() && log_size - sampler.log_size() < MIN_ACCEPTANCE.ln() {
+            return None;
+        }
+
+        Some(Self {
+            sampler,
+            filter,
+            log_size,
+            estimated,
+        })
+    }
+}
+
+/// 正確に数えた制約（必須の文字集合、`runs_counted`なら連続回数と連続文字も）を満たす候補のうち、
+/// 残りの制約も満たす割合の推定値
+///
+/// 固定シードで標本を取るため、同じ設定では毎回同じ値になります。
+/// 1つも満たさなければNone。
+fn estimate_acceptance(
+    sampler: &Sampler,
+    filter: &CandidateFilter,
+    runs_counted: bool,
+) -> Option<f64> {
+    let mut rng = ChaCha20Rng::seed_from_u64(0);
+    let mut chars = Vec::new();
+
+    let (mut samples, mut accepted) = (0, 0);
+    for _ in 0..MAX_ACCEPTANCE_DRAWS {
+        if samples == ACCEPTANCE_SAMPLES {
+            break;
+        }
+        chars.clear();
+        sampler.sample_into(&mut chars, &mut rng);
+        if !filter.satisfies_required_sets(&chars)
+            || (runs_counted && !filter.satisfies_local_run_rules(&chars))
+        {
+            continue;
+        }
+        samples += 1;
+        accepted += filter.satisfies_limits(&chars) as usize;
+    }
+
+    (accepted > 0).then(|| accepted as f64 / samples as f64)
+}
+
+/// 必須の文字集合を満たすパスワードの総数の対数（自然対数）
+///
+/// 包除原理で数えます。集合の部分集合 T ごとに
+/// T の文字を除外した設定のパスワード数を符号付きで足し合わせます。
+pub(crate) fn log_required_sets_space_size(config: &PasswordConfig) -> Option<f64> {
+    let base = PasswordSpace::from_config(config);
+    let log_base = base.log_size();
+    if log_base == f64::NEG_INFINITY {
+        return None;
+    }
+
+    let sets = &config.required_sets;
+    let mut fraction = 0.0;
+    for mask in 0u32..(1 << sets.len()) {
+        let removed: String = (0..sets.len())
+            .filter(|i| mask & (1 << i) != 0)
+            .map(|i| sets[i].as_str())
+            .collect();
+        let reduced = PasswordConfig {
+            exclude_chars: format!("{}{}", config.exclude_chars, removed),
+            required_sets: Vec::new(),
+            max_consecutive: None,
+            // 文字を除外すると表は小さくなるため、元の設定で数え上げていれば同じく数え上げる
+            max_occurrences: config.max_occurrences.filter(|_| base.counts_occurrences()),
+            no_sequences: false,
+            max_bytes: None,
+            ..config.clone()
+        };
+        // 除外によって必須の文字種などが満たせなくなった場合は0通り
+        let log_size = if mask == 0 {
+            log_base
+        } else if reduced.validate().is_err() {
+            continue;
+        } else {
+            PasswordSpace::from_config(&reduced).log_size()
+        };
+        let sign = if mask.count_ones() % 2 == 0 {
+            1.0
+        } else {
+            -1.0
+        };
+        fraction += sign * (log_size - log_base).exp();
+    }
+
+    // 丸め誤差で0にならない場合に備え、極端に小さい割合は実現不可能とみなす
+    (fraction > 1e-9).then(|| log_base + fraction.ln())
+}
+
+/// 1つの文字種の文字セットと、パスワード中に含める文字数の範囲
+#[derive(Clone)]
+struct ClassPool {
+    class: CharClass,
+    chars: Vec<char>,
+    min: usize,
+    max: usize,
+    /// 両端に固定した位置のうち、この文字種の数（`min`・`max`には含まない）
+    fixed: usize,
+    /// 最大出現回数を数え上げる場合の、この文字種の文字列の数
+    occupancy: Option<Arc<Occupancy>>,
+}
+
+/// 各文字の出現回数が上限以下の文字列を数え上げ、一様に選ぶ
+///
+/// 長さ r の文字列で各文字が m 回以下のものは `r! * [x^r] (Σ_{j<=m} x^j/j!)^s` 通り
+/// （s は文字セットの大きさ）。文字ごとに出現回数をこの数に比例して選び、
+/// 並びをシャッフルすると、そのような文字列全体の一様分布になります。
+struct Occupancy {
+    /// 1文字あたりの出現回数の上限
+    max: usize,
+    /// `log_ways[i][r]`: i 文字で長さ r を埋める重み `[x^r] (Σ_{j<=m} x^j/j!)^i` の対数
+    log_ways: Vec<Vec<f64>>,
+    /// `log_factorial[n]`: ln(n!)
+    log_factorial: Vec<f64>,
+}
+
+impl Occupancy {
+    /// 計算量の見積もり（`MAX_OCCUPANCY_TABLE_SIZE`と比べる）
+    fn cost(chars: usize, length: usize, max: usize) -> usize {
+        (chars + 1)
+            .saturating_mul(length + 1)
+            .saturating_mul(max.min(length) + 1)
+    }
+
+    fn new(chars: usize, length: usize, max: usize) -> Self {
+        let max = max.min(length);
+        let mut log_factorial = vec![0.0; length + 1];
+        for n in 1..=length {
+            log_factorial[n] = log_factorial[n - 1] + (n as f64).ln();
+        }
+
+        let mut log_ways = vec![vec![f64::NEG_INFINITY; length + 1]; chars + 1];
+        log_ways[0][0] = 0.0;
+        for i in 1..=chars {
+            for r in 0..=length {
+                let terms: Vec<f64> = (0..=max.min(r))
+                    .map(|j| log_ways[i - 1][r - j] - log_factorial[j])
+                    .collect();
+                log_ways[i][r] = log_sum_exp(&terms);
+            }
+        }
+
+        Self {
+            max,
+            log_ways,
+            log_factorial,
+        }
+    }
+
+    /// 各文字の出現回数が上限以下で長さ`length`の文字列の数の対数（自然対数）
+    fn log_words(&self, length: usize) -> f64 {
+        match self.log_ways.last().and_then(|ways| ways.get(length)) {
+            Some(log_ways) => log_ways + self.log_factorial[length],
+            None => f64::NEG_INFINITY,
+        }
+    }
+
+    /// 出現回数の組を選び、`pool`の文字をその回数ずつ`chars`に追加する（並びはシャッフルしない）
+    fn sample_into<R: Rng + ?Sized>(
+        &self,
+        pool: &[char],
+        length: usize,
+        chars: &mut Vec<char>,
+        indices: &mut RandomIndices<'_, R>,
+    ) {
+        let mut remaining = length;
+        for (letter, &c) in pool.iter().enumerate() {
+            if remaining == 0 {
+                break;
+            }
+            let rest = &self.log_ways[pool.len() - letter - 1];
+            let total = self.log_ways[pool.len() - letter][remaining];
+            let mut u = indices.unit();
+            let mut chosen = None;
+            for j in 0..=self.max.min(remaining) {
+                let log_weight = rest[remaining - j] - self.log_factorial[j];
+                if log_weight == f64::NEG_INFINITY {
+                    continue;
+                }
+                // 浮動小数点の丸めに備え、最後の有効な候補を保持しておく
+                chosen = Some(j);
+                u -= (log_weight - total).exp();
+                if u < 0.0 {
+                    break;
+                }
+            }
+            let count = chosen.expect("validated config always has a feasible occurrence count");
+            chars.extend(std::iter::repeat_n(c, count));
+            remaining -= count;
+        }
+    }
+}
+
+/// 設定の制約を満たすパスワード全体
+///
+/// 先頭・末尾の文字種の指定がある場合は、両端の文字種の組（レイアウト）ごとに
+/// 残りの位置を`ClassCountSampler`で数え上げ、その総数に比例してレイアウトを選びます。
+/// 指定が無ければレイアウトは1つだけで、乱数の消費も従来と変わりません。
+/// 必須の文字集合や繰り返し・連続文字の制約はここでは考慮しません。
+pub(crate) struct PasswordSpace {
+    layouts: Vec<Layout>,
+    /// 最大出現回数を`Occupancy`で数え上げているか
+    counts_occurrences: bool,
+    /// レイアウトが複数ある場合の、総数に比例した選び方
+    layout_index: Option<WeightedIndex<f64>>,
+}
+
+/// 両端の文字種を固定した場合のパスワードの集合
+struct Layout {
+    /// 先頭に置く文字種（`interior.pools`の添字）
+    first: Option<usize>,
+    /// 末尾に置く文字種（`interior.pools`の添字）
+    last: Option<usize>,
+    /// 両端を除いた位置の文字数の分布
+    interior: ClassCountSampler,
+    /// このレイアウトのパスワードの総数の対数（自然対数）
+    log_size: f64,
+}
+
+impl PasswordSpace {
+    pub(crate) fn from_config(config: &PasswordConfig) -> Self {
+        let mut pools: Vec<ClassPool> = build_class_charsets(config)
+            .into_iter()
+            .filter(|charset| !charset.chars.is_empty())
+            .map(|charset| {
+                let (min, max) = config.count_range(&charset);
+                ClassPool {
+                    class: charset.class,
+                    chars: charset.chars,
+                    min,
+                    max,
+                    fixed: 0,
+                    occupancy: None,
+                }
+            })
+            .collect();
+
+        // 最大出現回数は、表が大きすぎなければ文字種ごとに数え上げる
+        let occupancy_length = |pool: &ClassPool| pool.max.min(config.length);
+        let counts_occurrences = config.max_occurrences.is_some_and(|max| {
+            pools
+                .iter()
+                .map(|pool| Occupancy::cost(pool.chars.len(), occupancy_length(pool), max))
+                .fold(0, usize::saturating_add)
+                <= MAX_OCCUPANCY_TABLE_SIZE
+        });
+        if let Some(max) = config.max_occurrences.filter(|_| counts_occurrences) {
+            for pool in &mut pools {
+                let length = occupancy_length(pool);
+                pool.occupancy = Some(Arc::new(Occupancy::new(pool.chars.len(), length, max)));
+            }
+        }
+
+        // 位置の指定が無ければNone、あれば指定された文字種の文字セットの添字
+        let candidates = |rule: &Option<Vec<CharClass>>| -> Vec<Option<usize>> {
+            match rule {
+                None => vec![None],
+                Some(classes) => (0..pools.len())
+                    .filter(|&i| classes.contains(&pools[i].class))
+                    .map(Some)
+                    .collect(),
+            }
+        };
+
+        let mut layouts = Vec::new();
+        for first in candidates(&config.start_with) {
+            for last in candidates(&config.end_with) {
+                layouts.extend(Layout::new(&pools, config.length, first, last));
+            }
+        }
+
+        let layout_index = (layouts.len() > 1).then(|| {
+            let max = layouts
+                .iter()
+                .map(|l| l.log_size)
+                .fold(f64::NEG_INFINITY, f64::max);
+            WeightedIndex::new(layouts.iter().map(|l| (l.log_size - max).exp()))
+                .expect("feasible layouts have positive weights")
+        });
+
+        Self {
+            layouts,
+            counts_occurrences,
+            layout_index,
+        }
+    }
+
+    /// 最大出現回数を正確に数えているか（表が大きすぎる場合は数えない）
+    pub(crate) fn counts_occurrences(&self) -> bool {
+        self.counts_occurrences
+    }
+
+    /// 制約を満たすパスワードが存在するか
+    pub(crate) fn is_feasible(&self) -> bool {
+        !self.layouts.is_empty()
+    }
+
+    /// 制約を満たすパスワードの総数の対数（自然対数）。実現不可能な制約では負の無限大
+    fn log_size(&self) -> f64 {
+        let log_sizes: Vec<f64> = self.layouts.iter().map(|l| l.log_size).collect();
+        log_sum_exp(&log_sizes)
+    }
+
+    /// パスワードを1つ選び、文字を`chars`に追加する
+    fn sample_into<R: Rng + ?Sized>(&self, chars: &mut Vec<char>, rng: &mut R) {
+        let layout = match &self.layout_index {
+            None => self
+                .layouts
+                .first()
+                .expect("validated config always has a feasible layout"),
+            Some(index) => &self.layouts[index.sample(rng)],
+        };
+
+        // 文字種ごとの文字数を決めてから各文字を選び、位置をシャッフルする
+        let start = chars.len();
+        let pools = &layout.interior.pools;
+        let counts = layout.interior.sample_counts(rng);
+        let mut indices = RandomIndices::new(rng);
+        if self.counts_occurrences {
+            Self::sample_occupancy_into(layout, &counts, chars, &mut indices);
+            return;
+        }
+        for (pool, &count) in pools.iter().zip(&counts) {
+            chars.extend((0..count).map(|_| indices.choose(&pool.chars)));
+        }
+        indices.shuffle(&mut chars[start..]);
+
+        if let Some(first) = layout.first {
+            chars.insert(start, indices.choose(&pools[first].chars));
+        }
+        if let Some(last) = layout.last {
+            chars.push(indices.choose(&pools[last].chars));
+        }
+    }
+
+    /// 最大出現回数を数え上げている場合の`sample_into`
+    ///
+    /// 文字種ごとに、両端に固定した位置の分も含めた文字列の文字を選び、
+    /// その中から両端の文字を一様に取り出します。
+    fn sample_occupancy_into<R: Rng + ?Sized>(
+        layout: &Layout,
+        counts: &[usize],
+        chars: &mut Vec<char>,
+        indices: &mut RandomIndices<'_, R>,
+    ) {
+        let start = chars.len();
+        let (mut first, mut last) = (None, None);
+        for (k, (pool, &count)) in layout.interior.pools.iter().zip(counts).enumerate() {
+            let segment = chars.len();
+            let occupancy = pool
+                .occupancy
+                .as_ref()
+                .expect("every pool counts occurrences");
+            occupancy.sample_into(&pool.chars, count + pool.fixed, chars, indices);
+            for (end, slot) in [(layout.first, &mut first), (layout.last, &mut last)] {
+                if end == Some(k) {
+                    let index = segment + indices.index(chars.len() - segment);
+                    *slot = Some(chars.swap_remove(index));
+                }
+            }
+        }
+        indices.shuffle(&mut chars[start..]);
+
+        if let Some(first) = first {
+            chars.insert(start, first);
+        }
+        if let Some(last) = last {
+            chars.push(last);
+        }
+    }
+}
+
+/// 乱数のバイト列をまとめて取得し、添字を一様に選ぶ
+///
+/// 大きさが256以下の範囲はバイトの棄却サンプリングで選ぶため偏りがなく、
+/// 1文字ごとに乱数生成器を呼び出すより速く選べます。
+struct RandomIndices<'a, R: ?Sized> {
+    rng: &'a mut R,
+    block: [u8; RANDOM_BLOCK_SIZE],
+    position: usize,
+}
+
+impl<'a, R: Rng + ?Sized> RandomIndices<'a, R> {
+    fn new(rng: &'a mut R) -> Self {
+        Self {
+            rng,
+            block: [0; RANDOM_BLOCK_SIZE],
+            position: RANDOM_BLOCK_SIZE,
+        }
+    }
+
+    fn byte(&mut self) -> u8 {
+        if self.position == RANDOM_BLOCK_SIZE {
+            self.rng.fill_bytes(&mut self.block);
+            self.position = 0;
+        }
+        self.position += 1;
+        self.block[self.position - 1]
+    }
+
+    /// `0..n`から一様に選ぶ
+    fn index(&mut self, n: usize) -> usize {
+        if n > 256 {
+            return self.rng.gen_range(0..n);
+        }
+        // 256をnで割り切れる範囲に収まったバイトだけを使う
+        let zone = 256 - 256 % n;
+        loop {
+            let byte = self.byte() as usize;
+            if byte < zone {
+                return byte % n;
+            }
+        }
+    }
+
+    /// [0, 1)の一様な実数
+    fn unit(&mut self) -> f64 {
+        self.rng.gen()
+    }
+
+    fn choose<T: Copy>(&mut self, items: &[T]) -> T {
+        items[self.index(items.len())]
+    }
+
+    /// Fisher–Yatesのシャッフル
+    fn shuffle<T>(&mut self, items: &mut [T]) {
+        for i in (1..items.len()).rev() {
+            let j = self.index(i + 1);
+            items.swap(i, j);
+        }
+    }
+}
+
+impl Layout {
+    /// 両端の文字種を固定したレイアウト。実現不可能ならNone
+    fn new(
+        pools: &[ClassPool],
+        length: usize,
+        first: Option<usize>,
+        last: Option<usize>,
+    ) -> Option<Self> {
+        // 1文字のパスワードでは先頭と末尾は同じ位置
+        let last = match (length, first, last) {
+            (1, Some(first), Some(last)) if first != last => return None,
+            (1, Some(_), Some(_)) => None,
+            _ => last,
+        };
+
+        let fixed: Vec<usize> = first.into_iter().chain(last).collect();
+        if fixed.len() > length {
+            return None;
+        }
+
+        let mut interior_pools = pools.to_vec();
+        let mut log_size = 0.0;
+        for &index in &fixed {
+            let pool = &mut interior_pools[index];
+            if pool.max == 0 {
+                return None;
+            }
+            pool.min = pool.min.saturating_sub(1);
+            pool.max -= 1;
+            pool.fixed += 1;
+            // 出現回数を数え上げる場合は、固定した位置も文字種の文字列の重みに含める
+            if pool.occupancy.is_none() {
+                log_size += (pool.chars.len() as f64).ln();
+            }
+        }
+
+        let interior = ClassCountSampler::new(interior_pools, length - fixed.len());
+        let log_size = log_size + interior.log_space_size();
+        if log_size == f64::NEG_INFINITY {
+            return None;
+        }
+
+        Some(Self {
+            first,
+            last,
+            interior,
+            log_size,
+        })
+    }
+}
+
+/// 文字種ごとの文字数の組を、それを満たすパスワードの数に比例した確率で選ぶ
+///
+/// 文字数 n_i の組を満たすパスワードは `L! * Π(s_i^n_i / n_i!)` 通り（s_i は文字セットの大きさ）。
+/// この重みで組を選び、各文字を一様に選んでシャッフルすると、
+/// 制約を満たすパスワード全体の一様分布になります。
+/// 最大出現回数を数え上げる場合は `s_i^n_i` の代わりに`Occupancy`の文字列の数を使います。
+struct ClassCountSampler {
+    pools: Vec<ClassPool>,
+    length: usize,
+    /// `log_tail[k][r]`: 文字種 k 以降で残り r 文字を埋める重みの総和（対数）
+    log_tail: Vec<Vec<f64>>,
+    /// `log_factorial[n]`: ln(n!)
+    log_factorial: Vec<f64>,
+    /// `count_cdf[k][r]`: 文字種 k の文字数を`count_range`の順に選ぶ累積確率（残り r 文字）
+    ///
+    /// 長さが`MAX_TABULATED_LENGTH`を超える場合は空で、選ぶたびに計算します。
+    count_cdf: Vec<Vec<Vec<f64>>>,
+}
+
+impl ClassCountSampler {
+    fn new(pools: Vec<ClassPool>, length: usize) -> Self {
+        let mut log_factorial = vec![0.0; length + 1];
+        for n in 1..=length {
+            log_factorial[n] = log_factorial[n - 1] + (n as f64).ln();
+        }
+
+        let tabulate = length <= MAX_TABULATED_LENGTH;
+        let mut log_tail = vec![vec![f64::NEG_INFINITY; length + 1]; pools.len() + 1];
+        let mut count_cdf =
+            vec![vec![Vec::new(); length + 1]; if tabulate { pools.len() } else { 0 }];
+        log_tail[pools.len()][0] = 0.0;
+        for k in (0..pools.len()).rev() {
+            for r in 0..=length {
+                let terms: Vec<f64> = Self::count_range(&pools[k], r)
+                    .map(|n| {
+                        Self::log_class_weight(&pools[k], n, &log_factorial)
+                            + log_tail[k + 1][r - n]
+                    })
+                    .collect();
+                log_tail[k][r] = log_sum_exp(&terms);
+                if tabulate && log_tail[k][r] > f64::NEG_INFINITY {
+                    count_cdf[k][r] = cumulative_probabilities(&terms, log_tail[k][r]);
+                }
+            }
+        }
+
+        Self {
+            pools,
+            length,
+            log_tail,
+            log_factorial,
+            count_cdf,
+        }
+    }
+
+    /// 制約を満たす文字列の総数の対数（自然対数）。実現不可能な制約では負の無限大
+    fn log_space_size(&self) -> f64 {
+        self.log_tail[0][self.length] + self.log_factorial[self.length]
+    }
+
+    fn count_range(pool: &ClassPool, remaining: usize) -> std::ops::RangeInclusive<usize> {
+        pool.min..=pool.max.min(remaining)
+    }
+
+    fn log_class_weight(pool: &ClassPool, n: usize, log_factorial: &[f64]) -> f64 {
+        match &pool.occupancy {
+            None => n as f64 * (pool.chars.len() as f64).ln() - log_factorial[n],
+            Some(occupancy) => occupancy.log_words(n + pool.fixed) - log_factorial[n],
+        }
+    }
+
+    /// 文字種ごとの文字数を選ぶ（合計は常にパスワード長に一致）
+    ///
+    /// 文字種の数は高々`CharClass::ALL.len()`なので、配列で返します（残りは0）。
+    fn sample_counts<R: Rng + ?Sized>(&self, rng: &mut R) -> [usize; CharClass::ALL.len()] {
+        let mut remaining = self.length;
+        let mut counts = [0; CharClass::ALL.len()];
+
+        for (k, pool) in self.pools.iter().enumerate() {
+            if let Some(cdf) = self.count_cdf.get(k) {
+                // 累積確率が最後に1になるよう正規化してあるので、必ずどれかが選ばれる
+                let u: f64 = rng.gen();
+                let n = pool.min + cdf[remaining].partition_point(|&p| p <= u);
+                counts[k] = n;
+                remaining -= n;
+                continue;
+            }
+
+            let total = self.log_tail[k][remaining];
+            let mut u: f64 = rng.gen();
+            let mut chosen = None;
+            for n in Self::count_range(pool, remaining) {
+                let log_weight = Self::log_class_weight(pool, n, &self.log_factorial)
+                    + self.log_tail[k + 1][remaining - n];
+                if log_weight == f64::NEG_INFINITY {
+                    continue;
+                }
+                // 浮動小数点の丸めに備え、最後の有効な候補を保持しておく
+                chosen = Some(n);
+                u -= (log_weight - total).exp();
+                if u < 0.0 {
+                    break;
+                }
+            }
+            let n = chosen.expect("validated config always has a feasible class count");
+            counts[k] = n;
+            remaining -= n;
+        }
+
+        counts
+    }
+}
+
+/// 直前の文字からの連続の向き（`RunSpace`の状態）
+const NO_STEP: usize = 0;
+const STEP_UP: usize = 1;
+const STEP_DOWN: usize = 2;
+
+/// 連続回数の上限と連続文字の禁止を満たす文字列全体
+///
+/// 各位置の状態（その位置の文字、同じ文字の連続回数、直前の文字からの連続の向き）ごとに、
+/// 残りの位置の埋め方の数を末尾から数え上げます（動的計画法）。
+/// その数に比例して先頭から1文字ずつ選ぶと、制約を満たす文字列全体の一様分布になります。
+/// 文字種ごとの文字数は考慮しません。
+struct RunSpace {
+    chars: Vec<char>,
+    length: usize,
+    /// 先頭・末尾に置ける文字（位置の指定が無ければすべて）
+    first: Vec<bool>,
+    last: Vec<bool>,
+    /// 同じ文字の連続回数の上限（長さ以上なら制約なし）
+    max_run: Option<usize>,
+    /// 連続文字の禁止があれば、コードが1大きい・小さい同じ文字種の文字
+    up: Vec<Option<usize>>,
+    down: Vec<Option<usize>>,
+    /// 状態の連続回数・向きの数
+    runs: usize,
+    steps: usize,
+    /// `completions[i][state]`: 位置iの状態から末尾までの埋め方の数（位置ごとに正規化）
+    completions: Vec<Vec<f64>>,
+    /// 文字列の総数の対数（自然対数）。実現不可能な制約では負の無限大
+    log_size: f64,
+}
+
+impl RunSpace {
+    /// 隣り合う文字だけで決まる制約（連続回数の上限と連続文字の禁止）があるか
+    fn is_needed(config: &PasswordConfig) -> bool {
+        config
+            .max_consecutive
+            .is_some_and(|max| max < config.length)
+            || config.no_sequences
+    }
+
+    /// 設定の文字列全体。制約が無い場合、文字種ごとの文字数に「1文字以上」以外の指定がある場合、
+    /// 表が大きすぎる場合はNone
+    fn from_config(config: &PasswordConfig) -> Option<Self> {
+        if !Self::is_needed(config) {
+            return None;
+        }
+
+        let mut chars = Vec::new();
+        let mut classes = Vec::new();
+        for charset in build_class_charsets(config) {
+            let (min, max) = config.count_range(&charset);
+            if charset.chars.is_empty() {
+                continue;
+            }
+            if min > 1 || max < config.length {
+                return None;
+            }
+            classes.extend(std::iter::repeat_n(charset.class, charset.chars.len()));
+            chars.extend(charset.chars);
+        }
+
+        let position_mask = |rule: &Option<Vec<CharClass>>| -> Vec<bool> {
+            classes
+                .iter()
+                .map(|class| rule.as_ref().is_none_or(|allowed| allowed.contains(class)))
+                .collect()
+        };
+        let index: HashMap<char, usize> = chars.iter().enumerate().map(|(i, &c)| (c, i)).collect();
+        let neighbour = |c: char, step: i32| -> Option<usize> {
+            let next = char::from_u32((c as u32).checked_add_signed(step)?)?;
+            let class = CharClass::of(c);
+            (config.no_sequences && class != CharClass::Symbol && CharClass::of(next) == class)
+                .then(|| index.get(&next).copied())
+                .flatten()
+        };
+
+        let up: Vec<Option<usize>> = chars.iter().map(|&c| neighbour(c, 1)).collect();
+        let down: Vec<Option<usize>> = chars.iter().map(|&c| neighbour(c, -1)).collect();
+        let max_run = config.max_consecutive.filter(|&max| max < config.length);
+        // 連続文字になる組が無ければ向きは区別しない
+        let steps = if up.iter().chain(&down).any(Option::is_some) {
+            3
+        } else {
+            1
+        };
+        let mut space = Self {
+            first: position_mask(&config.start_with),
+            last: position_mask(&config.end_with),
+            chars,
+            length: config.length,
+            max_run,
+            up,
+            down,
+            runs: max_run.unwrap_or(1),
+            steps,
+            completions: Vec::new(),
+            log_size: f64::NEG_INFINITY,
+        };
+        if space.states().saturating_mul(space.length) > MAX_RUN_TABLE_SIZE {
+            return None;
+        }
+        let present = vec![true; space.chars.len()];
+        let mut completions = Vec::with_capacity(space.length);
+        space.log_size = space.log_count(&present, Some(&mut completions));
+        space.completions = completions;
+        Some(space)
+    }
+
+    fn states(&self) -> usize {
+        self.chars.len() * self.runs * self.steps
+    }
+
+    fn state(&self, c: usize, run: usize, step: usize) -> usize {
+        (c * self.runs + run) * self.steps + step
+    }
 
-        (0..self.config.length)
-            .map(|_| *self.charset.choose(&mut rng).unwrap())
-            .collect()
+    fn allowed_at(&self, position: usize, c: usize) -> bool {
+        (position != 0 || self.first[c]) && (position != self.length - 1 || self.last[c])
     }
+
+    /// 状態`(c, run, step)`の直後に文字`next`を置いた場合の状態。置けなければNone
+    fn successor(
+        &self,
+        (c, run, step): (usize, usize, usize),
+        next: usize,
+    ) -> Option<(usize, usize, usize)> {
+        if next == c {
+            return match self.max_run {
+                Some(max) if run + 1 >= max => None,
+                Some(_) => Some((c, run + 1, NO_STEP)),
+                None => Some((c, 0, NO_STEP)),
+            };
+        }
+        let next_step = if self.up[c] == Some(next) {
+            STEP_UP
+        } else if self.down[c] == Some(next) {
+            STEP_DOWN
+        } else {
+            NO_STEP
+        };
+        // 同じ向きに2回続くと3文字の連続文字になる
+        (next_step == NO_STEP || next_step != step).then_some((next, 0, next_step))
+    }
+
+    /// `present`の文字だけを使う文字列の総数の対数（自然対数）
+    ///
+    /// `completions`を渡すと、位置ごとの埋め方の数（各位置で最大値が1になるよう正規化）を残します。
+    fn log_count(&self, present: &[bool], mut completions: Option<&mut Vec<Vec<f64>>>) -> f64 {
+        let n = self.chars.len();
+        // 末尾の位置の後には埋める位置が残っていない
+        let mut next = vec![1.0; self.states()];
+        let mut log_scale = 0.0;
+        if let Some(levels) = completions.as_deref_mut() {
+            levels.push(next.clone());
+        }
+
+        for position in (0..self.length - 1).rev() {
+            let allowed: Vec<bool> = (0..n)
+                .map(|c| present[c] && self.allowed_at(position + 1, c))
+                .collect();
+            let fresh = |c: usize, step: usize| {
+                if allowed[c] {
+                    next[self.state(c, 0, step)]
+                } else {
+                    0.0
+                }
+            };
+            let total: f64 = (0..n).map(|c| fresh(c, NO_STEP)).sum();
+
+            let mut current = vec![0.0; self.states()];
+            for c in 0..n {
+                let neighbours = [(self.up[c], STEP_UP), (self.down[c], STEP_DOWN)];
+                // 別の文字を置く場合（連続文字になる文字は向き付きの状態で数え直す）
+                let mut other = total - fresh(c, NO_STEP);
+                for (neighbour, _) in neighbours {
+                    if let Some(d) = neighbour {
+                        other -= fresh(d, NO_STEP);
+                    }
+                }
+                for run in 0..self.runs {
+                    let repeat = match self.successor((c, run, NO_STEP), c) {
+                        Some((_, next_run, _)) if allowed[c] => {
+                            next[self.state(c, next_run, NO_STEP)]
+                        }
+                        _ => 0.0,
+                    };
+                    for step in 0..self.steps {
+                        let mut sum = other + repeat;
+                        for (neighbour, neighbour_step) in neighbours {
+                            if let Some(d) = neighbour.filter(|_| neighbour_step != step) {
+                                sum += fresh(d, neighbour_step);
+                            }
+                        }
+                        current[self.state(c, run, step)] = sum.max(0.0);
+                    }
+                }
+            }
+
+            let max = current.iter().copied().fold(0.0, f64::max);
+            if max > 0.0 {
+                current.iter_mut().for_each(|w| *w /= max);
+                log_scale += max.ln();
+            }
+            if let Some(levels) = completions.as_deref_mut() {
+                levels.push(current.clone());
+            }
+            next = current;
+        }
+
+        if let Some(levels) = completions {
+            levels.reverse();
+        }
+        let total: f64 = (0..n)
+            .filter(|&c| present[c] && self.allowed_at(0, c))
+            .map(|c| next[self.state(c, 0, NO_STEP)])
+            .sum();
+        total.ln() + log_scale
+    }
+
+    /// 必須の文字集合をそれぞれ1文字以上含む文字列の総数の対数（自然対数）
+    ///
+    /// `log_required_sets_space_size`と同じく包除原理で数えます。
+    fn log_size_with_required_sets(&self, sets: &[Vec<char>]) -> f64 {
+        if self.log_size == f64::NEG_INFINITY {
+            return f64::NEG_INFINITY;
+        }
+        let mut fraction = 0.0;
+        for mask in 0u32..(1 << sets.len()) {
+            let log_size = if mask == 0 {
+                self.log_size
+            } else {
+                let present: Vec<bool> = self
+                    .chars
+                    .iter()
+                    .map(|c| !(0..sets.len()).any(|i| mask & (1 << i) != 0 && sets[i].contains(c)))
+                    .collect();
+                self.log_count(&present, None)
+            };
+            let sign = if mask.count_ones() % 2 == 0 {
+                1.0
+            } else {
+                -1.0
+            };
+            fraction += sign * (log_size - self.log_size).exp();
+        }
+
+        // 丸め誤差で0にならない場合に備え、極端に小さい割合は実現不可能とみなす
+        if fraction > 1e-9 {
+            self.log_size + fraction.ln()
+        } else {
+            f64::NEG_INFINITY
+        }
+    }
+
+    /// 文字列を1つ選び、文字を`chars`に追加する
+    fn sample_into<R: Rng + ?Sized>(&self, chars: &mut Vec<char>, rng: &mut R) {
+        let n = self.chars.len();
+        let mut state = None;
+        for (position, level) in self.completions.iter().enumerate() {
+            let candidates = (0..n)
+                .filter(|&c| self.allowed_at(position, c))
+                .filter_map(|c| match state {
+                    None => Some((c, 0, NO_STEP)),
+                    Some(state) => self.successor(state, c),
+                });
+            let weight = |&(c, run, step): &(usize, usize, usize)| level[self.state(c, run, step)];
+            let total: f64 = candidates.clone().map(|s| weight(&s)).sum();
+
+            // 浮動小数点の丸めに備え、最後の有効な候補を保持しておく
+            let mut u = rng.gen::<f64>() * total;
+            let mut chosen = None;
+            for candidate in candidates.filter(|s| weight(s) > 0.0) {
+                chosen = Some(candidate);
+                u -= weight(&candidate);
+                if u < 0.0 {
+                    break;
+                }
+            }
+            let next = chosen.expect("validated config always has a feasible continuation");
+            chars.push(self.chars[next.0]);
+            state = Some(next);
+        }
+    }
+}
+
+/// 対数で表した重みから累積確率を計算（最後の値がちょうど1になるよう正規化）
+fn cumulative_probabilities(log_weights: &[f64], log_total: f64) -> Vec<f64> {
+    let mut sum = 0.0;
+    let mut cdf: Vec<f64> = log_weights
+        .iter()
+        .map(|w| {
+            sum += (w - log_total).exp();
+            sum
+        })
+        .collect();
+    for p in &mut cdf {
+        *p /= sum;
+    }
+    cdf
+}
+
+/// 対数の和 ln(Σ exp(x_i)) を桁あふれなく計算
+fn log_sum_exp(terms: &[f64]) -> f64 {
+    let max = terms.iter().copied().fold(f64::NEG_INFINITY, f64::max);
+    if max == f64::NEG_INFINITY {
+        return max;
+    }
+    max + terms.iter().map(|x| (x - max).exp()).sum::<f64>().ln()
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::charset::{CharacterSets, UnicodeSet};
 
     #[test]
     fn test_generate_correct_length() {
@@ -49,6 +1242,7 @@ mod tests {
             use_lowercase: true,
             use_digits: false,
             use_symbols: false,
+            ..Default::default()
         };
 
         let generator = PasswordGenerator::new(config).unwrap();
@@ -66,6 +1260,7 @@ mod tests {
             use_lowercase: false,
             use_digits: true,
             use_symbols: false,
+            ..Default::default()
         };
 
         let generator = PasswordGenerator::new(config).unwrap();
@@ -83,6 +1278,7 @@ mod tests {
             use_lowercase: false,
             use_digits: false,
             use_symbols: false,
+            ..Default::default()
         };
 
         let result = PasswordGenerator::new(config);
@@ -97,6 +1293,7 @@ mod tests {
             use_lowercase: true,
             use_digits: true,
             use_symbols: true,
+            ..Default::default()
         };
 
         let result = PasswordGenerator::new(config);
@@ -115,4 +1312,606 @@ mod tests {
         // 確率的には異なるはず（理論上は同じ可能性もあるが極めて低い）
         assert!(password1 != password2 || password2 != password3);
     }
+
+    #[test]
+    fn test_generate_contains_every_enabled_class() {
+        let config = PasswordConfig {
+            length: 4,
+            ..Default::default()
+        };
+        let generator = PasswordGenerator::new(config).unwrap();
+
+        for _ in 0..200 {
+            let password = generator.generate();
+            assert!(password.chars().any(|c| c.is_ascii_uppercase()));
+            assert!(password.chars().any(|c| c.is_ascii_lowercase()));
+            assert!(password.chars().any(|c| c.is_ascii_digit()));
+            assert!(password.chars().any(|c| CharacterSets::SYMBOLS.contains(c)));
+        }
+    }
+
+    #[test]
+    fn test_generate_required_class_position_is_not_fixed() {
+        let config = PasswordConfig {
+            length: 2,
+            use_uppercase: true,
+            use_lowercase: false,
+            use_digits: true,
+            use_symbols: false,
+            ..Default::default()
+        };
+        let generator = PasswordGenerator::new(config).unwrap();
+
+        // 大文字が先頭に来る場合と末尾に来る場合の両方が現れる
+        let passwords: Vec<String> = (0..200).map(|_| generator.generate()).collect();
+        assert!(passwords
+            .iter()
+            .any(|p| p.starts_with(|c: char| c.is_ascii_uppercase())));
+        assert!(passwords
+            .iter()
+            .any(|p| p.ends_with(|c: char| c.is_ascii_uppercase())));
+    }
+
+    #[test]
+    fn test_generate_length_shorter_than_classes_error() {
+        let config = PasswordConfig {
+            length: 2,
+            ..Default::default()
+        };
+
+        assert!(matches!(
+            PasswordGenerator::new(config),
+            Err(crate::error::RpgError::LengthTooShortForClasses { .. })
+        ));
+    }
+
+    #[test]
+    fn test_generate_respects_min_and_max_counts() {
+        let config = PasswordConfig {
+            length: 12,
+            min_digits: 4,
+            max_symbols: Some(2),
+            ..Default::default()
+        };
+        let generator = PasswordGenerator::new(config).unwrap();
+
+        for _ in 0..200 {
+            let password = generator.generate();
+            let digits = password.chars().filter(|c| c.is_ascii_digit()).count();
+            let symbols = password
+                .chars()
+                .filter(|&c| CharacterSets::SYMBOLS.contains(c))
+                .count();
+            assert_eq!(password.len(), 12);
+            assert!(digits >= 4, "{}", password);
+            assert!((1..=2).contains(&symbols), "{}", password);
+        }
+    }
+
+    #[test]
+    fn test_generate_avoid_ambiguous() {
+        let config = PasswordConfig {
+            length: 64,
+            avoid_ambiguous: true,
+            ..Default::default()
+        };
+        let generator = PasswordGenerator::new(config).unwrap();
+
+        for _ in 0..50 {
+            let password = generator.generate();
+            assert!(!password
+                .chars()
+                .any(|c| CharacterSets::AMBIGUOUS.contains(c)));
+        }
+    }
+
+    #[test]
+    fn test_generate_custom_charset_and_exclude() {
+        let config = PasswordConfig {
+            length: 32,
+            custom_charset: Some("abcdef0123".to_string()),
+            exclude_chars: "0".to_string(),
+            ..Default::default()
+        };
+        let generator = PasswordGenerator::new(config).unwrap();
+
+        let password = generator.generate();
+        assert_eq!(password.len(), 32);
+        assert!(password.chars().all(|c| "abcdef123".contains(c)));
+    }
+
+    #[test]
+    fn test_generate_with_seeded_rng_is_deterministic() {
+        use rand::SeedableRng;
+        use rand_chacha::ChaCha20Rng;
+
+        let generator = PasswordGenerator::new(PasswordConfig::default()).unwrap();
+        let mut rng1 = ChaCha20Rng::seed_from_u64(42);
+        let mut rng2 = ChaCha20Rng::seed_from_u64(42);
+
+        assert_eq!(
+            generator.generate_with(&mut rng1),
+            generator.generate_with(&mut rng2)
+        );
+        assert_ne!(
+            generator.generate_with(&mut rng1),
+            generator.generate_with(&mut ChaCha20Rng::seed_from_u64(43))
+        );
+    }
+
+    #[test]
+    fn test_generate_into_appends_to_buffer() {
+        let generator = PasswordGenerator::new(PasswordConfig::default()).unwrap();
+        let mut buf = String::from("user:");
+        generator.generate_into(&mut buf);
+        assert!(buf.starts_with("user:"));
+        assert_eq!(buf.len(), 5 + 16);
+
+        // 同じ乱数列ならgenerate_withと同じパスワード
+        let mut buf = String::new();
+        generator.generate_into_with(&mut buf, &mut ChaCha20Rng::seed_from_u64(42));
+        assert_eq!(
+            buf,
+            generator.generate_with(&mut ChaCha20Rng::seed_from_u64(42))
+        );
+    }
+
+    #[test]
+    fn test_random_indices_are_uniform() {
+        let mut rng = ChaCha20Rng::seed_from_u64(0);
+        let mut indices = RandomIndices::new(&mut rng);
+        // 256で割り切れない大きさ（棄却あり）と、バイトに収まらない大きさ
+        for n in [3, 94, 300] {
+            let trials = 200 * n;
+            let mut counts = vec![0usize; n];
+            for _ in 0..trials {
+                counts[indices.index(n)] += 1;
+            }
+            assert!(
+                counts.iter().all(|&count| (100..=300).contains(&count)),
+                "n = {}: {:?}",
+                n,
+                counts
+            );
+        }
+
+        let mut items = [1, 2, 3, 4, 5];
+        indices.shuffle(&mut items);
+        items.sort_unstable();
+        assert_eq!(items, [1, 2, 3, 4, 5]);
+    }
+
+    #[test]
+    fn test_generate_unique_exhausts_small_space() {
+        // {a,b}の長さ3: 8通り
+        let config = PasswordConfig {
+            length: 3,
+            custom_charset: Some("ab".to_string()),
+            ..Default::default()
+        };
+        let generator = PasswordGenerator::new(config).unwrap();
+        let mut passwords = generator.generate_unique(8).unwrap();
+        passwords.sort();
+        passwords.dedup();
+        assert_eq!(passwords.len(), 8);
+
+        assert_eq!(
+            generator.generate_unique(9),
+            Err(crate::error::RpgError::NotEnoughUniqueValues {
+                requested: 9,
+                available: 8.0,
+            })
+        );
+    }
+
+    #[test]
+    fn test_entropy_is_exact() {
+        let exact =
+            |config: PasswordConfig| PasswordGenerator::new(config).unwrap().entropy_is_exact();
+        assert!(exact(PasswordConfig::default()));
+        assert!(exact(PasswordConfig {
+            max_consecutive: Some(1),
+            no_sequences: true,
+            ..Default::default()
+        }));
+        assert!(exact(PasswordConfig {
+            max_occurrences: Some(1),
+            ..Default::default()
+        }));
+        assert!(!exact(PasswordConfig {
+            max_occurrences: Some(1),
+            no_sequences: true,
+            ..Default::default()
+        }));
+    }
+
+    #[test]
+    fn test_entropy_bits_matches_counting() {
+        // 大文字・数字を各1文字以上含む長さ2: 26*10*2 通り
+        let config = PasswordConfig {
+            length: 2,
+            use_uppercase: true,
+            use_lowercase: false,
+            use_digits: true,
+            use_symbols: false,
+            ..Default::default()
+        };
+        let generator = PasswordGenerator::new(config).unwrap();
+        let expected = (26.0f64 * 10.0 * 2.0).log2();
+        assert!((generator.entropy_bits() - expected).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_position_rules_entropy_matches_counting() {
+        // 大文字・数字を各1文字以上含む長さ3で先頭が大文字:
+        // 先頭A + 残り2文字（数字1文字以上）: 26 * (36^2 - 26^2) 通り
+        let config = PasswordConfig {
+            length: 3,
+            use_lowercase: false,
+            use_symbols: false,
+            start_with: Some(vec![CharClass::Uppercase]),
+            ..Default::default()
+        };
+        let generator = PasswordGenerator::new(config).unwrap();
+        let expected = (26.0f64 * (36.0 * 36.0 - 26.0 * 26.0)).log2();
+        assert!((generator.entropy_bits() - expected).abs() < 1e-9);
+
+        for _ in 0..100 {
+            let password = generator.generate();
+            assert!(password.starts_with(|c: char| c.is_ascii_uppercase()));
+            assert!(password.chars().any(|c| c.is_ascii_digit()));
+        }
+    }
+
+    #[test]
+    fn test_position_rules_first_and_last() {
+        let config = PasswordConfig {
+            length: 8,
+            start_with: Some(vec![CharClass::Lowercase]),
+            end_with: Some(vec![CharClass::Digit]),
+            ..Default::default()
+        };
+        let generator = PasswordGenerator::new(config).unwrap();
+
+        for _ in 0..200 {
+            let password = generator.generate();
+            assert_eq!(password.len(), 8);
+            assert!(password.starts_with(|c: char| c.is_ascii_lowercase()));
+            assert!(password.ends_with(|c: char| c.is_ascii_digit()));
+            assert!(password.chars().any(|c| c.is_ascii_uppercase()));
+            assert!(password.chars().any(|c| CharacterSets::SYMBOLS.contains(c)));
+        }
+    }
+
+    #[test]
+    fn test_position_rules_single_character() {
+        let config = PasswordConfig {
+            length: 1,
+            use_uppercase: false,
+            use_symbols: false,
+            custom_charset: Some("ab12".to_string()),
+            start_with: Some(vec![CharClass::Lowercase, CharClass::Digit]),
+            end_with: Some(vec![CharClass::Digit]),
+            ..Default::default()
+        };
+        let generator = PasswordGenerator::new(config).unwrap();
+        assert!((generator.entropy_bits() - 1.0).abs() < 1e-9);
+        assert!(["1", "2"].contains(&generator.generate().as_str()));
+    }
+
+    #[test]
+    fn test_required_sets_entropy_matches_counting() {
+        // {a,b,c}の長さ2で a と c を各1文字以上: ac, ca の2通り
+        // a を1文字以上: 9 - 4 = 5通り
+        let config = |sets: &[&str]| PasswordConfig {
+            length: 2,
+            custom_charset: Some("abc".to_string()),
+            required_sets: sets.iter().map(|s| s.to_string()).collect(),
+            ..Default::default()
+        };
+        assert!((config(&["a"]).entropy_bits() - 5f64.log2()).abs() < 1e-9);
+        assert!((config(&["a", "c"]).entropy_bits() - 1.0).abs() < 1e-9);
+
+        let generator = PasswordGenerator::new(config(&["a", "c"])).unwrap();
+        for _ in 0..50 {
+            assert!(["ac", "ca"].contains(&generator.generate().as_str()));
+        }
+    }
+
+    #[test]
+    fn test_max_consecutive_forbids_identical_neighbours() {
+        // {a,b}の長さ6で同じ文字が隣り合わない: abab.. と baba.. の2通り
+        let config = PasswordConfig {
+            length: 6,
+            custom_charset: Some("ab".to_string()),
+            max_consecutive: Some(1),
+            ..Default::default()
+        };
+        let generator = PasswordGenerator::new(config).unwrap();
+        assert!((generator.entropy_bits() - 1.0).abs() < 0.5);
+        for _ in 0..50 {
+            assert!(["ababab", "bababa"].contains(&generator.generate().as_str()));
+        }
+    }
+
+    #[test]
+    fn test_max_occurrences_and_no_sequences() {
+        let config = PasswordConfig {
+            length: 6,
+            custom_charset: Some("0123456789".to_string()),
+            max_occurrences: Some(1),
+            no_sequences: true,
+            ..Default::default()
+        };
+        let generator = PasswordGenerator::new(config).unwrap();
+        for _ in 0..100 {
+            let password: Vec<char> = generator.generate().chars().collect();
+            let mut sorted = password.clone();
+            sorted.sort_unstable();
+            sorted.dedup();
+            assert_eq!(sorted.len(), 6);
+            assert!(!password.windows(3).any(is_sequence));
+        }
+    }
+
+    #[test]
+    fn test_run_rules_entropy_matches_counting() {
+        let config = PasswordConfig {
+            length: 6,
+            custom_charset: Some("abcd12".to_string()),
+            min_digits: 1,
+            start_with: Some(vec![CharClass::Lowercase]),
+            required_sets: vec!["d".to_string()],
+            max_consecutive: Some(2),
+            no_sequences: true,
+            ..Default::default()
+        };
+        let alphabet: Vec<char> = "abcd12".chars().collect();
+        let mut expected = 0;
+        for mut code in 0..alphabet.len().pow(6) {
+            let password: Vec<char> = (0..6)
+                .map(|_| {
+                    let c = alphabet[code % alphabet.len()];
+                    code /= alphabet.len();
+                    c
+                })
+                .collect();
+            let valid = password[0].is_ascii_lowercase()
+                && password.iter().any(|c| c.is_ascii_digit())
+                && password.contains(&'d')
+                && password.chunk_by(|a, b| a == b).all(|run| run.len() <= 2)
+                && !password.windows(3).any(is_sequence);
+            expected += valid as usize;
+        }
+
+        let generator = PasswordGenerator::new(config).unwrap();
+        assert!((generator.entropy_bits() - (expected as f64).log2()).abs() < 1e-9);
+        for _ in 0..50 {
+            let password: Vec<char> = generator.generate().chars().collect();
+            assert!(password[0].is_ascii_lowercase() && password.contains(&'d'));
+            assert!(!password.windows(3).any(is_sequence));
+        }
+    }
+
+    #[test]
+    fn test_max_occurrences_entropy_matches_counting() {
+        let config = PasswordConfig {
+            length: 4,
+            custom_charset: Some("abc12".to_string()),
+            min_digits: 1,
+            start_with: Some(vec![CharClass::Lowercase]),
+            end_with: Some(vec![CharClass::Lowercase]),
+            required_sets: vec!["c".to_string()],
+            max_occurrences: Some(2),
+            ..Default::default()
+        };
+        let alphabet: Vec<char> = "abc12".chars().collect();
+        let mut valid = Vec::new();
+        for mut code in 0..alphabet.len().pow(4) {
+            let password: String = (0..4)
+                .map(|_| {
+                    let c = alphabet[code % alphabet.len()];
+                    code /= alphabet.len();
+                    c
+                })
+                .collect();
+            let chars: Vec<char> = password.chars().collect();
+            if chars[0].is_ascii_lowercase()
+                && chars[3].is_ascii_lowercase()
+                && chars.iter().any(|c| c.is_ascii_digit())
+                && chars.contains(&'c')
+                && alphabet
+                    .iter()
+                    .all(|a| chars.iter().filter(|c| *c == a).count() <= 2)
+            {
+                valid.push(password);
+            }
+        }
+
+        let generator = PasswordGenerator::new(config).unwrap();
+        assert!(generator.entropy_is_exact());
+        assert!((generator.entropy_bits() - (valid.len() as f64).log2()).abs() < 1e-9);
+
+        // 条件を満たすパスワードがそれぞれ同じくらいの頻度で選ばれる
+        let mut rng = ChaCha20Rng::seed_from_u64(3);
+        let trials = 200 * valid.len();
+        let mut counts: HashMap<String, usize> = HashMap::new();
+        for _ in 0..trials {
+            *counts.entry(generator.generate_with(&mut rng)).or_default() += 1;
+        }
+        assert_eq!(counts.len(), valid.len());
+        assert!(
+            counts.values().all(|&count| (130..=270).contains(&count)),
+            "{:?}",
+            counts
+        );
+    }
+
+    #[test]
+    fn test_max_occurrences_without_repeats_is_feasible() {
+        // 88文字から40文字を重複なく選ぶ（無作為な文字列の約0.01%だが、数え上げるので生成できる）
+        let config = PasswordConfig {
+            length: 40,
+            max_occurrences: Some(1),
+            ..Default::default()
+        };
+        let generator = PasswordGenerator::new(config).unwrap();
+        for _ in 0..20 {
+            let password: Vec<char> = generator.generate().chars().collect();
+            assert_eq!(password.len(), 40);
+            let mut distinct = password.clone();
+            distinct.sort_unstable();
+            distinct.dedup();
+            assert_eq!(distinct.len(), 40);
+        }
+    }
+
+    #[test]
+    fn test_run_rules_with_rare_valid_passwords() {
+        // 同じ文字が隣り合わない{a,b,c}の長さ30: 3*2^29 通り（無作為な文字列の約0.001%）
+        let config = PasswordConfig {
+            length: 30,
+            custom_charset: Some("abc".to_string()),
+            max_consecutive: Some(1),
+            ..Default::default()
+        };
+        let generator = PasswordGenerator::new(config).unwrap();
+        assert!((generator.entropy_bits() - (3.0f64.log2() + 29.0)).abs() < 1e-9);
+        for _ in 0..20 {
+            let password = generator.generate();
+            assert_eq!(password.len(), 30);
+            assert!(password
+                .as_bytes()
+                .windows(2)
+                .all(|pair| pair[0] != pair[1]));
+        }
+    }
+
+    #[test]
+    fn test_run_rules_sampling_is_uniform() {
+        // {a,b,c}の長さ3で同じ文字が隣り合わない: 3*2*2 = 12 通り
+        let config = PasswordConfig {
+            length: 3,
+            custom_charset: Some("abc".to_string()),
+            max_consecutive: Some(1),
+            ..Default::default()
+        };
+        let generator = PasswordGenerator::new(config).unwrap();
+        let mut rng = ChaCha20Rng::seed_from_u64(1);
+        let mut counts: HashMap<String, usize> = HashMap::new();
+        for _ in 0..12_000 {
+            *counts.entry(generator.generate_with(&mut rng)).or_default() += 1;
+        }
+        assert_eq!(counts.len(), 12);
+        assert!(counts.values().all(|&n| (850..1150).contains(&n)));
+    }
+
+    #[test]
+    fn test_unicode_length_counts_characters() {
+        let config = PasswordConfig {
+            length: 20,
+            unicode: vec![UnicodeSet::Greek, UnicodeSet::Cyrillic],
+            min_uppercase: 8,
+            ..Default::default()
+        };
+        let generator = PasswordGenerator::new(config).unwrap();
+        for _ in 0..50 {
+            let password = generator.generate();
+            assert_eq!(password.chars().count(), 20);
+            assert!(password.chars().filter(|c| c.is_uppercase()).count() >= 8);
+        }
+    }
+
+    #[test]
+    fn test_max_bytes_limits_utf8_length() {
+        let config = PasswordConfig {
+            length: 12,
+            unicode: vec![UnicodeSet::Kana],
+            max_bytes: Some(20),
+            ..Default::default()
+        };
+        let generator = PasswordGenerator::new(config).unwrap();
+        for _ in 0..100 {
+            let password = generator.generate();
+            assert_eq!(password.chars().count(), 12);
+            assert!(
+                password.len() <= 20,
+                "{:?} is {} bytes",
+                password,
+                password.len()
+            );
+        }
+    }
+
+    #[test]
+    fn test_is_sequence() {
+        let seq = |s: &str| is_sequence(&s.chars().collect::<Vec<_>>());
+        assert!(seq("abc") && seq("321") && seq("XYZ"));
+        assert!(!seq("abd") && !seq("aBc") && !seq("9:;") && !seq("aaa"));
+    }
+
+    #[test]
+    fn test_sample_counts_matches_uniform_distribution() {
+        let pools = vec![
+            ClassPool {
+                class: CharClass::Uppercase,
+                chars: vec!['A'],
+                min: 1,
+                max: 2,
+                fixed: 0,
+                occupancy: None,
+            },
+            ClassPool {
+                class: CharClass::Digit,
+                chars: vec!['0', '1', '2'],
+                min: 1,
+                max: 2,
+                fixed: 0,
+                occupancy: None,
+            },
+        ];
+        // 長さ3で条件を満たす文字列: A1つ+数字2つ → 3*9=27通り, A2つ+数字1つ → 3*3=9通り
+        let sampler = ClassCountSampler::new(pools, 3);
+        let mut rng = rand::thread_rng();
+        let trials = 20_000;
+        let one_upper = (0..trials)
+            .filter(|_| sampler.sample_counts(&mut rng)[..2] == [1, 2])
+            .count();
+
+        let ratio = one_upper as f64 / trials as f64;
+        assert!((ratio - 0.75).abs() < 0.03, "ratio = {}", ratio);
+    }
+
+    #[test]
+    fn test_sample_counts_with_and_without_table() {
+        // 制約なしなら文字数は二項分布: Aの文字数の平均は長さ×1/4
+        for length in [MAX_TABULATED_LENGTH, MAX_TABULATED_LENGTH + 40] {
+            let pool = |class, chars: &str| ClassPool {
+                class,
+                chars: chars.chars().collect(),
+                min: 0,
+                max: length,
+                fixed: 0,
+                occupancy: None,
+            };
+            let pools = vec![
+                pool(CharClass::Uppercase, "A"),
+                pool(CharClass::Digit, "012"),
+            ];
+            let sampler = ClassCountSampler::new(pools, length);
+            assert_eq!(sampler.count_cdf.is_empty(), length > MAX_TABULATED_LENGTH);
+
+            let mut rng = ChaCha20Rng::seed_from_u64(1);
+            let trials = 4000;
+            let mut total = 0;
+            for _ in 0..trials {
+                let counts = sampler.sample_counts(&mut rng);
+                assert_eq!(counts[0] + counts[1], length);
+                total += counts[0];
+            }
+            let mean = total as f64 / trials as f64;
+            let expected = length as f64 / 4.0;
+            assert!((mean - expected).abs() < 0.5, "mean = {}", mean);
+        }
+    }
 }
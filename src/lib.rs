@@ -2,18 +2,40 @@
 //!
 //! 暗号学的に安全なパスワードとパスフレーズを生成するCLIツール。
 
+pub mod analysis;
+pub mod apikey;
 pub mod charset;
 pub mod clipboard;
 pub mod colorize;
 pub mod config;
+pub mod entropy;
 pub mod error;
 pub mod generator;
 pub mod output;
 pub mod passphrase;
+pub mod passwordrules;
+pub mod pattern;
+pub mod pin;
+pub mod policy;
+pub mod presets;
+pub mod pronounceable;
+pub mod regex;
+pub mod secret;
+pub mod stream;
+pub mod token;
+pub mod unique;
 pub mod wordlist;
 
 // 主要な型を再エクスポート
+pub use apikey::{KeyChecksum, KeyConfig, KeyGenerator, KeyVerifier};
 pub use config::{CliArgs, PasswordConfig};
 pub use error::{Result, RpgError};
 pub use generator::PasswordGenerator;
 pub use passphrase::{PassphraseConfig, PassphraseGenerator};
+pub use pattern::{PatternConfig, PatternGenerator};
+pub use pin::{PinConfig, PinGenerator};
+pub use policy::Policy;
+pub use pronounceable::{PronounceableConfig, PronounceableGenerator};
+pub use regex::{RegexConfig, RegexGenerator};
+pub use secret::SecretGenerator;
+pub use token::{TokenConfig, TokenEncoding, TokenGenerator};
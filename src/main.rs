@@ -1,8 +1,20 @@
 use clap::Parser;
+use rand::{CryptoRng, RngCore, SeedableRng};
+use rand_chacha::ChaCha20Rng;
+use rpg::config::{
+    CheckArgs, Commands, KeyArgs, PinArgs, PresetsArgs, PresetsCommand, TokenArgs, VerifyKeyArgs,
+};
+use rpg::entropy::Entropy;
+use rpg::passwordrules::PasswordRules;
+use rpg::stream::{self, LineFormat};
 use rpg::{
-    colorize, error::Result, output, CliArgs, PassphraseConfig, PassphraseGenerator,
-    PasswordConfig, PasswordGenerator, RpgError,
+    analysis, colorize, error::Result, output, presets, CliArgs, KeyConfig, KeyGenerator,
+    KeyVerifier, PassphraseConfig, PassphraseGenerator, PasswordConfig, PasswordGenerator,
+    PatternConfig, PatternGenerator, PinConfig, PinGenerator, Policy, PronounceableConfig,
+    PronounceableGenerator, RegexConfig, RegexGenerator, RpgError, SecretGenerator, TokenConfig,
+    TokenEncoding, TokenGenerator,
 };
+use std::io::{self, BufRead};
 use std::process;
 
 fn main() {
@@ -15,14 +27,60 @@ fn main() {
 fn run() -> Result<()> {
     let args = CliArgs::parse();
 
+    match &args.command {
+        Some(Commands::Check(check_args)) => return run_check(check_args, args.no_color),
+        Some(Commands::Presets(presets_args)) => return run_presets(presets_args),
+        Some(Commands::Pin(pin_args)) => return run_pin(pin_args, args.no_color),
+        Some(Commands::Token(token_args)) => return run_token(token_args, args.no_color),
+        Some(Commands::Key(key_args)) => return run_key(key_args, args.no_color),
+        Some(Commands::VerifyKey(verify_args)) => return run_verify_key(verify_args),
+        None => {}
+    }
+
     // 生成個数の検証
     validate_args(&args)?;
 
-    // パスフレーズモードと通常モードで分岐
+    // テスト用の固定シード（出力は完全に予測可能になる）
+    match args.insecure_seed {
+        Some(seed) => run_mode(&args, &mut insecure_rng(seed)),
+        None => run_mode(&args, &mut rand::thread_rng()),
+    }
+}
+
+/// `--insecure-seed`用の固定シードの乱数生成器（警告を表示する）
+fn insecure_rng(seed: u64) -> ChaCha20Rng {
+    eprintln!(
+        "WARNING: --insecure-seed makes every generated value predictable. \
+         NEVER use this output as a real password or secret."
+    );
+    ChaCha20Rng::seed_from_u64(seed)
+}
+
+fn run_mode<R: RngCore + CryptoRng>(args: &CliArgs, rng: &mut R) -> Result<()> {
+    // 各生成モードのジェネレータを作り、共通の処理で出力する
     if args.passphrase {
-        run_passphrase_mode(&args)
+        let generator = PassphraseGenerator::new(passphrase_config(args)?)?;
+        emit(args, rng, &generator, colorize::colorize_passphrase)
+    } else if let Some(pattern) = &args.pattern {
+        let generator = PatternGenerator::new(PatternConfig {
+            pattern: pattern.to_string(),
+        })?;
+        emit(args, rng, &generator, colorize::colorize_password)
+    } else if let Some(regex) = &args.regex {
+        let generator = RegexGenerator::new(RegexConfig {
+            pattern: regex.to_string(),
+        })?;
+        emit(args, rng, &generator, colorize::colorize_password)
+    } else if args.pronounceable {
+        let generator = PronounceableGenerator::new(PronounceableConfig {
+            length: args.length,
+            capitalize: !args.no_uppercase,
+            include_digit: !args.no_digits,
+        })?;
+        emit(args, rng, &generator, colorize::colorize_password)
     } else {
-        run_password_mode(&args)
+        let generator = PasswordGenerator::new(password_config(args)?)?;
+        emit(args, rng, &generator, colorize::colorize_password)
     }
 }
 
@@ -30,45 +88,304 @@ fn validate_args(args: &CliArgs) -> Result<()> {
     if args.number == 0 {
         return Err(RpgError::InvalidGenerationCount(0));
     }
+    if args.threads == 0 {
+        return Err(RpgError::InvalidThreadCount(0));
+    }
+    if let Some(required) = args.min_entropy {
+        if !required.is_finite() || required < 0.0 {
+            return Err(RpgError::InvalidEntropyTarget(required));
+        }
+    }
     Ok(())
 }
 
-fn run_password_mode(args: &CliArgs) -> Result<()> {
-    let config = PasswordConfig::from(args.clone());
-    let generator = PasswordGenerator::new(config)?;
-
-    // 指定された個数のパスワードを生成
-    let passwords: Vec<String> = (0..args.number).map(|_| generator.generate()).collect();
+/// 生成結果を出力
+///
+/// `--unique`や`--copy`なら全て生成してから出力（コピーは最後の1つ）し、それ以外は
+/// 生成しながら標準出力に書き出します（`--threads`が2以上なら並列に生成）。
+fn emit<R, G>(
+    args: &CliArgs,
+    rng: &mut R,
+    generator: &G,
+    colorize_fn: fn(&str, bool) -> String,
+) -> Result<()>
+where
+    R: RngCore + CryptoRng,
+    G: SecretGenerator + Sync,
+{
+    check_min_entropy(args, generator)?;
 
     // カラー出力の判定
     let enable_color = output::should_enable_color(args.no_color);
+    let shown_entropy = args.show_entropy.then(|| shown_entropy(generator));
+
+    // 重複なしの場合は全て集めてから出力する
+    if args.unique {
+        let items = generator.generate_unique_with(args.number, rng)?;
+        return output::output_or_copy(items, args.copy, colorize_fn, enable_color, shown_entropy);
+    }
+
+    if args.copy {
+        let items = generator.iter_with(rng).take(args.number).collect();
+        return output::output_or_copy(items, true, colorize_fn, enable_color, shown_entropy);
+    }
+
+    let format = LineFormat {
+        colorize_fn,
+        enable_color,
+        entropy: shown_entropy,
+    };
+    stream::stream_to_stdout(
+        args.number,
+        args.threads,
+        rng,
+        |mut rng| generator.generate_with(&mut rng),
+        &format,
+    )
+}
+
+/// サブコマンドで指定された個数を生成して出力、またはクリップボードにコピー
+fn output_batch<G: SecretGenerator>(
+    generator: &G,
+    number: usize,
+    unique: bool,
+    copy: bool,
+    show_entropy: bool,
+    insecure_seed: Option<u64>,
+    no_color: bool,
+) -> Result<()> {
+    // テスト用の固定シード（出力は完全に予測可能になる）
+    let items = match insecure_seed {
+        Some(seed) => collect_batch(generator, number, unique, &mut insecure_rng(seed))?,
+        None => collect_batch(generator, number, unique, &mut rand::thread_rng())?,
+    };
+
+    // カラー出力の判定
+    let enable_color = output::should_enable_color(no_color);
 
-    // 出力またはクリップボードにコピー
     output::output_or_copy(
-        passwords,
-        args.copy,
+        items,
+        copy,
         colorize::colorize_password,
         enable_color,
+        show_entropy.then(|| shown_entropy(generator)),
     )
 }
 
-fn run_passphrase_mode(args: &CliArgs) -> Result<()> {
+/// 指定された個数を生成（`unique`なら重複なし）
+fn collect_batch<G, R>(
+    generator: &G,
+    number: usize,
+    unique: bool,
+    rng: &mut R,
+) -> Result<Vec<String>>
+where
+    G: SecretGenerator,
+    R: RngCore + CryptoRng,
+{
+    if unique {
+        generator.generate_unique_with(number, rng)
+    } else {
+        Ok(generator.iter_with(rng).take(number).collect())
+    }
+}
+
+/// 表示するエントロピー（上限にすぎない場合はそう表示する）
+fn shown_entropy<G: SecretGenerator>(generator: &G) -> Entropy {
+    if generator.entropy_is_upper_bound() {
+        Entropy::AtMost(generator.entropy_bits())
+    } else {
+        Entropy::Bits(generator.entropy_bits())
+    }
+}
+
+/// `--min-entropy`が指定されていれば、設定のエントロピーが足りているか検証
+///
+/// エントロピーが上限にすぎない場合（曖昧な正規表現など）は、足りていることを確かめられないためエラー。
+fn check_min_entropy<G: SecretGenerator>(args: &CliArgs, generator: &G) -> Result<()> {
+    let Some(required) = args.min_entropy else {
+        return Ok(());
+    };
+    let entropy_bits = generator.entropy_bits();
+    if entropy_bits < required {
+        return Err(RpgError::InsufficientEntropy {
+            actual: entropy_bits,
+            required,
+        });
+    }
+    if generator.entropy_is_upper_bound() {
+        return Err(RpgError::UnverifiableEntropy {
+            upper_bound: entropy_bits,
+            required,
+        });
+    }
+    Ok(())
+}
+
+/// CLI引数（`--policy`/`--preset`/`--rules`指定時はそのポリシー）からパスワード設定を作る
+fn password_config(args: &CliArgs) -> Result<PasswordConfig> {
+    let policy = if let Some(path) = &args.policy {
+        Some(Policy::load(path)?)
+    } else if let Some(name) = &args.preset {
+        Some(presets::load(name)?)
+    } else if let Some(rules) = &args.rules {
+        Some(PasswordRules::parse(rules)?.to_policy()?)
+    } else {
+        None
+    };
+    if let Some(policy) = policy {
+        return match args.entropy {
+            Some(bits) => policy.to_config_with_entropy(bits),
+            None => policy.to_config(),
+        };
+    }
+
+    let config = PasswordConfig::from(args.clone());
+    match args.entropy {
+        Some(bits) => config.with_target_entropy(bits),
+        None => Ok(config),
+    }
+}
+
+/// CLI引数からパスフレーズ設定を作る
+fn passphrase_config(args: &CliArgs) -> Result<PassphraseConfig> {
     let config = PassphraseConfig {
         word_count: args.words,
     };
-    let generator = PassphraseGenerator::new(config)?;
+    match args.entropy {
+        Some(bits) => config.with_target_entropy(bits),
+        None => Ok(config),
+    }
+}
 
-    // 指定された個数のパスフレーズを生成
-    let passphrases: Vec<String> = (0..args.number).map(|_| generator.generate()).collect();
+fn run_pin(pin_args: &PinArgs, no_color: bool) -> Result<()> {
+    if pin_args.number == 0 {
+        return Err(RpgError::InvalidGenerationCount(0));
+    }
+    let generator = PinGenerator::new(PinConfig {
+        length: pin_args.length,
+    })?;
 
-    // カラー出力の判定
-    let enable_color = output::should_enable_color(args.no_color);
+    // 指定された個数のPINを生成して出力、またはクリップボードにコピー
+    output_batch(
+        &generator,
+        pin_args.number,
+        pin_args.unique,
+        pin_args.copy,
+        pin_args.show_entropy,
+        pin_args.insecure_seed,
+        no_color,
+    )
+}
 
-    // 出力またはクリップボードにコピー
-    output::output_or_copy(
-        passphrases,
-        args.copy,
-        colorize::colorize_passphrase,
-        enable_color,
+fn run_token(token_args: &TokenArgs, no_color: bool) -> Result<()> {
+    if token_args.number == 0 {
+        return Err(RpgError::InvalidGenerationCount(0));
+    }
+    let default_bytes = match token_args.encoding {
+        TokenEncoding::Uuid => TokenConfig::UUID_BYTES,
+        _ => TokenConfig::default().bytes,
+    };
+    let generator = TokenGenerator::new(TokenConfig {
+        bytes: token_args.bytes.unwrap_or(default_bytes),
+        encoding: token_args.encoding,
+    })?;
+
+    // 指定された個数のトークンを生成して出力、またはクリップボードにコピー
+    output_batch(
+        &generator,
+        token_args.number,
+        false,
+        token_args.copy,
+        token_args.show_entropy,
+        token_args.insecure_seed,
+        no_color,
+    )
+}
+
+fn run_key(key_args: &KeyArgs, no_color: bool) -> Result<()> {
+    if key_args.number == 0 {
+        return Err(RpgError::InvalidGenerationCount(0));
+    }
+    let generator = KeyGenerator::new(KeyConfig {
+        prefix: key_args.prefix.clone(),
+        body_length: key_args.length,
+        checksum: key_args.checksum,
+    })?;
+
+    // 指定された個数のキーを生成して出力、またはクリップボードにコピー
+    output_batch(
+        &generator,
+        key_args.number,
+        false,
+        key_args.copy,
+        key_args.show_entropy,
+        key_args.insecure_seed,
+        no_color,
     )
 }
+
+fn run_verify_key(verify_args: &VerifyKeyArgs) -> Result<()> {
+    let verifier = KeyVerifier {
+        checksum: verify_args.checksum,
+        prefix: verify_args.prefix.clone(),
+        body_length: verify_args.length,
+    };
+    let key = read_secret("Key: ")?;
+    let verified = verifier.verify(key.trim())?;
+    println!(
+        "Valid key (prefix '{}', {}-character body)",
+        verified.prefix, verified.body_length
+    );
+    Ok(())
+}
+
+fn run_check(check_args: &CheckArgs, no_color: bool) -> Result<()> {
+    let mut extra_words = Vec::new();
+    for path in &check_args.wordlists {
+        extra_words.extend(analysis::load_wordlist(path)?);
+    }
+
+    let password = read_secret("Password: ")?;
+    let result = analysis::analyze(&password, &extra_words)?;
+
+    let enable_color = output::should_enable_color(no_color);
+    println!(
+        "{}",
+        output::format_analysis(&password, &result, enable_color)
+    );
+    Ok(())
+}
+
+/// 秘密の値を読み込む（端末ならエコーなしで入力、それ以外は標準入力の1行目）
+fn read_secret(prompt: &str) -> Result<String> {
+    if atty::is(atty::Stream::Stdin) {
+        return rpassword::prompt_password(prompt)
+            .map_err(|e| RpgError::Other(format!("Failed to read input: {}", e)));
+    }
+
+    let mut line = String::new();
+    io::stdin()
+        .lock()
+        .read_line(&mut line)
+        .map_err(|e| RpgError::Other(format!("Failed to read input: {}", e)))?;
+    Ok(line.trim_end_matches(['\r', '\n']).to_string())
+}
+
+fn run_presets(presets_args: &PresetsArgs) -> Result<()> {
+    match &presets_args.command {
+        PresetsCommand::List => {
+            let width = presets::PRESETS
+                .iter()
+                .map(|(name, _)| name.len())
+                .max()
+                .unwrap_or(0);
+            for (name, _) in presets::PRESETS {
+                let description = presets::load(name)?.description.unwrap_or_default();
+                println!("{:width$}  {}", name, description, width = width);
+            }
+        }
+        PresetsCommand::Show { name } => print!("{}", presets::source(name)?),
+    }
+    Ok(())
+}
@@ -1,30 +1,107 @@
 //! 出力とクリップボード操作を担当するモジュール
 
+use crate::analysis::{Analysis, MatchKind};
 use crate::clipboard;
+use crate::colorize::colorize_weak_segments;
+use crate::entropy::{format_entropy, Entropy};
 use crate::error::{Result, RpgError};
+use std::io::{self, BufWriter, Write};
 
+/// 生成結果を出力、またはクリップボードにコピー
+///
+/// `entropy`が指定されていれば、各アイテム（コピー時はメッセージ）の横に
+/// エントロピーと強度を表示します。
 pub fn output_or_copy(
     items: Vec<String>,
     copy: bool,
     colorize_fn: impl Fn(&str, bool) -> String,
     enable_color: bool,
+    entropy: Option<Entropy>,
 ) -> Result<()> {
     let last = items.last().ok_or(RpgError::EmptyOutput)?;
+    let suffix = entropy
+        .map(|entropy| format!("  ({})", entropy))
+        .unwrap_or_default();
 
     if !copy {
-        for item in &items {
-            println!("{}", colorize_fn(item, enable_color));
-        }
+        let lines = items
+            .iter()
+            .map(|item| format!("{}{}\n", colorize_fn(item, enable_color), suffix));
+        write_lines_to_stdout(lines)?;
     }
 
     if copy {
         clipboard::copy_to_clipboard(last)?;
-        println!("Copied to clipboard!");
+        println!("Copied to clipboard!{}", suffix);
     }
 
     Ok(())
 }
 
+/// 行をまとめて標準出力に書き出す
+///
+/// 出力先が閉じられた場合（`| head`など）は、残りを捨てて正常終了します。
+fn write_lines_to_stdout(mut lines: impl Iterator<Item = String>) -> Result<()> {
+    let stdout = io::stdout();
+    let mut writer = BufWriter::new(stdout.lock());
+    let result = lines
+        .try_for_each(|line| writer.write_all(line.as_bytes()))
+        .and_then(|()| writer.flush());
+    match result {
+        Err(e) if e.kind() != io::ErrorKind::BrokenPipe => {
+            Err(RpgError::Other(format!("Failed to write output: {}", e)))
+        }
+        _ => Ok(()),
+    }
+}
+
+/// パスワード分析結果を表示用に整形
+pub fn format_analysis(password: &str, analysis: &Analysis, enable_color: bool) -> String {
+    let classes = analysis
+        .classes
+        .iter()
+        .map(|class| class.to_string())
+        .collect::<Vec<_>>()
+        .join(", ");
+
+    let mut lines = vec![
+        format!(
+            "Password: {}",
+            colorize_weak_segments(password, &analysis.weak_ranges(), enable_color)
+        ),
+        format!("Entropy:  {}", format_entropy(analysis.entropy_bits)),
+        format!("Score:    {}/4", analysis.score()),
+        format!("Classes:  {}", classes),
+    ];
+
+    if !analysis.matches.is_empty() {
+        lines.push("Patterns:".to_string());
+        for m in &analysis.matches {
+            let kind = match &m.kind {
+                MatchKind::Dictionary { word } => format!("dictionary word \"{}\"", word),
+                MatchKind::Repeat => "repeat".to_string(),
+                MatchKind::Sequence => "sequence".to_string(),
+                MatchKind::KeyboardWalk => "keyboard walk".to_string(),
+            };
+            lines.push(format!(
+                "  - {} \"{}\" at {}-{} ({:.1} bits)",
+                kind,
+                m.token,
+                m.start + 1,
+                m.end,
+                m.entropy_bits
+            ));
+        }
+    }
+
+    if !analysis.feedback.is_empty() {
+        lines.push("Feedback:".to_string());
+        lines.extend(analysis.feedback.iter().map(|f| format!("  - {}", f)));
+    }
+
+    lines.join("\n")
+}
+
 pub fn should_enable_color(no_color: bool) -> bool {
     !no_color && atty::is(atty::Stream::Stdout)
 }
@@ -40,7 +117,7 @@ mod tests {
     #[test]
     fn test_output_or_copy_empty_list() {
         let items: Vec<String> = vec![];
-        let result = output_or_copy(items, false, dummy_colorize, false);
+        let result = output_or_copy(items, false, dummy_colorize, false, None);
 
         assert!(result.is_err());
         match result {
@@ -50,12 +127,25 @@ mod tests {
     }
 
     #[test]
+    fn test_format_analysis() {
+        let analysis = crate::analysis::analyze("abc123", &[]).unwrap();
+        let report = format_analysis("abc123", &analysis, false);
+
+        assert!(report.starts_with("Password: abc123\n"));
+        assert!(report.contains("Classes:  lowercase, digits"));
+        assert!(report.contains("sequence \"abc\" at 1-3"));
+        assert!(report.contains("Feedback:"));
+    }
+
+    #[test]
+    #[allow(clippy::bool_assert_comparison)]
     fn test_should_enable_color_with_no_color_flag() {
         // --no-color が指定されている場合
         assert_eq!(should_enable_color(true), false);
     }
 
     #[test]
+    #[allow(clippy::bool_comparison, clippy::nonminimal_bool)]
     fn test_should_enable_color_without_flag() {
         // --no-color が指定されていない場合はTTY判定に依存
         // CI環境ではTTYでない可能性が高いので、falseまたはtrueどちらでも良い
@@ -3,8 +3,13 @@
 //! 覚えやすいパスフレーズ（複数の単語をハイフンで繋げたもの）を生成します。
 
 use crate::error::{Result, RpgError};
+use crate::secret::SecretGenerator;
 use crate::wordlist::WORDLIST;
 use rand::seq::SliceRandom;
+use rand::{CryptoRng, RngCore};
+
+/// パスフレーズの最大ワード数
+pub const MAX_WORD_COUNT: usize = 20;
 
 /// パスフレーズ設定
 #[derive(Debug, Clone, PartialEq)]
@@ -25,13 +30,40 @@ impl PassphraseConfig {
             return Err(RpgError::InvalidWordCount(self.word_count));
         }
 
-        const MAX_WORD_COUNT: usize = 20;
         if self.word_count > MAX_WORD_COUNT {
             return Err(RpgError::WordCountTooLarge(self.word_count));
         }
 
         Ok(())
     }
+
+    /// 目標エントロピーに届く最小のワード数を設定した設定を返す
+    pub fn with_target_entropy(self, target_bits: f64) -> Result<Self> {
+        if !target_bits.is_finite() || target_bits < 0.0 {
+            return Err(RpgError::InvalidEntropyTarget(target_bits));
+        }
+
+        (1..=MAX_WORD_COUNT)
+            .map(|word_count| Self { word_count })
+            .find(|config| config.entropy_bits() >= target_bits)
+            .ok_or_else(|| RpgError::EntropyUnreachable {
+                target: target_bits,
+                max: Self {
+                    word_count: MAX_WORD_COUNT,
+                }
+                .entropy_bits(),
+            })
+    }
+
+    /// 生成されるパスフレーズのエントロピー（ビット）
+    ///
+    /// 単語は重複なしで選ぶため、log2(N × (N-1) × … × (N-k+1)) になります。
+    pub fn entropy_bits(&self) -> f64 {
+        let words = WORDLIST.len();
+        (0..self.word_count.min(words))
+            .map(|i| ((words - i) as f64).log2())
+            .sum()
+    }
 }
 
 /// パスフレーズジェネレータ
@@ -45,17 +77,36 @@ impl PassphraseGenerator {
         Ok(Self { config })
     }
 
+    /// 生成されるパスフレーズのエントロピー（ビット）
+    pub fn entropy_bits(&self) -> f64 {
+        self.config.entropy_bits()
+    }
+
     /// パスフレーズを生成（ハイフン区切り）
     pub fn generate(&self) -> String {
-        let mut rng = rand::thread_rng();
+        self.generate_with(&mut rand::thread_rng())
+    }
+
+    /// 指定された乱数生成器でパスフレーズを生成（ハイフン区切り）
+    pub fn generate_with<R: RngCore + CryptoRng>(&self, rng: &mut R) -> String {
         let words: Vec<&str> = WORDLIST
-            .choose_multiple(&mut rng, self.config.word_count)
+            .choose_multiple(rng, self.config.word_count)
             .copied()
             .collect();
         words.join("-")
     }
 }
 
+impl SecretGenerator for PassphraseGenerator {
+    fn entropy_bits(&self) -> f64 {
+        PassphraseGenerator::entropy_bits(self)
+    }
+
+    fn generate_with<R: RngCore + CryptoRng>(&self, rng: &mut R) -> String {
+        PassphraseGenerator::generate_with(self, rng)
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -92,6 +143,42 @@ mod tests {
         ));
     }
 
+    #[test]
+    fn test_passphrase_generate_unique() {
+        let generator = PassphraseGenerator::new(PassphraseConfig { word_count: 1 }).unwrap();
+        let mut passphrases = generator.generate_unique(WORDLIST.len()).unwrap();
+        passphrases.sort();
+        passphrases.dedup();
+        assert_eq!(passphrases.len(), WORDLIST.len());
+
+        assert!(matches!(
+            generator.generate_unique(WORDLIST.len() + 1),
+            Err(RpgError::NotEnoughUniqueValues { requested, .. }) if requested == WORDLIST.len() + 1
+        ));
+    }
+
+    #[test]
+    fn test_passphrase_entropy_without_replacement() {
+        let config = PassphraseConfig { word_count: 2 };
+        let n = WORDLIST.len() as f64;
+        let expected = (n * (n - 1.0)).log2();
+        assert!((config.entropy_bits() - expected).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_passphrase_with_target_entropy() {
+        // 256語なので1語あたり約8ビット
+        let config = PassphraseConfig::default()
+            .with_target_entropy(40.0)
+            .unwrap();
+        assert_eq!(config.word_count, 6);
+
+        assert!(matches!(
+            PassphraseConfig::default().with_target_entropy(500.0),
+            Err(RpgError::EntropyUnreachable { .. })
+        ));
+    }
+
     #[test]
     fn test_passphrase_generation_error() {
         let config = PassphraseConfig { word_count: 0 };
@@ -110,4 +197,16 @@ mod tests {
         // 確率的には異なるはず
         assert!(passphrase1 != passphrase2 || passphrase1.split('-').count() == 4);
     }
+
+    #[test]
+    fn test_passphrase_generate_with_seeded_rng_is_deterministic() {
+        use rand::SeedableRng;
+        use rand_chacha::ChaCha20Rng;
+
+        let generator = PassphraseGenerator::new(PassphraseConfig::default()).unwrap();
+        let passphrase1 = generator.generate_with(&mut ChaCha20Rng::seed_from_u64(7));
+        let passphrase2 = generator.generate_with(&mut ChaCha20Rng::seed_from_u64(7));
+
+        assert_eq!(passphrase1, passphrase2);
+    }
 }
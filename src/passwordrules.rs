@@ -0,0 +1,491 @@
+//! `passwordrules`属性の構文の解析モジュール
+//!
+//! Webサイトが公開しているパスワード要件
+//! （`minlength: 12; required: lower; required: [-!]; allowed: ascii-printable;`）を解析し、
+//! ポリシーファイルと同じ`Policy`に変換します。
+//!
+//! | 規則 | 値 |
+//! |------|----|
+//! | `required` | 最低1文字含める文字の集合（`,`区切り） |
+//! | `allowed` | 使ってよい文字の集合（`,`区切り） |
+//! | `minlength` / `maxlength` | 長さの範囲 |
+//! | `max-consecutive` | 同じ文字の最大連続数 |
+//!
+//! `required`は規則ごとに「その集合から最低1文字」を意味します。
+//!
+//! 文字の集合は`upper`、`lower`、`digit`、`special`、`ascii-printable`、
+//! または`[-!]`のような文字の列挙です（`]`は列挙の先頭にのみ置けます）。
+//! 生成したパスワードを扱いやすくするため、空白は`special`などに含めません。
+
+use crate::charset::{CharClass, CharacterSets};
+use crate::config::MAX_LENGTH;
+use crate::error::{Result, RpgError};
+use crate::policy::Policy;
+
+/// `special`に含まれる文字（仕様の定義から空白を除いたもの）
+const SPECIAL: &str = "-~!@#$%^&*_+=`|(){}[:;\"'<>,.?]";
+
+/// 解析済みの`passwordrules`
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct PasswordRules {
+    /// `required`の規則ごとの文字の集合
+    required: Vec<Vec<char>>,
+    allowed: Vec<char>,
+    min_length: Option<usize>,
+    max_length: Option<usize>,
+    max_consecutive: Option<usize>,
+}
+
+fn rule_error(rule: &str, message: impl Into<String>) -> RpgError {
+    RpgError::InvalidPolicyRule {
+        rule: rule.to_string(),
+        message: message.into(),
+    }
+}
+
+fn parse_error(position: usize, message: impl Into<String>) -> RpgError {
+    RpgError::PasswordRulesParse {
+        position,
+        message: message.into(),
+    }
+}
+
+/// 名前付きの文字の集合
+fn named_set(name: &str) -> Option<String> {
+    let chars = match name.to_ascii_lowercase().as_str() {
+        "upper" => CharacterSets::UPPERCASE.to_string(),
+        "lower" => CharacterSets::LOWERCASE.to_string(),
+        "digit" => CharacterSets::DIGITS.to_string(),
+        "special" => SPECIAL.to_string(),
+        "ascii-printable" => [
+            CharacterSets::UPPERCASE,
+            CharacterSets::LOWERCASE,
+            CharacterSets::DIGITS,
+            SPECIAL,
+        ]
+        .concat(),
+        _ => return None,
+    };
+    Some(chars)
+}
+
+/// 文字単位で読み進めるパーサー（位置は1始まり）
+struct Parser {
+    chars: Vec<char>,
+    index: usize,
+}
+
+impl Parser {
+    fn position(&self) -> usize {
+        self.index + 1
+    }
+
+    fn peek(&self) -> Option<char> {
+        self.chars.get(self.index).copied()
+    }
+
+    fn skip_whitespace(&mut self) {
+        while self.peek().is_some_and(char::is_whitespace) {
+            self.index += 1;
+        }
+    }
+
+    fn eat(&mut self, expected: char) -> bool {
+        if self.peek() == Some(expected) {
+            self.index += 1;
+            true
+        } else {
+            false
+        }
+    }
+
+    /// 規則名や集合名（英字と`-`の並び）
+    fn identifier(&mut self) -> String {
+        let start = self.index;
+        while self
+            .peek()
+            .is_some_and(|c| c.is_ascii_alphanumeric() || c == '-')
+        {
+            self.index += 1;
+        }
+        self.chars[start..self.index].iter().collect()
+    }
+
+    /// 規則の値の終わり（`;`または末尾）か
+    fn at_value_end(&self) -> bool {
+        matches!(self.peek(), None | Some(';'))
+    }
+
+    fn number(&mut self) -> Result<usize> {
+        let position = self.position();
+        let digits = self.identifier();
+        digits
+            .parse()
+            .map_err(|_| parse_error(position, format!("expected a number, found '{}'", digits)))
+    }
+
+    /// `,`区切りの文字の集合のリスト（和集合を返す）
+    fn char_sets(&mut self) -> Result<Vec<char>> {
+        let mut chars = Vec::new();
+        loop {
+            self.skip_whitespace();
+            let position = self.position();
+            let set: Vec<char> = if self.eat('[') {
+                let start = self.index;
+                // `]`は先頭にのみ置ける
+                if self.peek() == Some(']') {
+                    self.index += 1;
+                }
+                while self.peek().is_some_and(|c| c != ']') {
+                    self.index += 1;
+                }
+                let set = self.chars[start..self.index].to_vec();
+                if !self.eat(']') {
+                    return Err(parse_error(position, "unterminated character set"));
+                }
+                if let Some(c) = set.iter().find(|c| !c.is_ascii_graphic()) {
+                    return Err(parse_error(
+                        position,
+                        format!("unsupported character {:?} in character set", c),
+                    ));
+                }
+                set
+            } else {
+                let name = self.identifier();
+                if name.eq_ignore_ascii_case("unicode") {
+                    return Err(parse_error(position, "'unicode' is not supported"));
+                }
+                named_set(&name)
+                    .ok_or_else(|| {
+                        parse_error(position, format!("unknown character class '{}'", name))
+                    })?
+                    .chars()
+                    .collect()
+            };
+            for c in set {
+                if !chars.contains(&c) {
+                    chars.push(c);
+                }
+            }
+
+            self.skip_whitespace();
+            if !self.eat(',') {
+                return Ok(chars);
+            }
+        }
+    }
+}
+
+impl PasswordRules {
+    /// `passwordrules`の文字列を解析
+    pub fn parse(rules: &str) -> Result<Self> {
+        let mut parser = Parser {
+            chars: rules.chars().collect(),
+            index: 0,
+        };
+        let mut parsed = Self::default();
+
+        loop {
+            parser.skip_whitespace();
+            if parser.peek().is_none() {
+                break;
+            }
+            if parser.eat(';') {
+                continue;
+            }
+
+            let position = parser.position();
+            let name = parser.identifier().to_ascii_lowercase();
+            if name.is_empty() {
+                return Err(parse_error(position, "expected a rule name"));
+            }
+            parser.skip_whitespace();
+            if !parser.eat(':') {
+                return Err(parse_error(
+                    parser.position(),
+                    format!("expected ':' after '{}'", name),
+                ));
+            }
+            parser.skip_whitespace();
+
+            match name.as_str() {
+                "required" => {
+                    parsed.required.push(parser.char_sets()?);
+                }
+                "allowed" => {
+                    for c in parser.char_sets()? {
+                        if !parsed.allowed.contains(&c) {
+                            parsed.allowed.push(c);
+                        }
+                    }
+                }
+                "minlength" => {
+                    let n = parser.number()?;
+                    parsed.min_length = Some(parsed.min_length.map_or(n, |m| m.max(n)));
+                }
+                "maxlength" => {
+                    let n = parser.number()?;
+                    parsed.max_length = Some(parsed.max_length.map_or(n, |m| m.min(n)));
+                }
+                "max-consecutive" => {
+                    let n = parser.number()?;
+                    parsed.max_consecutive = Some(parsed.max_consecutive.map_or(n, |m| m.min(n)));
+                }
+                _ => return Err(parse_error(position, format!("unknown rule '{}'", name))),
+            }
+
+            parser.skip_whitespace();
+            if !parser.at_value_end() {
+                return Err(parse_error(
+                    parser.position(),
+                    "expected ';' at the end of the rule",
+                ));
+            }
+        }
+
+        Ok(parsed)
+    }
+
+    /// 値の範囲を確認（`Policy`の項目名ではなく`passwordrules`の規則名で報告する）
+    fn validate(&self) -> Result<()> {
+        if let Some(min) = self.min_length {
+            if min == 0 {
+                return Err(rule_error("minlength", "0 is less than 1"));
+            }
+            if min > MAX_LENGTH {
+                return Err(rule_error(
+                    "minlength",
+                    format!("{} is greater than the maximum length {}", min, MAX_LENGTH),
+                ));
+            }
+            if let Some(max) = self.max_length.filter(|&max| min > max) {
+                return Err(rule_error(
+                    "minlength",
+                    format!("{} is greater than maxlength {}", min, max),
+                ));
+            }
+        }
+        if self.max_length == Some(0) {
+            return Err(rule_error("maxlength", "0 is less than 1"));
+        }
+        if self.max_consecutive == Some(0) {
+            return Err(rule_error("max-consecutive", "0 is less than 1"));
+        }
+        Ok(())
+    }
+
+    /// 使ってよい文字（指定が無ければ`ascii-printable`）
+    fn allowed_chars(&self) -> Vec<char> {
+        let mut chars: Vec<char> = Vec::new();
+        for &c in self.required.iter().flatten().chain(&self.allowed) {
+            if !chars.contains(&c) {
+                chars.push(c);
+            }
+        }
+        if chars.is_empty() {
+            chars = named_set("ascii-printable").unwrap().chars().collect();
+        }
+        chars
+    }
+
+    /// 同じ生成器で扱えるポリシーに変換
+    ///
+    /// 使ってよい文字種をすべて必須にする`required`は文字種の必須指定に、
+    /// それ以外（`[-!]`や`upper, digit`など）は`required_sets`になります。
+    /// 長さや連続数の値に問題がある場合は、`passwordrules`の規則名と値を含むエラーを返します。
+    pub fn to_policy(&self) -> Result<Policy> {
+        self.validate()?;
+        let allowed = self.allowed_chars();
+        let allowed_in = |class: CharClass| -> Vec<char> {
+            allowed
+                .iter()
+                .copied()
+                .filter(|&c| CharClass::of(c) == class)
+                .collect()
+        };
+
+        let mut policy = Policy {
+            min_length: self.min_length,
+            max_length: self.max_length.map(|max| max.min(MAX_LENGTH)),
+            allowed: Some(Vec::new()),
+            max_consecutive: self.max_consecutive,
+            ..Default::default()
+        };
+
+        for class in CharClass::ALL {
+            let chars = allowed_in(class);
+            if chars.is_empty() {
+                continue;
+            }
+            policy.allowed.as_mut().unwrap().push(class.to_string());
+            if class == CharClass::Symbol {
+                policy.allowed_symbols = Some(chars.iter().collect());
+            } else {
+                policy
+                    .forbidden_chars
+                    .extend(class.chars().chars().filter(|c| !chars.contains(c)));
+            }
+        }
+
+        for set in &self.required {
+            let class = CharClass::of(set[0]);
+            let whole_class = set.iter().all(|&c| CharClass::of(c) == class)
+                && set.len() == allowed_in(class).len();
+            if whole_class {
+                let name = class.to_string();
+                if !policy.required.contains(&name) {
+                    policy.required.push(name);
+                }
+            } else {
+                policy.required_sets.push(set.iter().collect());
+            }
+        }
+
+        Ok(policy)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::generator::PasswordGenerator;
+
+    fn policy(rules: &str) -> Policy {
+        PasswordRules::parse(rules).unwrap().to_policy().unwrap()
+    }
+
+    fn error_position(result: Result<Policy>) -> usize {
+        match result {
+            Err(RpgError::PasswordRulesParse { position, .. }) => position,
+            other => panic!("expected a parse error, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_rules_typical_site() {
+        let policy = policy(
+            "minlength: 12; maxlength: 20; required: lower; required: upper; \
+             required: digit; required: [-!]; allowed: [_];",
+        );
+        assert_eq!(policy.min_length, Some(12));
+        assert_eq!(policy.max_length, Some(20));
+        assert_eq!(policy.allowed_symbols.as_deref(), Some("-!_"));
+
+        let config = policy.to_config().unwrap();
+        let generator = PasswordGenerator::new(config).unwrap();
+        for _ in 0..100 {
+            let password = generator.generate();
+            assert_eq!(password.len(), 16);
+            assert!(password.chars().any(|c| c.is_ascii_lowercase()));
+            assert!(password.chars().any(|c| c.is_ascii_uppercase()));
+            assert!(password.chars().any(|c| c.is_ascii_digit()));
+            assert!(password.chars().any(|c| "-!_".contains(c)));
+            assert!(password
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || "-!_".contains(c)));
+        }
+    }
+
+    #[test]
+    fn test_rules_default_to_ascii_printable() {
+        let policy = policy("minlength: 8");
+        assert_eq!(policy.allowed.as_ref().unwrap().len(), 4);
+        assert!(policy.required.is_empty());
+        assert!(policy.forbidden_chars.is_empty());
+        assert_eq!(policy.allowed_symbols.as_deref(), Some(SPECIAL));
+    }
+
+    #[test]
+    fn test_rules_custom_letters_become_forbidden_chars() {
+        let policy = policy("required: digit; allowed: [abc]");
+        let config = policy.to_config().unwrap();
+        let generator = PasswordGenerator::new(config).unwrap();
+        for _ in 0..50 {
+            assert!(generator
+                .generate()
+                .chars()
+                .all(|c| "abc0123456789".contains(c)));
+        }
+    }
+
+    #[test]
+    fn test_rules_case_insensitive_and_whitespace() {
+        assert_eq!(
+            policy("  MinLength : 10 ;REQUIRED: Upper ,Digit ;").min_length,
+            Some(10)
+        );
+    }
+
+    #[test]
+    fn test_rules_bracket_set_with_closing_bracket() {
+        let policy = policy("allowed: lower; required: []-]");
+        assert_eq!(policy.allowed_symbols.as_deref(), Some("]-"));
+    }
+
+    #[test]
+    fn test_rules_parse_errors() {
+        let result = |rules: &str| PasswordRules::parse(rules).and_then(|r| r.to_policy());
+        assert_eq!(error_position(result("minlength: 8; colour: red")), 15);
+        assert_eq!(
+            error_position(result("required: upper; allowed: emoji")),
+            27
+        );
+        assert_eq!(error_position(result("minlength 8")), 11);
+        assert_eq!(error_position(result("minlength: eight")), 12);
+        assert_eq!(error_position(result("required: [-!")), 11);
+        assert_eq!(error_position(result("required: upper lower")), 17);
+        assert_eq!(error_position(result("allowed: unicode")), 10);
+    }
+
+    #[test]
+    fn test_rules_value_errors_use_rule_names() {
+        let rule_error = |rules: &str| match PasswordRules::parse(rules).unwrap().to_policy() {
+            Err(RpgError::InvalidPolicyRule { rule, message }) => (rule, message),
+            other => panic!("expected a rule error, got {:?}", other),
+        };
+        assert_eq!(
+            rule_error("minlength: 0"),
+            ("minlength".to_string(), "0 is less than 1".to_string())
+        );
+        assert_eq!(
+            rule_error("minlength: 20; maxlength: 12"),
+            (
+                "minlength".to_string(),
+                "20 is greater than maxlength 12".to_string()
+            )
+        );
+        assert_eq!(
+            rule_error("minlength: 2000").1,
+            "2000 is greater than the maximum length 1024"
+        );
+        assert_eq!(rule_error("maxlength: 0").0, "maxlength");
+        assert_eq!(rule_error("max-consecutive: 0").0, "max-consecutive");
+    }
+
+    #[test]
+    fn test_rules_partial_requirements_become_required_sets() {
+        let policy = policy("required: upper, digit; required: [-]; allowed: lower, special");
+        assert!(policy.required.is_empty());
+        assert_eq!(policy.required_sets.len(), 2);
+        assert_eq!(policy.required_sets[1], "-");
+
+        let generator = PasswordGenerator::new(policy.to_config().unwrap()).unwrap();
+        for _ in 0..100 {
+            let password = generator.generate();
+            assert!(password.contains('-'), "{}", password);
+            assert!(
+                password
+                    .chars()
+                    .any(|c| c.is_ascii_uppercase() || c.is_ascii_digit()),
+                "{}",
+                password
+            );
+        }
+    }
+
+    #[test]
+    fn test_rules_max_consecutive() {
+        let policy = policy("minlength: 8; max-consecutive: 3; max-consecutive: 2");
+        assert_eq!(policy.max_consecutive, Some(2));
+    }
+}
@@ -0,0 +1,321 @@
+//! パターンベースのパスワード生成モジュール
+//!
+//! `Cvccv-d{4}-ss`のようなテンプレートから、固定書式のパスワードを生成します。
+//! プレースホルダーはKeePassのパスワードパターンに準じます。
+//!
+//! | 記号 | 文字セット |
+//! |------|------------|
+//! | `d`, `9` | 数字 |
+//! | `l` / `u` / `L` | 小文字 / 大文字 / 大小英字 |
+//! | `a` / `U` / `A` | 小文字+数字 / 大文字+数字 / 英数字 |
+//! | `h` / `H` | 16進数（小文字 / 大文字） |
+//! | `v` / `Z` / `V` | 母音（小文字 / 大文字 / 大小） |
+//! | `c` / `z` / `C` | 子音（小文字 / 大文字 / 大小） |
+//! | `s` | 記号 |
+//! | `b` | 括弧 |
+//! | `\x` | 文字`x`そのもの |
+//! | `{n}` | 直前の要素をn回繰り返す |
+//!
+//! 英数字以外の文字はそのまま出力されます。
+
+use crate::charset::CharacterSets;
+use crate::config::MAX_LENGTH;
+use crate::error::{Result, RpgError};
+use crate::secret::SecretGenerator;
+use rand::seq::SliceRandom;
+use rand::{CryptoRng, RngCore};
+
+/// 括弧の文字セット
+const BRACKETS: &str = "()[]{}<>";
+
+/// パターンの1文字分の要素
+#[derive(Debug, Clone, PartialEq)]
+enum PatternElement {
+    /// 固定の文字
+    Literal(char),
+    /// 文字セットから1文字を選ぶ
+    Choice(Vec<char>),
+}
+
+/// パターン設定
+#[derive(Debug, Clone, PartialEq)]
+pub struct PatternConfig {
+    pub pattern: String,
+}
+
+impl PatternConfig {
+    /// 設定の妥当性を検証
+    pub fn validate(&self) -> Result<()> {
+        parse_pattern(&self.pattern).map(|_| ())
+    }
+}
+
+/// パターンベースのパスワードジェネレータ
+///
+/// パターンは初期化時に解析・展開されます。
+pub struct PatternGenerator {
+    elements: Vec<PatternElement>,
+}
+
+impl PatternGenerator {
+    pub fn new(config: PatternConfig) -> Result<Self> {
+        let elements = parse_pattern(&config.pattern)?;
+        Ok(Self { elements })
+    }
+
+    /// 生成されるパスワードのエントロピー（ビット）
+    pub fn entropy_bits(&self) -> f64 {
+        self.elements
+            .iter()
+            .map(|element| match element {
+                PatternElement::Literal(_) => 0.0,
+                PatternElement::Choice(chars) => (chars.len() as f64).log2(),
+            })
+            .sum()
+    }
+
+    pub fn generate(&self) -> String {
+        self.generate_with(&mut rand::thread_rng())
+    }
+
+    /// 指定された乱数生成器でパスワードを生成
+    pub fn generate_with<R: RngCore + CryptoRng>(&self, rng: &mut R) -> String {
+        self.elements
+            .iter()
+            .map(|element| match element {
+                PatternElement::Literal(c) => *c,
+                PatternElement::Choice(chars) => *chars.choose(rng).unwrap(),
+            })
+            .collect()
+    }
+}
+
+impl SecretGenerator for PatternGenerator {
+    fn entropy_bits(&self) -> f64 {
+        PatternGenerator::entropy_bits(self)
+    }
+
+    fn generate_with<R: RngCore + CryptoRng>(&self, rng: &mut R) -> String {
+        PatternGenerator::generate_with(self, rng)
+    }
+}
+
+/// プレースホルダーに対応する文字セット
+fn placeholder_chars(placeholder: char) -> Option<Vec<char>> {
+    let upper = |s: &str| s.to_ascii_uppercase();
+    let vowels = CharacterSets::VOWELS;
+    let consonants = CharacterSets::CONSONANTS;
+
+    let chars: String = match placeholder {
+        'd' | '9' => CharacterSets::DIGITS.to_string(),
+        'l' => CharacterSets::LOWERCASE.to_string(),
+        'u' => CharacterSets::UPPERCASE.to_string(),
+        'L' => [CharacterSets::LOWERCASE, CharacterSets::UPPERCASE].concat(),
+        'a' => [CharacterSets::LOWERCASE, CharacterSets::DIGITS].concat(),
+        'U' => [CharacterSets::UPPERCASE, CharacterSets::DIGITS].concat(),
+        'A' => [
+            CharacterSets::LOWERCASE,
+            CharacterSets::UPPERCASE,
+            CharacterSets::DIGITS,
+        ]
+        .concat(),
+        'h' => "0123456789abcdef".to_string(),
+        'H' => "0123456789ABCDEF".to_string(),
+        'v' => vowels.to_string(),
+        'Z' => upper(vowels),
+        'V' => format!("{}{}", vowels, upper(vowels)),
+        'c' => consonants.to_string(),
+        'z' => upper(consonants),
+        'C' => format!("{}{}", consonants, upper(consonants)),
+        's' => CharacterSets::SYMBOLS.to_string(),
+        'b' => BRACKETS.to_string(),
+        _ => return None,
+    };
+
+    Some(chars.chars().collect())
+}
+
+fn parse_error(position: usize, message: impl Into<String>) -> RpgError {
+    RpgError::PatternParse {
+        position,
+        message: message.into(),
+    }
+}
+
+/// パターンを解析し、繰り返しを展開した要素列を返す
+///
+/// エラー位置は1始まりの文字位置です。
+fn parse_pattern(pattern: &str) -> Result<Vec<PatternElement>> {
+    let chars: Vec<char> = pattern.chars().collect();
+    if chars.is_empty() {
+        return Err(RpgError::InvalidLength(0));
+    }
+
+    let mut elements = Vec::new();
+    let mut i = 0;
+
+    while i < chars.len() {
+        let position = i + 1;
+        let element = match chars[i] {
+            '\\' => {
+                i += 1;
+                let c = chars
+                    .get(i)
+                    .ok_or_else(|| parse_error(position, "escape at end of pattern"))?;
+                PatternElement::Literal(*c)
+            }
+            '{' => {
+                return Err(parse_error(
+                    position,
+                    "repetition count without a preceding placeholder",
+                ))
+            }
+            '}' => return Err(parse_error(position, "unmatched '}'")),
+            c if c.is_ascii_alphanumeric() => {
+                let chars = placeholder_chars(c)
+                    .ok_or_else(|| parse_error(position, format!("unknown placeholder '{}'", c)))?;
+                PatternElement::Choice(chars)
+            }
+            c => PatternElement::Literal(c),
+        };
+        i += 1;
+
+        // 繰り返し回数 {n}
+        let mut count = 1;
+        if chars.get(i) == Some(&'{') {
+            let open = i + 1;
+            let close = chars[i..]
+                .iter()
+                .position(|&c| c == '}')
+                .map(|offset| i + offset)
+                .ok_or_else(|| parse_error(open, "unterminated repetition count"))?;
+            let digits: String = chars[i + 1..close].iter().collect();
+            count = digits.parse().map_err(|_| {
+                parse_error(open + 1, format!("invalid repetition count '{}'", digits))
+            })?;
+            if count == 0 {
+                return Err(parse_error(open + 1, "repetition count must be at least 1"));
+            }
+            i = close + 1;
+        }
+
+        if count > MAX_LENGTH - elements.len() {
+            return Err(RpgError::LengthTooLarge(
+                elements.len().saturating_add(count),
+            ));
+        }
+        elements.extend(std::iter::repeat_n(element, count));
+    }
+
+    Ok(elements)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn generate(pattern: &str) -> String {
+        let config = PatternConfig {
+            pattern: pattern.to_string(),
+        };
+        PatternGenerator::new(config).unwrap().generate()
+    }
+
+    #[test]
+    fn test_pattern_literals_and_classes() {
+        for _ in 0..50 {
+            let password = generate("Cvccv-d{4}-ss");
+            let chars: Vec<char> = password.chars().collect();
+
+            assert_eq!(chars.len(), 13);
+            assert!(CharacterSets::CONSONANTS.contains(chars[0].to_ascii_lowercase()));
+            assert!(CharacterSets::VOWELS.contains(chars[1]));
+            assert_eq!(chars[5], '-');
+            assert!(chars[6..10].iter().all(|c| c.is_ascii_digit()));
+            assert_eq!(chars[10], '-');
+            assert!(chars[11..]
+                .iter()
+                .all(|&c| CharacterSets::SYMBOLS.contains(c)));
+        }
+    }
+
+    #[test]
+    fn test_pattern_hex_and_escape() {
+        let password = generate(r"H{8}\d\\");
+        assert_eq!(password.len(), 10);
+        assert!(password[..8]
+            .chars()
+            .all(|c| "0123456789ABCDEF".contains(c)));
+        assert!(password.ends_with(r"d\"));
+    }
+
+    #[test]
+    fn test_pattern_entropy() {
+        let config = PatternConfig {
+            pattern: "d{4}-h".to_string(),
+        };
+        let generator = PatternGenerator::new(config).unwrap();
+        let expected = 4.0 * 10f64.log2() + 16f64.log2();
+        assert!((generator.entropy_bits() - expected).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_pattern_unknown_placeholder() {
+        assert_eq!(
+            parse_pattern("dd-x"),
+            Err(RpgError::PatternParse {
+                position: 4,
+                message: "unknown placeholder 'x'".to_string(),
+            })
+        );
+    }
+
+    #[test]
+    fn test_pattern_unterminated_repetition() {
+        assert!(matches!(
+            parse_pattern("ud{3"),
+            Err(RpgError::PatternParse { position: 3, .. })
+        ));
+    }
+
+    #[test]
+    fn test_pattern_invalid_repetition_count() {
+        assert!(matches!(
+            parse_pattern("d{x}"),
+            Err(RpgError::PatternParse { position: 3, .. })
+        ));
+        assert!(matches!(
+            parse_pattern("d{0}"),
+            Err(RpgError::PatternParse { position: 3, .. })
+        ));
+    }
+
+    #[test]
+    fn test_pattern_leading_repetition() {
+        assert!(matches!(
+            parse_pattern("{2}"),
+            Err(RpgError::PatternParse { position: 1, .. })
+        ));
+    }
+
+    #[test]
+    fn test_pattern_dangling_escape() {
+        assert!(matches!(
+            parse_pattern(r"dd\"),
+            Err(RpgError::PatternParse { position: 3, .. })
+        ));
+    }
+
+    #[test]
+    fn test_pattern_empty_and_too_long() {
+        assert_eq!(parse_pattern(""), Err(RpgError::InvalidLength(0)));
+        assert_eq!(
+            parse_pattern("d{2000}"),
+            Err(RpgError::LengthTooLarge(2000))
+        );
+        assert_eq!(
+            parse_pattern("dd{18446744073709551615}"),
+            Err(RpgError::LengthTooLarge(usize::MAX))
+        );
+    }
+}
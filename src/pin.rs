@@ -0,0 +1,265 @@
+//! 数字のPIN生成モジュール
+//!
+//! 推測されやすいPIN（同じ数字や同じ並びの繰り返し、1234や9876のような連番、
+//! 日付、よく使われるPIN）を除いた中から一様に選びます。
+
+use crate::error::{Result, RpgError};
+use crate::secret::SecretGenerator;
+use rand::{CryptoRng, Rng, RngCore};
+use std::collections::HashSet;
+
+/// PINの最小桁数
+pub const MIN_PIN_LENGTH: usize = 4;
+
+/// PINの最大桁数
+pub const MAX_PIN_LENGTH: usize = 10;
+
+/// よく使われる4桁のPIN（流出したPINの統計で上位のものと、テンキー上の直線などの形）
+const COMMON_4_DIGIT_PINS: &[&str] = &[
+    "1234", "1111", "0000", "1212", "7777", "1004", "2000", "4444", "2222", "6969", "9999", "3333",
+    "5555", "6666", "1122", "1313", "8888", "4321", "2001", "1010", "2580", "0852", "1470", "0741",
+    "3690", "0963", "1590", "7530", "1397", "1379", "2468", "1357", "5683", "0007", "1230", "0123",
+    "4567", "2525", "6789", "1221", "1001", "0911",
+];
+
+/// よく使われる6桁のPIN
+const COMMON_6_DIGIT_PINS: &[&str] = &[
+    "123456", "654321", "111111", "000000", "123123", "666666", "121212", "112233", "789456",
+    "159753", "987654", "696969", "252525", "147258", "147852", "258369", "520520", "102030",
+    "131313", "007007", "123321", "456789", "147369", "741852", "852456", "999999", "555555",
+    "777777", "888888", "222222", "333333", "444444",
+];
+
+/// PINの設定
+#[derive(Debug, Clone, PartialEq)]
+pub struct PinConfig {
+    /// 桁数
+    pub length: usize,
+}
+
+impl Default for PinConfig {
+    fn default() -> Self {
+        Self { length: 6 }
+    }
+}
+
+impl PinConfig {
+    /// 設定の妥当性を検証
+    pub fn validate(&self) -> Result<()> {
+        if !(MIN_PIN_LENGTH..=MAX_PIN_LENGTH).contains(&self.length) {
+            return Err(RpgError::InvalidPinLength(self.length));
+        }
+        Ok(())
+    }
+}
+
+/// PINのジェネレータ
+///
+/// 除外するPINの一覧は初期化時に作られます。
+pub struct PinGenerator {
+    length: usize,
+    blocklist: HashSet<String>,
+}
+
+impl PinGenerator {
+    pub fn new(config: PinConfig) -> Result<Self> {
+        config.validate()?;
+        Ok(Self {
+            length: config.length,
+            blocklist: weak_pins(config.length),
+        })
+    }
+
+    /// 推測されやすいPINとして除外されるか
+    pub fn is_blocked(&self, pin: &str) -> bool {
+        self.blocklist.contains(pin)
+    }
+
+    /// 除外されるPINの数
+    pub fn blocked_count(&self) -> usize {
+        self.blocklist.len()
+    }
+
+    /// 生成されるPINのエントロピー（ビット）
+    ///
+    /// 除外したPINを除いた総数の対数です。
+    pub fn entropy_bits(&self) -> f64 {
+        let total = 10f64.powi(self.length as i32);
+        (total - self.blocklist.len() as f64).log2()
+    }
+
+    pub fn generate(&self) -> String {
+        self.generate_with(&mut rand::thread_rng())
+    }
+
+    /// 指定された乱数生成器でPINを生成
+    pub fn generate_with<R: RngCore + CryptoRng>(&self, rng: &mut R) -> String {
+        // 除外されたPINを引いたら引き直す（残りのPIN全体から一様に選ばれる）
+        loop {
+            let pin: String = (0..self.length)
+                .map(|_| char::from(b'0' + rng.gen_range(0..10)))
+                .collect();
+            if !self.is_blocked(&pin) {
+                return pin;
+            }
+        }
+    }
+}
+
+impl SecretGenerator for PinGenerator {
+    fn entropy_bits(&self) -> f64 {
+        PinGenerator::entropy_bits(self)
+    }
+
+    fn generate_with<R: RngCore + CryptoRng>(&self, rng: &mut R) -> String {
+        PinGenerator::generate_with(self, rng)
+    }
+}
+
+/// 指定桁数の推測されやすいPINの一覧
+fn weak_pins(length: usize) -> HashSet<String> {
+    let mut pins = HashSet::new();
+
+    // 長さの約数の長さの並びの繰り返し（1111、1212、123123）
+    for block in (1..length).filter(|&block| length.is_multiple_of(block)) {
+        for value in 0..10usize.pow(block as u32) {
+            pins.insert(format!("{:0width$}", value, width = block).repeat(length / block));
+        }
+    }
+
+    // 1ずつ増える、または減る連番（0を挟んで一周するものも含む: 7890、3210）
+    for start in 0..10 {
+        for step in [1, 9] {
+            let pin = (0..length)
+                .map(|i| char::from(b'0' + ((start + step * i) % 10) as u8))
+                .collect();
+            pins.insert(pin);
+        }
+    }
+
+    pins.extend(dates(length));
+
+    pins.extend(
+        COMMON_4_DIGIT_PINS
+            .iter()
+            .chain(COMMON_6_DIGIT_PINS)
+            .filter(|pin| pin.len() == length)
+            .map(|pin| pin.to_string()),
+    );
+
+    pins
+}
+
+/// 日付として読めるPIN（4桁: MMDD、DDMM、YYYY。6桁: 2桁の年との組み合わせ。8桁: 4桁の年との組み合わせ）
+fn dates(length: usize) -> Vec<String> {
+    const DAYS_IN_MONTH: [u32; 12] = [31, 29, 31, 30, 31, 30, 31, 31, 30, 31, 30, 31];
+    let month_days = || {
+        (1..=12u32)
+            .flat_map(|month| (1..=DAYS_IN_MONTH[month as usize - 1]).map(move |day| (month, day)))
+    };
+
+    let mut pins = Vec::new();
+    match length {
+        4 => {
+            for (month, day) in month_days() {
+                pins.push(format!("{:02}{:02}", month, day));
+                pins.push(format!("{:02}{:02}", day, month));
+            }
+            pins.extend((1900..2100).map(|year| year.to_string()));
+        }
+        6 | 8 => {
+            let (years, width) = if length == 6 {
+                (0..100, 2)
+            } else {
+                (1900..2100, 4)
+            };
+            for year in years {
+                for (month, day) in month_days() {
+                    let year = format!("{:0width$}", year, width = width);
+                    pins.push(format!("{:02}{:02}{}", day, month, year));
+                    pins.push(format!("{:02}{:02}{}", month, day, year));
+                    pins.push(format!("{}{:02}{:02}", year, month, day));
+                }
+            }
+        }
+        _ => {}
+    }
+    pins
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn generator(length: usize) -> PinGenerator {
+        PinGenerator::new(PinConfig { length }).unwrap()
+    }
+
+    #[test]
+    fn test_pin_digits_and_length() {
+        for length in MIN_PIN_LENGTH..=MAX_PIN_LENGTH {
+            let generator = generator(length);
+            for _ in 0..20 {
+                let pin = generator.generate();
+                assert_eq!(pin.len(), length);
+                assert!(pin.chars().all(|c| c.is_ascii_digit()));
+                assert!(!generator.is_blocked(&pin));
+            }
+        }
+    }
+
+    #[test]
+    fn test_pin_blocks_weak_patterns() {
+        let four = generator(4);
+        for pin in [
+            "0000", "7777", "1212", "1234", "9876", "7890", "3210", "2580",
+        ] {
+            assert!(four.is_blocked(pin), "{} should be blocked", pin);
+        }
+        // 日付と年
+        for pin in ["1225", "2512", "0229", "1984", "2023"] {
+            assert!(four.is_blocked(pin), "{} should be blocked", pin);
+        }
+        for pin in ["3841", "7295", "1399", "4096"] {
+            assert!(!four.is_blocked(pin), "{} should be allowed", pin);
+        }
+
+        let six = generator(6);
+        for pin in [
+            "123123", "121212", "123456", "654321", "311299", "991231", "070415",
+        ] {
+            assert!(six.is_blocked(pin), "{} should be blocked", pin);
+        }
+        assert!(!six.is_blocked("483920"));
+
+        let eight = generator(8);
+        for pin in ["12341234", "19840704", "07041984", "04071984"] {
+            assert!(eight.is_blocked(pin), "{} should be blocked", pin);
+        }
+        assert!(!eight.is_blocked("18840704"));
+    }
+
+    #[test]
+    fn test_pin_entropy_excludes_blocked() {
+        let four = generator(4);
+        // 月日（MMDD・DDMMで重複あり）と年だけで数百個になる
+        assert!(four.blocked_count() > 500);
+        let expected = ((10_000 - four.blocked_count()) as f64).log2();
+        assert!((four.entropy_bits() - expected).abs() < 1e-9);
+        assert!(four.entropy_bits() < 10_000f64.log2());
+
+        let ten = generator(10);
+        assert!(ten.entropy_bits() < 10_000_000_000f64.log2());
+        assert!(ten.entropy_bits() > 33.0);
+    }
+
+    #[test]
+    fn test_pin_invalid_length() {
+        for length in [0, 3, 11] {
+            assert!(matches!(
+                PinGenerator::new(PinConfig { length }),
+                Err(RpgError::InvalidPinLength(l)) if l == length
+            ));
+        }
+    }
+}
@@ -0,0 +1,597 @@
+//! パスワードポリシーファイル（TOML）モジュール
+//!
+//! システムごとのパスワード要件をTOMLで記述し、`PasswordConfig`に変換します。
+//!
+//! ```toml
+//! name = "corp-vpn"
+//! min_length = 12
+//! max_length = 24
+//! required = ["uppercase", "lowercase", "digits"]
+//! required_sets = ["#%"]
+//! allowed = ["uppercase", "lowercase", "digits", "symbols"]
+//! allowed_symbols = "!#%+-_"
+//! forbidden_chars = "lIO0"
+//! safe_for = ["shell", "yaml"]
+//! max_bytes = 72
+//! max_consecutive = 2
+//! no_sequences = true
+//!
+//! [min]
+//! digits = 2
+//!
+//! [max]
+//! symbols = 3
+//!
+//! [position]
+//! first = ["letters"]
+//! last = ["alnum"]
+//! ```
+//!
+//! 文字種の名前には`CharClass::parse_group`の名前（`letters`、`alnum`など）を使えます。
+//! 規則に問題がある場合は、その規則名を含む`RpgError::InvalidPolicyRule`を返します。
+
+use crate::charset::{build_class_charsets, CharClass, SafeContext};
+use crate::config::{PasswordConfig, MAX_LENGTH};
+use crate::error::{Result, RpgError};
+use clap::ValueEnum;
+use serde::Deserialize;
+use std::fs;
+use std::path::Path;
+
+/// 長さの指定が無いときに使うパスワード長（許容範囲に丸める）
+const DEFAULT_LENGTH: usize = 16;
+
+/// パスワードポリシー
+#[derive(Debug, Clone, Default, PartialEq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Policy {
+    /// ポリシー名
+    pub name: Option<String>,
+    /// 説明
+    pub description: Option<String>,
+    /// 最小の長さ
+    pub min_length: Option<usize>,
+    /// 最大の長さ
+    pub max_length: Option<usize>,
+    /// 生成するパスワードの長さ（省略時は16を許容範囲に丸めた値）
+    pub length: Option<usize>,
+    /// 最低1文字含める文字種
+    #[serde(default)]
+    pub required: Vec<String>,
+    /// それぞれから最低1文字含める文字の集合（`["-!", "#$"]`など）
+    #[serde(default)]
+    pub required_sets: Vec<String>,
+    /// 使ってよい文字種（省略時はすべて）
+    pub allowed: Option<Vec<String>>,
+    /// 使ってよい記号
+    pub allowed_symbols: Option<String>,
+    /// 使ってはいけない文字
+    #[serde(default)]
+    pub forbidden_chars: String,
+    /// 見間違えやすい文字を除外
+    #[serde(default)]
+    pub avoid_ambiguous: bool,
+    /// 埋め込む文脈（`shell`、`url`など）
+    #[serde(default)]
+    pub safe_for: Vec<String>,
+    /// UTF-8でのバイト数の上限
+    pub max_bytes: Option<usize>,
+    /// 同じ文字が連続してよい最大数
+    pub max_consecutive: Option<usize>,
+    /// 同じ文字が現れてよい最大回数
+    pub max_occurrences: Option<usize>,
+    /// abcや321のような連続を禁止
+    #[serde(default)]
+    pub no_sequences: bool,
+    /// 文字種ごとの最小文字数
+    #[serde(default)]
+    pub min: ClassCounts,
+    /// 文字種ごとの最大文字数
+    #[serde(default)]
+    pub max: ClassCounts,
+    /// 位置に関する規則
+    #[serde(default)]
+    pub position: PositionRules,
+}
+
+/// 文字種ごとの文字数
+#[derive(Debug, Clone, Default, PartialEq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ClassCounts {
+    pub uppercase: Option<usize>,
+    pub lowercase: Option<usize>,
+    pub digits: Option<usize>,
+    pub symbols: Option<usize>,
+}
+
+impl ClassCounts {
+    fn get(&self, class: CharClass) -> Option<usize> {
+        match class {
+            CharClass::Uppercase => self.uppercase,
+            CharClass::Lowercase => self.lowercase,
+            CharClass::Digit => self.digits,
+            CharClass::Symbol => self.symbols,
+        }
+    }
+}
+
+/// 先頭・末尾の文字に使える文字種
+#[derive(Debug, Clone, Default, PartialEq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct PositionRules {
+    pub first: Option<Vec<String>>,
+    pub last: Option<Vec<String>>,
+}
+
+fn rule_error(rule: impl Into<String>, message: impl Into<String>) -> RpgError {
+    RpgError::InvalidPolicyRule {
+        rule: rule.into(),
+        message: message.into(),
+    }
+}
+
+/// 必須の文字種に使える文字が残っているか確認
+fn check_required_classes(config: &PasswordConfig, rule: &str) -> Result<()> {
+    match build_class_charsets(config)
+        .iter()
+        .find(|charset| charset.required && charset.chars.is_empty())
+    {
+        Some(charset) => Err(rule_error(
+            rule,
+            format!("removes all allowed {}", charset.class),
+        )),
+        None => Ok(()),
+    }
+}
+
+/// 文字種の名前のリストを解析（重複は除く）
+fn parse_classes(rule: &str, names: &[String]) -> Result<Vec<CharClass>> {
+    let mut classes = Vec::new();
+    for name in names {
+        let group = CharClass::parse_group(name)
+            .ok_or_else(|| rule_error(rule, format!("unknown character class '{}'", name)))?;
+        for class in group {
+            if !classes.contains(&class) {
+                classes.push(class);
+            }
+        }
+    }
+    Ok(classes)
+}
+
+impl Policy {
+    /// TOML文字列からポリシーを読み込む
+    pub fn from_toml(content: &str, source: &str) -> Result<Self> {
+        toml::from_str(content).map_err(|e: toml::de::Error| RpgError::PolicyParse {
+            source: source.to_string(),
+            message: e.to_string().trim_end().to_string(),
+        })
+    }
+
+    /// ポリシーファイルを読み込む
+    pub fn load(path: &Path) -> Result<Self> {
+        let content = fs::read_to_string(path).map_err(|e| RpgError::FileRead {
+            path: path.display().to_string(),
+            message: e.to_string(),
+        })?;
+        Self::from_toml(&content, &path.display().to_string())
+    }
+
+    /// 長さの許容範囲
+    fn length_range(&self) -> Result<(usize, usize)> {
+        let min = self.min_length.unwrap_or(1);
+        let max = self.max_length.unwrap_or(MAX_LENGTH);
+        if min == 0 {
+            return Err(rule_error("min_length", "must be at least 1"));
+        }
+        if max > MAX_LENGTH {
+            return Err(rule_error(
+                "max_length",
+                format!("must be at most {}", MAX_LENGTH),
+            ));
+        }
+        if min > max {
+            return Err(rule_error(
+                "min_length",
+                format!("{} is greater than max_length {}", min, max),
+            ));
+        }
+        Ok((min, max))
+    }
+
+    /// ポリシーを満たすパスワードを生成する設定に変換
+    pub fn to_config(&self) -> Result<PasswordConfig> {
+        let (min_length, max_length) = self.length_range()?;
+        let length = match self.length {
+            Some(length) if !(min_length..=max_length).contains(&length) => {
+                return Err(rule_error(
+                    "length",
+                    format!(
+                        "{} is outside the allowed range {}..={}",
+                        length, min_length, max_length
+                    ),
+                ))
+            }
+            Some(length) => length,
+            None => DEFAULT_LENGTH.clamp(min_length, max_length),
+        };
+
+        let required = parse_classes("required", &self.required)?;
+        let allowed = match &self.allowed {
+            Some(names) => parse_classes("allowed", names)?,
+            None => CharClass::ALL.to_vec(),
+        };
+        if let Some(class) = required.iter().find(|class| !allowed.contains(class)) {
+            return Err(rule_error(
+                "required",
+                format!("{} are required but not listed in 'allowed'", class),
+            ));
+        }
+
+        let mut config = PasswordConfig {
+            length,
+            use_uppercase: required.contains(&CharClass::Uppercase),
+            use_lowercase: required.contains(&CharClass::Lowercase),
+            use_digits: required.contains(&CharClass::Digit),
+            use_symbols: required.contains(&CharClass::Symbol),
+            avoid_ambiguous: self.avoid_ambiguous,
+            exclude_chars: self.forbidden_chars.clone(),
+            ..Default::default()
+        };
+
+        if let Some(symbols) = &self.allowed_symbols {
+            if !allowed.contains(&CharClass::Symbol) {
+                return Err(rule_error(
+                    "allowed_symbols",
+                    "symbols are not listed in 'allowed'",
+                ));
+            }
+            if let Some(c) = symbols
+                .chars()
+                .find(|&c| CharClass::of(c) != CharClass::Symbol)
+            {
+                return Err(rule_error(
+                    "allowed_symbols",
+                    format!("'{}' is not a symbol", c),
+                ));
+            }
+            config.custom_symbols = Some(symbols.clone());
+        }
+
+        // 必須でない文字種は追加文字として使えるようにする（最低文字数の保証なし）
+        for &class in allowed.iter().filter(|class| !required.contains(class)) {
+            match (class, &config.custom_symbols) {
+                (CharClass::Symbol, Some(symbols)) => config.include_chars.push_str(symbols),
+                _ => config.include_chars.push_str(class.chars()),
+            }
+        }
+
+        for class in CharClass::ALL {
+            let min = self.min.get(class);
+            let max = self.max.get(class);
+            if min.is_some_and(|min| min > 0) && !allowed.contains(&class) {
+                return Err(rule_error(
+                    format!("min.{}", class),
+                    format!("{} are not listed in 'allowed'", class),
+                ));
+            }
+            if let (Some(min), Some(max)) = (min, max) {
+                if min > max {
+                    return Err(rule_error(
+                        format!("max.{}", class),
+                        format!("{} is less than min.{} ({})", max, class, min),
+                    ));
+                }
+            }
+            if max == Some(0) && required.contains(&class) {
+                return Err(rule_error(
+                    format!("max.{}", class),
+                    format!("is 0 but {} are required", class),
+                ));
+            }
+        }
+        config.min_uppercase = self.min.uppercase.unwrap_or(0);
+        config.min_lowercase = self.min.lowercase.unwrap_or(0);
+        config.min_digits = self.min.digits.unwrap_or(0);
+        config.min_symbols = self.min.symbols.unwrap_or(0);
+        config.max_uppercase = self.max.uppercase;
+        config.max_lowercase = self.max.lowercase;
+        config.max_digits = self.max.digits;
+        config.max_symbols = self.max.symbols;
+
+        // 禁止文字や文脈によって必須の文字種が空になっていないか
+        check_required_classes(&config, "forbidden_chars")?;
+        for name in &self.safe_for {
+            let context = SafeContext::from_str(name, true)
+                .map_err(|_| rule_error("safe_for", format!("unknown context '{}'", name)))?;
+            if !config.safe_for.contains(&context) {
+                config.safe_for.push(context);
+            }
+        }
+        check_required_classes(&config, "safe_for")?;
+
+        config.required_sets = self.required_sets.clone();
+        for (rule, limit) in [
+            ("max_consecutive", self.max_consecutive),
+            ("max_occurrences", self.max_occurrences),
+        ] {
+            if limit == Some(0) {
+                return Err(rule_error(rule, "must be at least 1"));
+            }
+        }
+        config.max_consecutive = self.max_consecutive;
+        config.max_occurrences = self.max_occurrences;
+        config.no_sequences = self.no_sequences;
+        config.max_bytes = self.max_bytes;
+        config.start_with =
+            self.position_classes("position.first", &self.position.first, &allowed)?;
+        config.end_with = self.position_classes("position.last", &self.position.last, &allowed)?;
+
+        config.validate().map_err(|e| self.attribute_error(e))?;
+        Ok(config)
+    }
+
+    /// 目標エントロピーに届く最短の長さ（ただし許容範囲内）の設定に変換
+    pub fn to_config_with_entropy(&self, target_bits: f64) -> Result<PasswordConfig> {
+        let (min_length, max_length) = self.length_range()?;
+        let config = self.to_config()?;
+
+        let longest = PasswordConfig {
+            length: max_length,
+            ..config.clone()
+        };
+        let shortest = config.with_target_entropy(target_bits)?;
+        if shortest.length > max_length {
+            return Err(RpgError::EntropyUnreachable {
+                target: target_bits,
+                max: longest.entropy_bits(),
+            });
+        }
+
+        Ok(PasswordConfig {
+            length: shortest.length.max(min_length),
+            ..shortest
+        })
+    }
+
+    fn position_classes(
+        &self,
+        rule: &str,
+        names: &Option<Vec<String>>,
+        allowed: &[CharClass],
+    ) -> Result<Option<Vec<CharClass>>> {
+        let Some(names) = names else {
+            return Ok(None);
+        };
+        let classes: Vec<CharClass> = parse_classes(rule, names)?
+            .into_iter()
+            .filter(|class| allowed.contains(class))
+            .collect();
+        if classes.is_empty() {
+            return Err(rule_error(
+                rule,
+                "none of the listed character classes are allowed",
+            ));
+        }
+        Ok(Some(classes))
+    }
+
+    /// 組み合わせの検証エラーを、原因となった規則名付きのエラーに変換
+    fn attribute_error(&self, error: RpgError) -> RpgError {
+        let rule = match &error {
+            RpgError::LengthTooShortForClasses { .. } => "required",
+            RpgError::MinimumsExceedLength { .. } => "min",
+            RpgError::MaximumsBelowLength { .. } => "max",
+            RpgError::UnsatisfiablePositionRule(_) => "position",
+            RpgError::UnsatisfiableRequiredSet(_) => "required_sets",
+            RpgError::ByteLimitTooSmall { .. } => "max_bytes",
+            _ => return error,
+        };
+        let message = error.to_string();
+        rule_error(rule, message.trim_start_matches("Error: "))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::generator::PasswordGenerator;
+
+    fn policy(content: &str) -> Policy {
+        Policy::from_toml(content, "test").unwrap()
+    }
+
+    fn rule_of(result: Result<PasswordConfig>) -> String {
+        match result {
+            Err(RpgError::InvalidPolicyRule { rule, .. }) => rule,
+            other => panic!("expected a policy rule error, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_policy_full_example() {
+        let policy = policy(
+            r#"
+            name = "corp-vpn"
+            min_length = 12
+            max_length = 24
+            required = ["uppercase", "lowercase", "digits"]
+            allowed_symbols = "!#%+-_"
+            forbidden_chars = "lIO0"
+
+            [min]
+            digits = 2
+
+            [max]
+            symbols = 3
+
+            [position]
+            first = ["letters"]
+            last = ["alnum"]
+            "#,
+        );
+        let config = policy.to_config().unwrap();
+        assert_eq!(config.length, 16);
+        assert!(!config.use_symbols);
+        let generator = PasswordGenerator::new(config).unwrap();
+
+        for _ in 0..200 {
+            let password = generator.generate();
+            let chars: Vec<char> = password.chars().collect();
+            assert_eq!(chars.len(), 16);
+            assert!(chars[0].is_ascii_alphabetic(), "{}", password);
+            assert!(chars[15].is_ascii_alphanumeric(), "{}", password);
+            assert!(chars.iter().any(|c| c.is_ascii_uppercase()));
+            assert!(chars.iter().filter(|c| c.is_ascii_digit()).count() >= 2);
+            assert!(chars.iter().all(|&c| !"lIO0".contains(c)));
+            let symbols: Vec<&char> = chars
+                .iter()
+                .filter(|c| !c.is_ascii_alphanumeric())
+                .collect();
+            assert!(symbols.len() <= 3);
+            assert!(symbols.iter().all(|&&c| "!#%+-_".contains(c)));
+        }
+    }
+
+    #[test]
+    fn test_policy_defaults_to_all_classes() {
+        let config = Policy::default().to_config().unwrap();
+        assert_eq!(config.length, DEFAULT_LENGTH);
+        assert!(!config.use_uppercase && !config.use_symbols);
+        assert!(config.include_chars.contains('A'));
+        assert!(config.include_chars.contains('!'));
+    }
+
+    #[test]
+    fn test_policy_length_clamped_to_range() {
+        assert_eq!(policy("min_length = 20").to_config().unwrap().length, 20);
+        assert_eq!(policy("max_length = 8").to_config().unwrap().length, 8);
+    }
+
+    #[test]
+    fn test_policy_parse_errors() {
+        assert!(matches!(
+            Policy::from_toml("min_length = \"twelve\"", "test"),
+            Err(RpgError::PolicyParse { .. })
+        ));
+        assert!(matches!(
+            Policy::from_toml("min_lenght = 12", "test"),
+            Err(RpgError::PolicyParse { .. })
+        ));
+    }
+
+    #[test]
+    fn test_policy_rule_errors_name_the_rule() {
+        assert_eq!(
+            rule_of(policy("min_length = 10\nmax_length = 8").to_config()),
+            "min_length"
+        );
+        assert_eq!(
+            rule_of(policy("max_length = 8\nlength = 9").to_config()),
+            "length"
+        );
+        assert_eq!(
+            rule_of(policy("required = [\"emoji\"]").to_config()),
+            "required"
+        );
+        assert_eq!(
+            rule_of(policy("required = [\"symbols\"]\nallowed = [\"letters\"]").to_config()),
+            "required"
+        );
+        assert_eq!(
+            rule_of(policy("allowed_symbols = \"ab\"").to_config()),
+            "allowed_symbols"
+        );
+        assert_eq!(
+            rule_of(
+                policy("required = [\"digits\"]\nforbidden_chars = \"0123456789\"").to_config()
+            ),
+            "forbidden_chars"
+        );
+        assert_eq!(
+            rule_of(policy("allowed = [\"lower\"]\n[min]\ndigits = 1").to_config()),
+            "min.digits"
+        );
+        assert_eq!(
+            rule_of(policy("[min]\ndigits = 3\n[max]\ndigits = 2").to_config()),
+            "max.digits"
+        );
+        assert_eq!(
+            rule_of(policy("allowed = [\"lower\"]\n[position]\nfirst = [\"digits\"]").to_config()),
+            "position.first"
+        );
+        assert_eq!(
+            rule_of(policy("length = 8\n[min]\ndigits = 5\nsymbols = 5").to_config()),
+            "min"
+        );
+    }
+
+    #[test]
+    fn test_policy_required_sets() {
+        let config = policy("length = 6\nallowed_symbols = \"-!_\"\nrequired_sets = [\"-!\"]")
+            .to_config()
+            .unwrap();
+        let generator = PasswordGenerator::new(config).unwrap();
+        for _ in 0..100 {
+            assert!(generator.generate().contains(['-', '!']));
+        }
+
+        assert_eq!(
+            rule_of(policy("allowed = [\"lower\"]\nrequired_sets = [\"-!\"]").to_config()),
+            "required_sets"
+        );
+    }
+
+    #[test]
+    fn test_policy_repetition_rules() {
+        let config = policy("max_consecutive = 1\nno_sequences = true")
+            .to_config()
+            .unwrap();
+        assert_eq!(config.max_consecutive, Some(1));
+        assert!(config.no_sequences);
+        assert_eq!(
+            rule_of(policy("max_occurrences = 0").to_config()),
+            "max_occurrences"
+        );
+    }
+
+    #[test]
+    fn test_policy_safe_for() {
+        let config = policy("safe_for = [\"url\", \"URL\", \"shell\"]")
+            .to_config()
+            .unwrap();
+        assert_eq!(config.safe_for, vec![SafeContext::Url, SafeContext::Shell]);
+        assert_eq!(
+            rule_of(policy("safe_for = [\"html\"]").to_config()),
+            "safe_for"
+        );
+        assert_eq!(
+            rule_of(
+                policy("required = [\"symbols\"]\nallowed_symbols = \"&<\"\nsafe_for = [\"xml\"]")
+                    .to_config()
+            ),
+            "safe_for"
+        );
+    }
+
+    #[test]
+    fn test_policy_max_bytes() {
+        let config = policy("max_bytes = 72").to_config().unwrap();
+        assert_eq!(config.max_bytes, Some(72));
+        assert_eq!(
+            rule_of(policy("min_length = 12\nmax_bytes = 8").to_config()),
+            "max_bytes"
+        );
+    }
+
+    #[test]
+    fn test_policy_entropy_respects_length_range() {
+        let policy = policy("min_length = 14\nmax_length = 20");
+        assert_eq!(policy.to_config_with_entropy(10.0).unwrap().length, 14);
+        assert!(policy.to_config_with_entropy(96.0).unwrap().length <= 20);
+        assert!(matches!(
+            policy.to_config_with_entropy(200.0),
+            Err(RpgError::EntropyUnreachable { .. })
+        ));
+    }
+}
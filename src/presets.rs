@@ -0,0 +1,158 @@
+//! 組み込みのポリシープリセット
+//!
+//! よく使われるシステムのパスワード要件を、ポリシーファイルと同じTOML形式で埋め込んでいます。
+//! `rpg presets show <name>`の出力はそのまま`--policy`用のファイルとして使えます。
+
+use crate::error::{Result, RpgError};
+use crate::policy::Policy;
+
+/// プリセット名とポリシー（TOML）の組
+pub const PRESETS: &[(&str, &str)] = &[
+    (
+        "aws-iam",
+        r#"name = "aws-iam"
+description = "AWS IAM user passwords (all four classes, IAM symbol set)"
+min_length = 8
+max_length = 128
+length = 20
+required = ["uppercase", "lowercase", "digits", "symbols"]
+allowed_symbols = "!@#$%^&*()_+-=[]{}|'"
+"#,
+    ),
+    (
+        "active-directory",
+        r#"name = "active-directory"
+description = "Windows Active Directory with password complexity enabled"
+min_length = 8
+max_length = 127
+length = 16
+required = ["uppercase", "lowercase", "digits", "symbols"]
+"#,
+    ),
+    (
+        "pci-dss",
+        r#"name = "pci-dss"
+description = "PCI DSS v4.0 (at least 12 characters, letters and digits)"
+min_length = 12
+length = 16
+required = ["uppercase", "lowercase", "digits"]
+"#,
+    ),
+    (
+        "mysql-safe",
+        r#"name = "mysql-safe"
+description = "Database passwords safe in connection strings and shell scripts"
+min_length = 12
+max_length = 32
+length = 24
+required = ["uppercase", "lowercase", "digits", "symbols"]
+# Only symbols that need no escaping in URLs (DSNs) or shell scripts: - _ .
+safe_for = ["url", "shell"]
+
+[position]
+first = ["letters"]
+"#,
+    ),
+    (
+        "wifi-wpa2",
+        r#"name = "wifi-wpa2"
+description = "WPA2/WPA3 personal passphrases, easy to type on TVs and phones"
+min_length = 8
+max_length = 63
+length = 20
+required = ["uppercase", "lowercase", "digits"]
+allowed = ["uppercase", "lowercase", "digits"]
+avoid_ambiguous = true
+"#,
+    ),
+    (
+        "bios",
+        r#"name = "bios"
+description = "Firmware/BIOS passwords (short, lowercase and digits, safe on QWERTY/QWERTZ layouts)"
+min_length = 8
+max_length = 20
+length = 12
+required = ["lowercase", "digits"]
+allowed = ["lowercase", "digits"]
+avoid_ambiguous = true
+# Letters that move between QWERTY, QWERTZ and AZERTY layouts
+# (digits still need Shift on AZERTY)
+forbidden_chars = "azqwmy"
+"#,
+    ),
+];
+
+/// プリセットのTOMLを名前で探す
+pub fn source(name: &str) -> Result<&'static str> {
+    PRESETS
+        .iter()
+        .find(|(preset, _)| *preset == name)
+        .map(|(_, toml)| *toml)
+        .ok_or_else(|| RpgError::UnknownPreset(name.to_string()))
+}
+
+/// プリセットのポリシーを読み込む
+pub fn load(name: &str) -> Result<Policy> {
+    Policy::from_toml(source(name)?, &format!("preset {}", name))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::charset::{build_class_charsets, SafeContext};
+    use crate::generator::PasswordGenerator;
+
+    #[test]
+    fn test_presets_are_valid_and_named_consistently() {
+        for (name, _) in PRESETS {
+            let policy = load(name).unwrap();
+            assert_eq!(policy.name.as_deref(), Some(*name));
+            assert!(policy.description.is_some());
+
+            let generator = PasswordGenerator::new(policy.to_config().unwrap()).unwrap();
+            assert!(!generator.generate().is_empty());
+        }
+    }
+
+    #[test]
+    fn test_preset_names_are_unique() {
+        for (i, (name, _)) in PRESETS.iter().enumerate() {
+            assert!(PRESETS[i + 1..].iter().all(|(other, _)| other != name));
+        }
+    }
+
+    #[test]
+    fn test_unknown_preset() {
+        assert_eq!(
+            load("nope"),
+            Err(RpgError::UnknownPreset("nope".to_string()))
+        );
+    }
+
+    #[test]
+    fn test_preset_bios_avoids_layout_sensitive_chars() {
+        let config = load("bios").unwrap().to_config().unwrap();
+        let generator = PasswordGenerator::new(config).unwrap();
+        for _ in 0..50 {
+            let password = generator.generate();
+            assert_eq!(password.len(), 12);
+            assert!(password
+                .chars()
+                .all(|c| (c.is_ascii_lowercase() || c.is_ascii_digit()) && !"azqwmy".contains(c)));
+        }
+    }
+
+    #[test]
+    fn test_preset_mysql_safe_symbols_are_url_and_shell_safe() {
+        let config = load("mysql-safe").unwrap().to_config().unwrap();
+        let chars: String = build_class_charsets(&config)
+            .into_iter()
+            .flat_map(|charset| charset.chars)
+            .collect();
+        assert!(chars.contains(['-', '_', '.']));
+        for c in chars.chars() {
+            assert!(SafeContext::Url.is_safe(c), "{:?} is not URL-safe", c);
+            assert!(SafeContext::Shell.is_safe(c), "{:?} is not shell-safe", c);
+        }
+    }
+}
@@ -0,0 +1,322 @@
+//! 発音可能なパスワード生成モジュール
+//!
+//! pwgenと同様に子音と母音の音素を交互に並べ、読み上げやすいパスワードを生成します。
+//! 大文字化と数字の挿入はオプションです。
+
+use crate::charset::CharacterSets;
+use crate::config::MAX_LENGTH;
+use crate::error::{Result, RpgError};
+use crate::secret::SecretGenerator;
+use rand::seq::SliceRandom;
+use rand::{CryptoRng, Rng, RngCore};
+
+/// 子音の音素
+///
+/// 子音の音素は母音字を、母音の音素は子音字を含まないため、
+/// 音素列と生成される文字列は1対1に対応します（エントロピー計算の前提）。
+const CONSONANT_PHONEMES: &[&str] = &[
+    "b", "c", "ch", "d", "f", "g", "gh", "h", "j", "k", "l", "m", "n", "ng", "p", "ph", "r", "s",
+    "sh", "t", "th", "v", "w", "x", "y", "z",
+];
+
+/// 母音の音素
+const VOWEL_PHONEMES: &[&str] = &[
+    "a", "e", "i", "o", "u", "ae", "ai", "au", "ea", "ee", "ei", "ie", "io", "oa", "oo", "ou",
+];
+
+/// 音素の種類
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum PhonemeKind {
+    Consonant,
+    Vowel,
+}
+
+impl PhonemeKind {
+    fn phonemes(self) -> &'static [&'static str] {
+        match self {
+            PhonemeKind::Consonant => CONSONANT_PHONEMES,
+            PhonemeKind::Vowel => VOWEL_PHONEMES,
+        }
+    }
+
+    fn next(self) -> Self {
+        match self {
+            PhonemeKind::Consonant => PhonemeKind::Vowel,
+            PhonemeKind::Vowel => PhonemeKind::Consonant,
+        }
+    }
+
+    /// 残り文字数に収まる音素
+    fn candidates(self, remaining: usize) -> Vec<&'static str> {
+        self.phonemes()
+            .iter()
+            .copied()
+            .filter(|phoneme| phoneme.len() <= remaining)
+            .collect()
+    }
+}
+
+/// 発音可能なパスワードの設定
+#[derive(Debug, Clone, PartialEq)]
+pub struct PronounceableConfig {
+    /// パスワードの長さ
+    pub length: usize,
+    /// 1文字を大文字にする
+    pub capitalize: bool,
+    /// 数字を1文字挿入する
+    pub include_digit: bool,
+}
+
+impl Default for PronounceableConfig {
+    fn default() -> Self {
+        Self {
+            length: 12,
+            capitalize: true,
+            include_digit: true,
+        }
+    }
+}
+
+impl PronounceableConfig {
+    /// 設定の妥当性を検証
+    pub fn validate(&self) -> Result<()> {
+        if self.length == 0 {
+            return Err(RpgError::InvalidLength(self.length));
+        }
+
+        if self.length > MAX_LENGTH {
+            return Err(RpgError::LengthTooLarge(self.length));
+        }
+
+        // 数字を挿入する場合も英字が最低1文字必要
+        let required = 1 + self.include_digit as usize;
+        if self.length < required {
+            return Err(RpgError::LengthTooShortForClasses {
+                length: self.length,
+                required,
+            });
+        }
+
+        Ok(())
+    }
+
+    /// 生成されるパスワードのエントロピー（ビット）
+    ///
+    /// 文字セット全体からの一様選択ではなく、音素の選択・大文字化の位置・
+    /// 数字とその挿入位置の選択から実際のエントロピーを計算します。
+    pub fn entropy_bits(&self) -> f64 {
+        let letters = self.letter_count();
+
+        // consonant[r] / vowel[r]: 次が子音 / 母音の音素で、残りr文字のときのエントロピー
+        let mut consonant = vec![0.0; letters + 1];
+        let mut vowel = vec![0.0; letters + 1];
+        for r in 1..=letters {
+            consonant[r] = phoneme_step_entropy(PhonemeKind::Consonant, r, &vowel);
+            vowel[r] = phoneme_step_entropy(PhonemeKind::Vowel, r, &consonant);
+        }
+
+        // 最初の音素の種類の選択で1ビット
+        let mut bits = 1.0 + (consonant[letters] + vowel[letters]) / 2.0;
+        if self.capitalize {
+            bits += (letters as f64).log2();
+        }
+        if self.include_digit {
+            bits += (CharacterSets::DIGITS.len() as f64 * (letters + 1) as f64).log2();
+        }
+        bits
+    }
+
+    fn letter_count(&self) -> usize {
+        self.length - self.include_digit as usize
+    }
+}
+
+/// 音素を1つ選ぶ段階のエントロピーと、その後の期待エントロピーの和
+fn phoneme_step_entropy(kind: PhonemeKind, remaining: usize, next: &[f64]) -> f64 {
+    let candidates = kind.candidates(remaining);
+    let count = candidates.len() as f64;
+    let rest: f64 = candidates
+        .iter()
+        .map(|phoneme| next[remaining - phoneme.len()])
+        .sum();
+    count.log2() + rest / count
+}
+
+/// 発音可能なパスワードのジェネレータ
+pub struct PronounceableGenerator {
+    config: PronounceableConfig,
+}
+
+impl PronounceableGenerator {
+    pub fn new(config: PronounceableConfig) -> Result<Self> {
+        config.validate()?;
+        Ok(Self { config })
+    }
+
+    /// 生成されるパスワードのエントロピー（ビット）
+    pub fn entropy_bits(&self) -> f64 {
+        self.config.entropy_bits()
+    }
+
+    pub fn generate(&self) -> String {
+        self.generate_with(&mut rand::thread_rng())
+    }
+
+    /// 指定された乱数生成器でパスワードを生成
+    pub fn generate_with<R: RngCore + CryptoRng>(&self, rng: &mut R) -> String {
+        let letters = self.config.letter_count();
+
+        // 子音と母音の音素を交互に並べる
+        let mut kind = if rng.gen() {
+            PhonemeKind::Consonant
+        } else {
+            PhonemeKind::Vowel
+        };
+        let mut chars: Vec<char> = Vec::with_capacity(self.config.length);
+        while chars.len() < letters {
+            let candidates = kind.candidates(letters - chars.len());
+            chars.extend(candidates.choose(rng).unwrap().chars());
+            kind = kind.next();
+        }
+
+        if self.config.capitalize {
+            let index = rng.gen_range(0..letters);
+            chars[index] = chars[index].to_ascii_uppercase();
+        }
+
+        if self.config.include_digit {
+            let digits: Vec<char> = CharacterSets::DIGITS.chars().collect();
+            let index = rng.gen_range(0..=letters);
+            chars.insert(index, *digits.choose(rng).unwrap());
+        }
+
+        chars.into_iter().collect()
+    }
+}
+
+impl SecretGenerator for PronounceableGenerator {
+    fn entropy_bits(&self) -> f64 {
+        PronounceableGenerator::entropy_bits(self)
+    }
+
+    /// 音素の選び方が一様ではなく、別の音素の並びから同じ文字列になることもあるため、
+    /// 総数とは限らない
+    fn entropy_is_exact(&self) -> bool {
+        false
+    }
+
+    fn entropy_is_upper_bound(&self) -> bool {
+        true
+    }
+
+    fn generate_with<R: RngCore + CryptoRng>(&self, rng: &mut R) -> String {
+        PronounceableGenerator::generate_with(self, rng)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_pronounceable_default_config() {
+        let config = PronounceableConfig::default();
+        assert_eq!(config.length, 12);
+        assert!(config.capitalize);
+        assert!(config.include_digit);
+    }
+
+    #[test]
+    fn test_pronounceable_length_and_classes() {
+        let generator = PronounceableGenerator::new(PronounceableConfig::default()).unwrap();
+
+        for _ in 0..100 {
+            let password = generator.generate();
+            assert_eq!(password.len(), 12);
+            assert_eq!(password.chars().filter(|c| c.is_ascii_digit()).count(), 1);
+            assert_eq!(
+                password.chars().filter(|c| c.is_ascii_uppercase()).count(),
+                1
+            );
+        }
+    }
+
+    #[test]
+    fn test_pronounceable_letters_only() {
+        let config = PronounceableConfig {
+            length: 20,
+            capitalize: false,
+            include_digit: false,
+        };
+        let generator = PronounceableGenerator::new(config).unwrap();
+
+        let password = generator.generate();
+        assert_eq!(password.len(), 20);
+        assert!(password.chars().all(|c| c.is_ascii_lowercase()));
+    }
+
+    #[test]
+    fn test_pronounceable_alternates_vowels() {
+        let config = PronounceableConfig {
+            length: 30,
+            capitalize: false,
+            include_digit: false,
+        };
+        let generator = PronounceableGenerator::new(config).unwrap();
+
+        // 子音が3文字以上連続することはない（子音の音素は最大2文字）
+        let is_vowel = |c: char| CharacterSets::VOWELS.contains(c);
+        for _ in 0..50 {
+            let password: Vec<char> = generator.generate().chars().collect();
+            assert!(password.windows(3).all(|w| w.iter().any(|&c| is_vowel(c))));
+        }
+    }
+
+    #[test]
+    fn test_pronounceable_validate() {
+        let config = PronounceableConfig {
+            length: 0,
+            ..Default::default()
+        };
+        assert!(matches!(config.validate(), Err(RpgError::InvalidLength(0))));
+
+        let config = PronounceableConfig {
+            length: 1,
+            ..Default::default()
+        };
+        assert!(matches!(
+            config.validate(),
+            Err(RpgError::LengthTooShortForClasses { .. })
+        ));
+    }
+
+    #[test]
+    fn test_pronounceable_entropy_single_letter() {
+        // 1文字: 子音26通り or 母音5通り（1文字の音素のみ）を1/2ずつ
+        let config = PronounceableConfig {
+            length: 1,
+            capitalize: false,
+            include_digit: false,
+        };
+        let consonants = CONSONANT_PHONEMES.iter().filter(|p| p.len() == 1).count();
+        let vowels = VOWEL_PHONEMES.iter().filter(|p| p.len() == 1).count();
+        let expected = 1.0 + ((consonants as f64).log2() + (vowels as f64).log2()) / 2.0;
+        assert!((config.entropy_bits() - expected).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_phonemes_do_not_mix_letter_kinds() {
+        let is_vowel = |c: char| CharacterSets::VOWELS.contains(c);
+        assert!(CONSONANT_PHONEMES.iter().all(|p| !p.chars().any(is_vowel)));
+        assert!(VOWEL_PHONEMES.iter().all(|p| p.chars().all(is_vowel)));
+    }
+
+    #[test]
+    fn test_pronounceable_entropy_below_uniform() {
+        let config = PronounceableConfig::default();
+        let uniform = config.length as f64 * 62f64.log2();
+        let bits = config.entropy_bits();
+        assert!(bits > 0.0);
+        assert!(bits < uniform, "{} >= {}", bits, uniform);
+    }
+}
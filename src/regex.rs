@@ -0,0 +1,693 @@
+//! 正規表現に一致するランダム文字列の生成モジュール
+//!
+//! `[A-Z]{3}-[0-9]{4,6}`や`(sk|pk)_[a-f0-9]{32}`のような制限付きの正規表現から、
+//! それに一致する文字列を生成します。
+//!
+//! 対応している構文:
+//!
+//! | 構文 | 意味 |
+//! |------|------|
+//! | `a`, `\.` | 文字そのもの |
+//! | `[a-z_]`, `[^"']` | 文字クラス（否定は印字可能なASCIIに対する補集合） |
+//! | `.` | 空白以外の印字可能なASCII |
+//! | `\d`, `\w`, `\s` / `\D`, `\W`, `\S` | 数字、英数字と`_`、空白 / その補集合 |
+//! | `(...)`, `(?:...)` | グループ |
+//! | `a\|b` | 選択 |
+//! | `?`, `{n}`, `{n,m}` | 回数が有限の繰り返し |
+//! | `^`, `$` | パターン全体の先頭・末尾のみ（無視されます） |
+//!
+//! `*`、`+`、`{n,}`のような上限のない繰り返し、後方参照、先読み・後読み、
+//! 単語境界は位置付きのエラーになります。
+//!
+//! 選択肢と繰り返し回数は、それぞれが生成しうる文字列の数に比例した確率で選ぶため、
+//! 曖昧さのない正規表現では一致する文字列全体から一様に選ばれます。
+
+use crate::config::MAX_LENGTH;
+use crate::error::{Result, RpgError};
+use crate::secret::SecretGenerator;
+use rand::distributions::{Distribution, WeightedIndex};
+use rand::seq::SliceRandom;
+use rand::{CryptoRng, RngCore};
+use std::collections::BTreeSet;
+
+/// グループの入れ子の深さの上限（再帰下降で解析するためスタックあふれを防ぐ）
+const MAX_GROUP_DEPTH: usize = 256;
+
+/// `.`と否定クラスの対象になる文字（空白以外の印字可能なASCII）
+const PRINTABLE: std::ops::RangeInclusive<char> = '!'..='~';
+
+/// 正規表現の構文木の1ノード
+#[derive(Debug, Clone)]
+struct Node {
+    kind: NodeKind,
+    /// 生成しうる文字列の数の自然対数
+    log_count: f64,
+    /// 生成しうる文字列の最大長
+    max_len: usize,
+}
+
+#[derive(Debug, Clone)]
+enum NodeKind {
+    /// 文字集合から1文字を選ぶ
+    Set(Vec<char>),
+    /// 連結
+    Concat(Vec<Node>),
+    /// 選択（生成しうる文字列の数で重み付け）
+    Alternation(Vec<Node>, WeightedIndex<f64>),
+    /// min回からの繰り返し（回数は生成しうる文字列の数で重み付け）
+    Repeat(Box<Node>, usize, WeightedIndex<f64>),
+}
+
+/// 対数の重みを、最大値を1とする重みに変換
+fn weights_from_logs(logs: &[f64]) -> WeightedIndex<f64> {
+    let max = logs.iter().copied().fold(f64::NEG_INFINITY, f64::max);
+    WeightedIndex::new(logs.iter().map(|log| (log - max).exp()))
+        .expect("the largest weight is always 1")
+}
+
+impl Node {
+    fn set(chars: Vec<char>) -> Self {
+        Self {
+            log_count: (chars.len() as f64).ln(),
+            max_len: 1,
+            kind: NodeKind::Set(chars),
+        }
+    }
+
+    fn concat(mut nodes: Vec<Node>) -> Self {
+        if nodes.len() == 1 {
+            return nodes.pop().unwrap();
+        }
+        Self {
+            log_count: nodes.iter().map(|node| node.log_count).sum(),
+            max_len: nodes
+                .iter()
+                .fold(0, |total: usize, node| total.saturating_add(node.max_len)),
+            kind: NodeKind::Concat(nodes),
+        }
+    }
+
+    fn alternation(mut branches: Vec<Node>) -> Self {
+        if branches.len() == 1 {
+            return branches.pop().unwrap();
+        }
+        let logs: Vec<f64> = branches.iter().map(|node| node.log_count).collect();
+        let max = logs.iter().copied().fold(f64::NEG_INFINITY, f64::max);
+        Self {
+            log_count: max + logs.iter().map(|log| (log - max).exp()).sum::<f64>().ln(),
+            max_len: branches.iter().map(|node| node.max_len).max().unwrap_or(0),
+            kind: NodeKind::Alternation(branches, weights_from_logs(&logs)),
+        }
+    }
+
+    fn repeat(node: Node, min: usize, max: usize) -> Self {
+        // k回の繰り返しはcount^k通り
+        let logs: Vec<f64> = (min..=max).map(|k| k as f64 * node.log_count).collect();
+        let top = logs[logs.len() - 1];
+        Self {
+            log_count: top + logs.iter().map(|log| (log - top).exp()).sum::<f64>().ln(),
+            max_len: node.max_len.saturating_mul(max),
+            kind: NodeKind::Repeat(Box::new(node), min, weights_from_logs(&logs)),
+        }
+    }
+
+    fn generate_into<R: RngCore + CryptoRng>(&self, out: &mut String, rng: &mut R) {
+        match &self.kind {
+            NodeKind::Set(chars) => out.push(*chars.choose(rng).unwrap()),
+            NodeKind::Concat(nodes) => {
+                for node in nodes {
+                    node.generate_into(out, rng);
+                }
+            }
+            NodeKind::Alternation(branches, weights) => {
+                branches[weights.sample(rng)].generate_into(out, rng)
+            }
+            NodeKind::Repeat(node, min, weights) => {
+                for _ in 0..min + weights.sample(rng) {
+                    node.generate_into(out, rng);
+                }
+            }
+        }
+    }
+}
+
+/// 正規表現の設定
+#[derive(Debug, Clone, PartialEq)]
+pub struct RegexConfig {
+    pub pattern: String,
+}
+
+impl RegexConfig {
+    /// 設定の妥当性を検証
+    pub fn validate(&self) -> Result<()> {
+        parse_regex(&self.pattern).map(|_| ())
+    }
+}
+
+/// 正規表現に一致する文字列のジェネレータ
+///
+/// 正規表現は初期化時に解析されます。
+pub struct RegexGenerator {
+    root: Node,
+}
+
+impl RegexGenerator {
+    pub fn new(config: RegexConfig) -> Result<Self> {
+        let root = parse_regex(&config.pattern)?;
+        Ok(Self { root })
+    }
+
+    /// 生成される文字列のエントロピー（ビット）
+    ///
+    /// 一致する文字列の数から計算します。曖昧な正規表現（`a|a`など）では
+    /// 同じ文字列を重複して数えるため、実際より大きくなります。
+    pub fn entropy_bits(&self) -> f64 {
+        self.root.log_count / std::f64::consts::LN_2
+    }
+
+    pub fn generate(&self) -> String {
+        self.generate_with(&mut rand::thread_rng())
+    }
+
+    /// 指定された乱数生成器で文字列を生成
+    pub fn generate_with<R: RngCore + CryptoRng>(&self, rng: &mut R) -> String {
+        let mut out = String::with_capacity(self.root.max_len);
+        self.root.generate_into(&mut out, rng);
+        out
+    }
+}
+
+impl SecretGenerator for RegexGenerator {
+    fn entropy_bits(&self) -> f64 {
+        RegexGenerator::entropy_bits(self)
+    }
+
+    /// 曖昧な正規表現では同じ文字列を重複して数えるため、総数とは限らない
+    fn entropy_is_exact(&self) -> bool {
+        false
+    }
+
+    fn entropy_is_upper_bound(&self) -> bool {
+        true
+    }
+
+    fn generate_with<R: RngCore + CryptoRng>(&self, rng: &mut R) -> String {
+        RegexGenerator::generate_with(self, rng)
+    }
+}
+
+fn parse_error(position: usize, message: impl Into<String>) -> RpgError {
+    RpgError::RegexParse {
+        position,
+        message: message.into(),
+    }
+}
+
+/// 正規表現を解析して構文木を返す
+///
+/// エラー位置は1始まりの文字位置です。
+fn parse_regex(pattern: &str) -> Result<Node> {
+    let chars: Vec<char> = pattern.chars().collect();
+    if chars.is_empty() {
+        return Err(RpgError::InvalidLength(0));
+    }
+
+    let mut parser = Parser {
+        chars,
+        pos: 0,
+        depth: 0,
+    };
+    let root = parser.parse_alternation()?;
+    if parser.pos < parser.chars.len() {
+        // 対応するグループのない')'で止まっている
+        return Err(parse_error(parser.pos + 1, "unmatched ')'"));
+    }
+    if root.max_len > MAX_LENGTH {
+        return Err(RpgError::LengthTooLarge(root.max_len));
+    }
+    Ok(root)
+}
+
+/// 再帰下降パーサー
+struct Parser {
+    chars: Vec<char>,
+    pos: usize,
+    /// 解析中のグループの入れ子の深さ
+    depth: usize,
+}
+
+impl Parser {
+    fn peek(&self) -> Option<char> {
+        self.chars.get(self.pos).copied()
+    }
+
+    /// 現在の文字の1始まりの位置
+    fn position(&self) -> usize {
+        self.pos + 1
+    }
+
+    fn parse_alternation(&mut self) -> Result<Node> {
+        let mut branches = vec![self.parse_concat()?];
+        while self.peek() == Some('|') {
+            self.pos += 1;
+            branches.push(self.parse_concat()?);
+        }
+        Ok(Node::alternation(branches))
+    }
+
+    fn parse_concat(&mut self) -> Result<Node> {
+        let mut nodes = Vec::new();
+        while let Some(c) = self.peek() {
+            if c == '|' || c == ')' {
+                break;
+            }
+            if let Some(atom) = self.parse_atom()? {
+                let node = self.parse_quantifier(atom)?;
+                if node.max_len > MAX_LENGTH {
+                    return Err(RpgError::LengthTooLarge(node.max_len));
+                }
+                nodes.push(node);
+            }
+        }
+        Ok(Node::concat(nodes))
+    }
+
+    /// 1要素を解析（位置だけを表す`^`と`$`はNone）
+    fn parse_atom(&mut self) -> Result<Option<Node>> {
+        let position = self.position();
+        let c = self.chars[self.pos];
+        self.pos += 1;
+
+        let node = match c {
+            '(' => self.parse_group(position)?,
+            '[' => self.parse_class(position)?,
+            '.' => Node::set(PRINTABLE.collect()),
+            '\\' => self.parse_escape(position)?,
+            '^' if position == 1 => return Ok(None),
+            '$' if position == self.chars.len() => return Ok(None),
+            '^' | '$' => {
+                return Err(parse_error(
+                    position,
+                    format!("'{}' is only supported at the ends of the pattern", c),
+                ))
+            }
+            '*' | '+' | '?' | '{' => {
+                return Err(parse_error(
+                    position,
+                    format!("quantifier '{}' without a preceding element", c),
+                ))
+            }
+            ']' | '}' => return Err(parse_error(position, format!("unmatched '{}'", c))),
+            c => Node::set(vec![c]),
+        };
+        Ok(Some(node))
+    }
+
+    fn parse_group(&mut self, open: usize) -> Result<Node> {
+        if self.peek() == Some('?') {
+            let rest: String = self.chars[self.pos..].iter().take(3).collect();
+            if rest.starts_with("?:") {
+                self.pos += 2;
+            } else if rest.starts_with("?=")
+                || rest.starts_with("?!")
+                || rest.starts_with("?<=")
+                || rest.starts_with("?<!")
+            {
+                return Err(parse_error(open, "lookarounds are not supported"));
+            } else {
+                return Err(parse_error(
+                    open,
+                    "unsupported group syntax (only '(...)' and '(?:...)' are supported)",
+                ));
+            }
+        }
+
+        if self.depth == MAX_GROUP_DEPTH {
+            return Err(parse_error(
+                open,
+                format!("groups nested too deeply (max: {})", MAX_GROUP_DEPTH),
+            ));
+        }
+        self.depth += 1;
+        let node = self.parse_alternation()?;
+        self.depth -= 1;
+        if self.peek() != Some(')') {
+            return Err(parse_error(open, "unterminated group"));
+        }
+        self.pos += 1;
+        Ok(node)
+    }
+
+    /// `[`の後ろから`]`までを解析
+    fn parse_class(&mut self, open: usize) -> Result<Node> {
+        let negated = self.peek() == Some('^');
+        if negated {
+            self.pos += 1;
+        }
+
+        let mut set = BTreeSet::new();
+        let mut first = true;
+        loop {
+            let position = self.position();
+            let c = self
+                .peek()
+                .ok_or_else(|| parse_error(open, "unterminated character class"))?;
+            self.pos += 1;
+            if c == ']' && !first {
+                break;
+            }
+            first = false;
+
+            let start = if c == '\\' {
+                match self.parse_class_escape(position)? {
+                    ClassItem::Char(c) => c,
+                    ClassItem::Set(chars) => {
+                        set.extend(chars);
+                        continue;
+                    }
+                }
+            } else {
+                c
+            };
+
+            // 範囲 a-z（末尾の'-'は文字そのもの）
+            let is_range = self.peek() == Some('-')
+                && self
+                    .chars
+                    .get(self.pos + 1)
+                    .is_some_and(|&next| next != ']');
+            if !is_range {
+                set.insert(start);
+                continue;
+            }
+            self.pos += 1;
+            let end_position = self.position();
+            let end = self.chars[self.pos];
+            self.pos += 1;
+            let end = if end == '\\' {
+                match self.parse_class_escape(end_position)? {
+                    ClassItem::Char(c) => c,
+                    ClassItem::Set(_) => {
+                        return Err(parse_error(
+                            end_position,
+                            "a shorthand class cannot end a range",
+                        ))
+                    }
+                }
+            } else {
+                end
+            };
+            if end < start {
+                return Err(parse_error(
+                    position,
+                    format!("range '{}-{}' is out of order", start, end),
+                ));
+            }
+            set.extend(start..=end);
+        }
+
+        let chars: Vec<char> = if negated {
+            PRINTABLE.filter(|c| !set.contains(c)).collect()
+        } else {
+            set.into_iter().collect()
+        };
+        if chars.is_empty() {
+            return Err(parse_error(open, "character class matches no characters"));
+        }
+        Ok(Node::set(chars))
+    }
+
+    /// `\`の後ろを解析（文字クラスの外）
+    fn parse_escape(&mut self, position: usize) -> Result<Node> {
+        match self.parse_class_escape(position)? {
+            ClassItem::Char(c) => Ok(Node::set(vec![c])),
+            ClassItem::Set(chars) => Ok(Node::set(chars)),
+        }
+    }
+
+    /// `\`の後ろを解析
+    fn parse_class_escape(&mut self, position: usize) -> Result<ClassItem> {
+        let c = self
+            .peek()
+            .ok_or_else(|| parse_error(position, "escape at end of pattern"))?;
+        self.pos += 1;
+
+        let shorthand = |matches: fn(&char) -> bool, negated: bool| {
+            let universe = PRINTABLE.chain(std::iter::once(' '));
+            ClassItem::Set(universe.filter(|c| matches(c) != negated).collect())
+        };
+        let word = |c: &char| c.is_ascii_alphanumeric() || *c == '_';
+        let item = match c {
+            'd' => shorthand(char::is_ascii_digit, false),
+            'D' => shorthand(char::is_ascii_digit, true),
+            'w' => shorthand(word, false),
+            'W' => shorthand(word, true),
+            's' => shorthand(|c| *c == ' ', false),
+            'S' => shorthand(|c| *c == ' ', true),
+            '1'..='9' | 'k' => {
+                return Err(parse_error(position, "backreferences are not supported"))
+            }
+            'b' | 'B' | 'A' | 'z' | 'Z' | 'G' => {
+                return Err(parse_error(
+                    position,
+                    format!("anchor '\\{}' is not supported", c),
+                ))
+            }
+            c if c.is_ascii_alphanumeric() => {
+                return Err(parse_error(
+                    position,
+                    format!("unsupported escape '\\{}'", c),
+                ))
+            }
+            c => ClassItem::Char(c),
+        };
+        Ok(item)
+    }
+
+    /// 直前の要素に続く繰り返し指定を解析
+    fn parse_quantifier(&mut self, atom: Node) -> Result<Node> {
+        let position = self.position();
+        let (min, max) = match self.peek() {
+            Some('?') => {
+                self.pos += 1;
+                (0, 1)
+            }
+            Some(c @ ('*' | '+')) => {
+                return Err(parse_error(
+                    position,
+                    format!(
+                        "unbounded repetition '{}' is not supported (use {{n,m}})",
+                        c
+                    ),
+                ))
+            }
+            Some('{') => self.parse_counts(position)?,
+            _ => return Ok(atom),
+        };
+
+        if let Some(c @ ('?' | '*' | '+' | '{')) = self.peek() {
+            return Err(parse_error(
+                self.position(),
+                format!("quantifier '{}' cannot follow another quantifier", c),
+            ));
+        }
+        Ok(Node::repeat(atom, min, max))
+    }
+
+    /// `{n}`または`{n,m}`を解析
+    fn parse_counts(&mut self, open: usize) -> Result<(usize, usize)> {
+        let close = self.chars[self.pos..]
+            .iter()
+            .position(|&c| c == '}')
+            .map(|offset| self.pos + offset)
+            .ok_or_else(|| parse_error(open, "unterminated repetition count"))?;
+        let body: String = self.chars[self.pos + 1..close].iter().collect();
+        self.pos = close + 1;
+
+        let count = |digits: &str| {
+            digits
+                .trim()
+                .parse::<usize>()
+                .map_err(|_| parse_error(open + 1, format!("invalid repetition count '{}'", body)))
+        };
+        let (min, max) = match body.split_once(',') {
+            None => {
+                let n = count(&body)?;
+                (n, n)
+            }
+            Some((_, max)) if max.trim().is_empty() => {
+                return Err(parse_error(
+                    open,
+                    "unbounded repetition '{n,}' is not supported (use {n,m})",
+                ))
+            }
+            Some((min, max)) => (count(min)?, count(max)?),
+        };
+        if min > max {
+            return Err(parse_error(
+                open + 1,
+                format!("repetition range {{{}}} is out of order", body),
+            ));
+        }
+        if max > MAX_LENGTH {
+            return Err(RpgError::LengthTooLarge(max));
+        }
+        Ok((min, max))
+    }
+}
+
+/// 文字クラス内の要素
+enum ClassItem {
+    Char(char),
+    Set(Vec<char>),
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn generator(pattern: &str) -> RegexGenerator {
+        RegexGenerator::new(RegexConfig {
+            pattern: pattern.to_string(),
+        })
+        .unwrap()
+    }
+
+    fn error_position(pattern: &str) -> usize {
+        match parse_regex(pattern) {
+            Err(RpgError::RegexParse { position, .. }) => position,
+            Err(other) => panic!("expected a regex parse error, got {:?}", other),
+            Ok(_) => panic!("expected a regex parse error for {:?}", pattern),
+        }
+    }
+
+    fn error_message(pattern: &str) -> String {
+        match parse_regex(pattern) {
+            Err(RpgError::RegexParse { message, .. }) => message,
+            Err(other) => panic!("expected a regex parse error, got {:?}", other),
+            Ok(_) => panic!("expected a regex parse error for {:?}", pattern),
+        }
+    }
+
+    #[test]
+    fn test_regex_classes_and_counts() {
+        let generator = generator("^[A-Z]{3}-[0-9]{4,6}$");
+        for _ in 0..100 {
+            let value = generator.generate();
+            let (letters, digits) = value.split_once('-').unwrap();
+            assert_eq!(letters.len(), 3);
+            assert!(letters.chars().all(|c| c.is_ascii_uppercase()));
+            assert!((4..=6).contains(&digits.len()));
+            assert!(digits.chars().all(|c| c.is_ascii_digit()));
+        }
+    }
+
+    #[test]
+    fn test_regex_alternation_and_groups() {
+        let generator = generator(r"(sk|pk)_(?:live|test)_\w{8}");
+        let mut seen = BTreeSet::new();
+        for _ in 0..200 {
+            let value = generator.generate();
+            let prefix = &value[..value.len() - 8];
+            assert!(["sk_live_", "sk_test_", "pk_live_", "pk_test_"].contains(&prefix));
+            seen.insert(prefix.to_string());
+        }
+        assert_eq!(seen.len(), 4);
+    }
+
+    #[test]
+    fn test_regex_negated_class_and_dot() {
+        let generator = generator(r"[^a-zA-Z0-9]{20}.");
+        for _ in 0..50 {
+            let value = generator.generate();
+            assert_eq!(value.len(), 21);
+            assert!(value[..20].chars().all(|c| c.is_ascii_punctuation()));
+            assert!(value.chars().last().unwrap().is_ascii_graphic());
+        }
+    }
+
+    #[test]
+    fn test_regex_class_edge_cases() {
+        let generator = generator(r"[]a-][\d.]");
+        for _ in 0..50 {
+            let value: Vec<char> = generator.generate().chars().collect();
+            assert!("]a-".contains(value[0]));
+            assert!(value[1].is_ascii_digit() || value[1] == '.');
+        }
+    }
+
+    #[test]
+    fn test_regex_entropy() {
+        let expected = 4.0 * 16f64.log2();
+        assert!((generator("[0-9a-f]{4}").entropy_bits() - expected).abs() < 1e-9);
+
+        // 1桁または2桁の数字: 10 + 100通り
+        let expected = 110f64.log2();
+        assert!((generator(r"\d{1,2}").entropy_bits() - expected).abs() < 1e-9);
+
+        // 選択肢ごとの文字列の数の合計: 2 + 26通り
+        let expected = 28f64.log2();
+        assert!((generator("x[yz]|[a-z]").entropy_bits() - expected).abs() < 1e-9);
+
+        assert_eq!(generator("fixed").entropy_bits(), 0.0);
+    }
+
+    #[test]
+    fn test_regex_alternation_is_weighted_by_size() {
+        // "a"は1通り、[0-9]{2}は100通りなので、"a"はほとんど選ばれない
+        let generator = generator("a|[0-9]{2}");
+        let singles = (0..1000).filter(|_| generator.generate() == "a").count();
+        assert!(singles < 40, "'a' was chosen {} times", singles);
+    }
+
+    #[test]
+    fn test_regex_unsupported_constructs() {
+        assert!(error_message("a*").contains("unbounded repetition '*'"));
+        assert!(error_message("a+").contains("unbounded repetition '+'"));
+        assert!(error_message("a{2,}").contains("unbounded repetition"));
+        assert!(error_message(r"(a)\1").contains("backreferences"));
+        assert!(error_message("(?=a)b").contains("lookarounds"));
+        assert!(error_message("(?<!a)b").contains("lookarounds"));
+        assert!(error_message(r"\bword").contains("anchor"));
+        assert!(error_message(r"\x41").contains("unsupported escape"));
+        assert!(error_message("a??").contains("cannot follow another quantifier"));
+    }
+
+    #[test]
+    fn test_regex_error_positions() {
+        assert_eq!(error_position("ab(cd"), 3);
+        assert_eq!(error_position("ab)"), 3);
+        assert_eq!(error_position("x[abc"), 2);
+        assert_eq!(error_position("[z-a]"), 2);
+        assert_eq!(error_position("[^!-~]"), 1);
+        assert_eq!(error_position("a{3,1}"), 3);
+        assert_eq!(error_position("a{x}"), 3);
+        assert_eq!(error_position("*a"), 1);
+        assert_eq!(error_position("a^b"), 2);
+        assert_eq!(error_position(r"ab\"), 3);
+    }
+
+    #[test]
+    fn test_regex_empty_and_too_long() {
+        assert!(matches!(parse_regex(""), Err(RpgError::InvalidLength(0))));
+        assert!(matches!(
+            parse_regex("a{2000}"),
+            Err(RpgError::LengthTooLarge(2000))
+        ));
+        assert!(matches!(
+            parse_regex("(a{100}){100}"),
+            Err(RpgError::LengthTooLarge(10000))
+        ));
+    }
+
+    #[test]
+    fn test_regex_group_depth_limit() {
+        let nested = |depth: usize| format!("{}a{}", "(".repeat(depth), ")".repeat(depth));
+        assert!(parse_regex(&nested(MAX_GROUP_DEPTH)).is_ok());
+        assert_eq!(
+            error_position(&nested(MAX_GROUP_DEPTH + 1)),
+            MAX_GROUP_DEPTH + 1
+        );
+        assert!(error_message(&"(".repeat(50_000)).contains("nested too deeply"));
+    }
+}
@@ -0,0 +1,153 @@
+//! ジェネレータ共通のトレイトモジュール
+//!
+//! パスワード・パスフレーズなどの各ジェネレータは`SecretGenerator`を実装するため、
+//! 同じ書き方で無限イテレータとして扱えます（`take(n)`、`filter`、ユーザー名との`zip`など）。
+
+use crate::error::Result;
+use crate::unique;
+use rand::rngs::ThreadRng;
+use rand::{CryptoRng, RngCore};
+use std::iter::FusedIterator;
+
+/// 秘密の値（パスワード、パスフレーズ、トークンなど）のジェネレータ
+pub trait SecretGenerator {
+    /// 生成される値のエントロピー（ビット）
+    fn entropy_bits(&self) -> f64;
+
+    /// `entropy_bits`が生成され得る値の総数（の対数）に一致するか
+    ///
+    /// 推定値の場合や、同じ値を別の選び方で重複して数える場合はfalseを返します。
+    fn entropy_is_exact(&self) -> bool {
+        true
+    }
+
+    /// `entropy_bits`が上限にすぎず、実際はもっと小さいことがあるか
+    ///
+    /// 同じ値を別の選び方で重複して数える場合はtrueを返します（推定値の場合はfalse）。
+    fn entropy_is_upper_bound(&self) -> bool {
+        false
+    }
+
+    /// 指定された乱数生成器で値を1つ生成
+    fn generate_with<R: RngCore + CryptoRng>(&self, rng: &mut R) -> String;
+
+    /// 値を1つ生成
+    fn generate(&self) -> String {
+        self.generate_with(&mut rand::thread_rng())
+    }
+
+    /// 値を生成し続ける無限イテレータ
+    fn iter(&self) -> Secrets<'_, Self, ThreadRng> {
+        self.iter_with(rand::thread_rng())
+    }
+
+    /// 指定された乱数生成器で値を生成し続ける無限イテレータ
+    ///
+    /// 乱数生成器を使い続けたい場合は`&mut rng`を渡せます。
+    fn iter_with<R: RngCore + CryptoRng>(&self, rng: R) -> Secrets<'_, Self, R> {
+        Secrets {
+            generator: self,
+            rng,
+        }
+    }
+
+    /// 重複のない`count`個の値を生成
+    ///
+    /// `count`が生成され得る値の総数を超える場合は`NotEnoughUniqueValues`を返します
+    /// （総数が正確に分からない場合は、重複が続いた時点で返します）。
+    fn generate_unique(&self, count: usize) -> Result<Vec<String>> {
+        self.generate_unique_with(count, &mut rand::thread_rng())
+    }
+
+    /// 指定された乱数生成器で重複のない`count`個の値を生成
+    fn generate_unique_with<R: RngCore + CryptoRng>(
+        &self,
+        count: usize,
+        rng: &mut R,
+    ) -> Result<Vec<String>> {
+        let exact_entropy_bits = self.entropy_is_exact().then(|| self.entropy_bits());
+        unique::collect_unique(count, exact_entropy_bits, || self.generate_with(rng))
+    }
+}
+
+/// `SecretGenerator::iter`が返す無限イテレータ
+pub struct Secrets<'a, G: ?Sized, R> {
+    generator: &'a G,
+    rng: R,
+}
+
+impl<G: SecretGenerator + ?Sized, R: RngCore + CryptoRng> Iterator for Secrets<'_, G, R> {
+    type Item = String;
+
+    fn next(&mut self) -> Option<String> {
+        Some(self.generator.generate_with(&mut self.rng))
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (usize::MAX, None)
+    }
+}
+
+impl<G: SecretGenerator + ?Sized, R: RngCore + CryptoRng> FusedIterator for Secrets<'_, G, R> {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{PassphraseConfig, PassphraseGenerator, PasswordConfig, PasswordGenerator};
+    use rand::SeedableRng;
+    use rand_chacha::ChaCha20Rng;
+
+    #[test]
+    fn test_iter_take_and_filter() {
+        let generator = PasswordGenerator::new(PasswordConfig::default()).unwrap();
+        let passwords: Vec<String> = generator
+            .iter()
+            .filter(|p| p.starts_with(|c: char| c.is_ascii_alphabetic()))
+            .take(5)
+            .collect();
+        assert_eq!(passwords.len(), 5);
+        assert!(passwords.iter().all(|p| p.len() == 16));
+    }
+
+    #[test]
+    fn test_iter_with_matches_generate_with() {
+        let generator = PassphraseGenerator::new(PassphraseConfig::default()).unwrap();
+        let mut rng = ChaCha20Rng::seed_from_u64(42);
+        let expected: Vec<String> = (0..4).map(|_| generator.generate_with(&mut rng)).collect();
+
+        let mut rng = ChaCha20Rng::seed_from_u64(42);
+        let from_iter: Vec<String> = generator.iter_with(&mut rng).take(3).collect();
+        assert_eq!(from_iter, expected[..3]);
+        // イテレータを捨てたあとも同じ乱数生成器を使い続けられる
+        assert_eq!(generator.generate_with(&mut rng), expected[3]);
+    }
+
+    #[test]
+    fn test_iter_zip_with_usernames() {
+        let generator = PasswordGenerator::new(PasswordConfig::default()).unwrap();
+        let users = ["alice", "bob", "carol"];
+        let accounts: Vec<(&str, String)> = users.into_iter().zip(generator.iter()).collect();
+        assert_eq!(accounts.len(), 3);
+        assert_eq!(accounts[2].0, "carol");
+    }
+
+    /// 種類の違うジェネレータを同じ関数で扱える
+    fn first_two<G: SecretGenerator>(generator: &G) -> Vec<String> {
+        generator
+            .iter_with(ChaCha20Rng::seed_from_u64(0))
+            .take(2)
+            .collect()
+    }
+
+    #[test]
+    fn test_trait_unifies_generators() {
+        let password = PasswordGenerator::new(PasswordConfig::default()).unwrap();
+        let passphrase = PassphraseGenerator::new(PassphraseConfig::default()).unwrap();
+        assert_eq!(first_two(&password).len(), 2);
+        assert_eq!(first_two(&passphrase)[0].split('-').count(), 4);
+        assert_eq!(
+            SecretGenerator::entropy_bits(&passphrase),
+            passphrase.entropy_bits()
+        );
+    }
+}
@@ -0,0 +1,216 @@
+//! 大量生成のためのストリーミング出力モジュール
+//!
+//! 生成したものをためずに、バッファ付きの書き込み先へ順に書き出します。
+//! スレッド数が2以上なら、スレッドごとに独立したCSPRNG（呼び出し元の乱数生成器から
+//! シードしたChaCha20）でチャンク単位に並列生成し、生成順のまま書き出します。
+
+use crate::entropy::Entropy;
+use crate::error::{Result, RpgError};
+use rand::SeedableRng;
+use rand_chacha::rand_core::CryptoRngCore;
+use rand_chacha::ChaCha20Rng;
+use std::io::{self, BufWriter, Write};
+use std::sync::mpsc;
+use std::thread;
+
+/// 並列生成で1スレッドがまとめて生成する個数
+const CHUNK_SIZE: usize = 1024;
+
+/// 書き込みバッファのサイズ
+const BUFFER_SIZE: usize = 64 * 1024;
+
+/// 1行分の書式（色付けとエントロピー表示）
+pub struct LineFormat<C> {
+    pub colorize_fn: C,
+    pub enable_color: bool,
+    pub entropy: Option<Entropy>,
+}
+
+/// `count`個生成しながら標準出力に書き出す
+///
+/// 出力先が閉じられた場合（`| head`など）は、そこで生成をやめて正常終了します。
+pub fn stream_to_stdout<R, F, C>(
+    count: usize,
+    threads: usize,
+    rng: &mut R,
+    generate: F,
+    format: &LineFormat<C>,
+) -> Result<()>
+where
+    R: CryptoRngCore,
+    F: Fn(&mut dyn CryptoRngCore) -> String + Sync,
+    C: Fn(&str, bool) -> String,
+{
+    let stdout = io::stdout();
+    let writer = BufWriter::with_capacity(BUFFER_SIZE, stdout.lock());
+    match write_generated(writer, count, threads, rng, generate, format) {
+        Err(e) if e.kind() == io::ErrorKind::BrokenPipe => Ok(()),
+        result => result.map_err(|e| RpgError::Other(format!("Failed to write output: {}", e))),
+    }
+}
+
+/// `count`個生成しながら`writer`に1行ずつ書き出す
+///
+/// 固定シードの乱数生成器を渡した場合、出力はスレッド数が同じなら再現されます
+/// （チャンクをスレッドに順番に割り当てるため）。
+pub fn write_generated<W, R, F, C>(
+    mut writer: W,
+    count: usize,
+    threads: usize,
+    rng: &mut R,
+    generate: F,
+    format: &LineFormat<C>,
+) -> io::Result<()>
+where
+    W: Write,
+    R: CryptoRngCore,
+    F: Fn(&mut dyn CryptoRngCore) -> String + Sync,
+    C: Fn(&str, bool) -> String,
+{
+    let suffix = format
+        .entropy
+        .map(|entropy| format!("  ({})", entropy))
+        .unwrap_or_default();
+    let mut write_line = |item: &str| {
+        writeln!(
+            writer,
+            "{}{}",
+            (format.colorize_fn)(item, format.enable_color),
+            suffix
+        )
+    };
+
+    if threads <= 1 {
+        for _ in 0..count {
+            write_line(&generate(&mut *rng))?;
+        }
+    } else {
+        let chunks = count.div_ceil(CHUNK_SIZE);
+        let generate = &generate;
+        thread::scope(|scope| -> io::Result<()> {
+            // スレッドiはチャンクi、i+threads、…を生成する
+            let receivers: Vec<mpsc::Receiver<Vec<String>>> = (0..threads.min(chunks))
+                .map(|worker| {
+                    let (sender, receiver) = mpsc::sync_channel(2);
+                    let mut worker_rng = ChaCha20Rng::from_rng(&mut *rng)
+                        .expect("seeding from a CSPRNG does not fail");
+                    scope.spawn(move || {
+                        for chunk in (worker..chunks).step_by(threads) {
+                            let size = CHUNK_SIZE.min(count - chunk * CHUNK_SIZE);
+                            let items = (0..size).map(|_| generate(&mut worker_rng)).collect();
+                            // 書き出し側が終了していれば生成もやめる
+                            if sender.send(items).is_err() {
+                                return;
+                            }
+                        }
+                    });
+                    receiver
+                })
+                .collect();
+
+            for chunk in 0..chunks {
+                let items = receivers[chunk % threads]
+                    .recv()
+                    .expect("generator thread panicked");
+                for item in &items {
+                    write_line(item)?;
+                }
+            }
+            Ok(())
+        })?;
+    }
+
+    writer.flush()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::Rng;
+
+    /// 指定回数の書き込みのあとは閉じられたパイプとして振る舞うライター
+    struct ClosingWriter {
+        remaining: usize,
+    }
+
+    impl Write for ClosingWriter {
+        fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+            if self.remaining == 0 {
+                return Err(io::ErrorKind::BrokenPipe.into());
+            }
+            self.remaining -= 1;
+            Ok(buf.len())
+        }
+
+        fn flush(&mut self) -> io::Result<()> {
+            Ok(())
+        }
+    }
+
+    fn plain() -> LineFormat<fn(&str, bool) -> String> {
+        LineFormat {
+            colorize_fn: |s, _| s.to_string(),
+            enable_color: false,
+            entropy: None,
+        }
+    }
+
+    fn generate(rng: &mut dyn CryptoRngCore) -> String {
+        rng.gen_range(0..1_000_000u32).to_string()
+    }
+
+    fn run(count: usize, threads: usize, seed: u64) -> Vec<String> {
+        let mut out = Vec::new();
+        let mut rng = ChaCha20Rng::seed_from_u64(seed);
+        write_generated(&mut out, count, threads, &mut rng, generate, &plain()).unwrap();
+        String::from_utf8(out)
+            .unwrap()
+            .lines()
+            .map(str::to_string)
+            .collect()
+    }
+
+    #[test]
+    fn test_sequential_matches_direct_generation() {
+        let mut rng = ChaCha20Rng::seed_from_u64(7);
+        let expected: Vec<String> = (0..10).map(|_| generate(&mut rng)).collect();
+        assert_eq!(run(10, 1, 7), expected);
+    }
+
+    #[test]
+    fn test_parallel_count_and_reproducibility() {
+        for count in [1, CHUNK_SIZE, CHUNK_SIZE + 1, 5 * CHUNK_SIZE + 3] {
+            let lines = run(count, 4, 42);
+            assert_eq!(lines.len(), count);
+            assert_eq!(lines, run(count, 4, 42));
+        }
+        // スレッドごとに別の乱数列を使う
+        let lines = run(4 * CHUNK_SIZE, 4, 1);
+        assert_ne!(lines[..CHUNK_SIZE], lines[CHUNK_SIZE..2 * CHUNK_SIZE]);
+    }
+
+    #[test]
+    fn test_entropy_suffix() {
+        let format = LineFormat {
+            entropy: Some(Entropy::Bits(10.0)),
+            ..plain()
+        };
+        let mut out = Vec::new();
+        let mut rng = ChaCha20Rng::seed_from_u64(0);
+        write_generated(&mut out, 2, 1, &mut rng, |_| "ab".to_string(), &format).unwrap();
+        assert_eq!(
+            String::from_utf8(out).unwrap(),
+            "ab  (10.0 bits, very weak)\nab  (10.0 bits, very weak)\n"
+        );
+    }
+
+    #[test]
+    fn test_closed_writer_stops_generation() {
+        for threads in [1, 3] {
+            let mut rng = ChaCha20Rng::seed_from_u64(0);
+            let writer = ClosingWriter { remaining: 5 };
+            let result = write_generated(writer, 100_000, threads, &mut rng, generate, &plain());
+            assert_eq!(result.unwrap_err().kind(), io::ErrorKind::BrokenPipe);
+        }
+    }
+}
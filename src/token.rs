@@ -0,0 +1,296 @@
+//! トークン・APIキー生成モジュール
+//!
+//! 指定したバイト数の乱数をそのままエンコードするため、エントロピーはバイト数×8ビットで、
+//! エンコードによる偏りはありません。UUIDv4も生成できます。
+
+use crate::error::{Result, RpgError};
+use crate::secret::SecretGenerator;
+use clap::ValueEnum;
+use rand::{CryptoRng, RngCore};
+
+/// トークンの最大バイト数
+pub const MAX_TOKEN_BYTES: usize = 1024;
+
+/// 16進数（小文字）
+const HEX_ALPHABET: &[u8; 16] = b"0123456789abcdef";
+
+/// URLセーフなBase64（RFC 4648 §5）
+const BASE64URL_ALPHABET: &[u8; 64] =
+    b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789-_";
+
+/// Base32（RFC 4648 §6）
+const BASE32_ALPHABET: &[u8; 32] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZ234567";
+
+/// Base58（Bitcoinのアルファベット。0、O、I、lを含まない）
+const BASE58_ALPHABET: &[u8; 58] = b"123456789ABCDEFGHJKLMNPQRSTUVWXYZabcdefghijkmnopqrstuvwxyz";
+
+/// トークンのエンコード
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum TokenEncoding {
+    /// 16進数（小文字）
+    Hex,
+    /// URLセーフなBase64（パディングなし）
+    Base64url,
+    /// Base32（大文字、パディングなし）
+    Base32,
+    /// Base58（Bitcoinのアルファベット）
+    Base58,
+    /// UUIDv4（常に16バイト、うち122ビットがランダム）
+    Uuid,
+}
+
+impl TokenEncoding {
+    /// バイト列をエンコード
+    ///
+    /// # Panics
+    ///
+    /// `Uuid`で16バイト以外の入力を渡した場合
+    pub fn encode(self, bytes: &[u8]) -> String {
+        match self {
+            TokenEncoding::Hex => encode_bits(bytes, HEX_ALPHABET, 4),
+            TokenEncoding::Base64url => encode_bits(bytes, BASE64URL_ALPHABET, 6),
+            TokenEncoding::Base32 => encode_bits(bytes, BASE32_ALPHABET, 5),
+            TokenEncoding::Base58 => encode_base58(bytes),
+            TokenEncoding::Uuid => format_uuid_v4(bytes),
+        }
+    }
+}
+
+/// 1文字あたり`bits`ビットのアルファベットでエンコード（パディングなし）
+fn encode_bits(bytes: &[u8], alphabet: &[u8], bits: u32) -> String {
+    let mask = (1u32 << bits) - 1;
+    let mut out = String::with_capacity((bytes.len() * 8).div_ceil(bits as usize));
+    let (mut buffer, mut buffered) = (0u32, 0u32);
+    for &byte in bytes {
+        buffer = (buffer << 8) | byte as u32;
+        buffered += 8;
+        while buffered >= bits {
+            buffered -= bits;
+            out.push(alphabet[((buffer >> buffered) & mask) as usize] as char);
+        }
+        buffer &= (1 << buffered) - 1;
+    }
+    // 余ったビットは右側を0で埋める
+    if buffered > 0 {
+        out.push(alphabet[((buffer << (bits - buffered)) & mask) as usize] as char);
+    }
+    out
+}
+
+/// Base58でエンコード（先頭の0のバイトは`1`になる）
+fn encode_base58(bytes: &[u8]) -> String {
+    let zeros = bytes.iter().take_while(|&&byte| byte == 0).count();
+
+    // 58進数の桁（下位から）に変換
+    let mut digits: Vec<u8> = Vec::with_capacity(bytes.len() * 138 / 100 + 1);
+    for &byte in &bytes[zeros..] {
+        let mut carry = byte as u32;
+        for digit in digits.iter_mut() {
+            carry += (*digit as u32) << 8;
+            *digit = (carry % 58) as u8;
+            carry /= 58;
+        }
+        while carry > 0 {
+            digits.push((carry % 58) as u8);
+            carry /= 58;
+        }
+    }
+
+    std::iter::repeat_n('1', zeros)
+        .chain(
+            digits
+                .iter()
+                .rev()
+                .map(|&digit| BASE58_ALPHABET[digit as usize] as char),
+        )
+        .collect()
+}
+
+/// 16バイトをUUIDv4の書式にする（バージョンとバリアントのビットは上書き）
+fn format_uuid_v4(bytes: &[u8]) -> String {
+    let mut uuid: [u8; 16] = bytes.try_into().expect("a UUID is 16 bytes");
+    uuid[6] = (uuid[6] & 0x0f) | 0x40;
+    uuid[8] = (uuid[8] & 0x3f) | 0x80;
+
+    let hex = encode_bits(&uuid, HEX_ALPHABET, 4);
+    format!(
+        "{}-{}-{}-{}-{}",
+        &hex[..8],
+        &hex[8..12],
+        &hex[12..16],
+        &hex[16..20],
+        &hex[20..]
+    )
+}
+
+/// トークンの設定
+#[derive(Debug, Clone, PartialEq)]
+pub struct TokenConfig {
+    /// 乱数のバイト数（UUIDは16）
+    pub bytes: usize,
+    /// エンコード
+    pub encoding: TokenEncoding,
+}
+
+impl Default for TokenConfig {
+    fn default() -> Self {
+        Self {
+            bytes: 32,
+            encoding: TokenEncoding::Hex,
+        }
+    }
+}
+
+impl TokenConfig {
+    /// UUIDのバイト数
+    pub const UUID_BYTES: usize = 16;
+
+    /// 設定の妥当性を検証
+    pub fn validate(&self) -> Result<()> {
+        if self.encoding == TokenEncoding::Uuid {
+            if self.bytes != Self::UUID_BYTES {
+                return Err(RpgError::UuidSizeFixed(self.bytes));
+            }
+            return Ok(());
+        }
+        if !(1..=MAX_TOKEN_BYTES).contains(&self.bytes) {
+            return Err(RpgError::InvalidTokenSize(self.bytes));
+        }
+        Ok(())
+    }
+}
+
+/// トークンのジェネレータ
+pub struct TokenGenerator {
+    config: TokenConfig,
+}
+
+impl TokenGenerator {
+    pub fn new(config: TokenConfig) -> Result<Self> {
+        config.validate()?;
+        Ok(Self { config })
+    }
+
+    /// 生成されるトークンのエントロピー（ビット）
+    pub fn entropy_bits(&self) -> f64 {
+        match self.config.encoding {
+            // バージョンとバリアントの6ビットは固定
+            TokenEncoding::Uuid => 122.0,
+            _ => (self.config.bytes * 8) as f64,
+        }
+    }
+
+    pub fn generate(&self) -> String {
+        self.generate_with(&mut rand::thread_rng())
+    }
+
+    /// 指定された乱数生成器でトークンを生成
+    pub fn generate_with<R: RngCore + CryptoRng>(&self, rng: &mut R) -> String {
+        let mut bytes = vec![0u8; self.config.bytes];
+        rng.fill_bytes(&mut bytes);
+        self.config.encoding.encode(&bytes)
+    }
+}
+
+impl SecretGenerator for TokenGenerator {
+    fn entropy_bits(&self) -> f64 {
+        TokenGenerator::entropy_bits(self)
+    }
+
+    fn generate_with<R: RngCore + CryptoRng>(&self, rng: &mut R) -> String {
+        TokenGenerator::generate_with(self, rng)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn generator(bytes: usize, encoding: TokenEncoding) -> TokenGenerator {
+        TokenGenerator::new(TokenConfig { bytes, encoding }).unwrap()
+    }
+
+    #[test]
+    fn test_encode_rfc4648_vectors() {
+        let hex = |s: &str| TokenEncoding::Hex.encode(s.as_bytes());
+        let b32 = |s: &str| TokenEncoding::Base32.encode(s.as_bytes());
+        let b64 = |s: &str| TokenEncoding::Base64url.encode(s.as_bytes());
+
+        assert_eq!(hex("foobar"), "666f6f626172");
+        assert_eq!(b32("f"), "MY");
+        assert_eq!(b32("foob"), "MZXW6YQ");
+        assert_eq!(b32("foobar"), "MZXW6YTBOI");
+        assert_eq!(b64("f"), "Zg");
+        assert_eq!(b64("foob"), "Zm9vYg");
+        assert_eq!(b64("foobar"), "Zm9vYmFy");
+        assert_eq!(TokenEncoding::Base64url.encode(&[0xfb, 0xff]), "-_8");
+    }
+
+    #[test]
+    fn test_encode_base58() {
+        let b58 = |bytes: &[u8]| TokenEncoding::Base58.encode(bytes);
+        assert_eq!(b58(b"Hello World!"), "2NEpo7TZRRrLZSi2U");
+        assert_eq!(b58(&[0, 0, 0x28, 0x7f, 0xb4, 0xcd]), "11233QC4");
+        assert_eq!(b58(&[0]), "1");
+        assert_eq!(b58(&[]), "");
+    }
+
+    #[test]
+    fn test_token_lengths_and_entropy() {
+        let cases = [
+            (TokenEncoding::Hex, 64),
+            (TokenEncoding::Base64url, 43),
+            (TokenEncoding::Base32, 52),
+        ];
+        for (encoding, length) in cases {
+            let generator = generator(32, encoding);
+            assert_eq!(generator.generate().len(), length);
+            assert_eq!(generator.entropy_bits(), 256.0);
+        }
+
+        // Base58は値によって長さが変わる
+        let generator = generator(32, TokenEncoding::Base58);
+        for _ in 0..20 {
+            let token = generator.generate();
+            assert!((32..=44).contains(&token.len()), "{}", token);
+            assert!(token.bytes().all(|b| BASE58_ALPHABET.contains(&b)));
+        }
+    }
+
+    #[test]
+    fn test_uuid_v4_format() {
+        let generator = generator(16, TokenEncoding::Uuid);
+        assert_eq!(generator.entropy_bits(), 122.0);
+        for _ in 0..20 {
+            let uuid = generator.generate();
+            let groups: Vec<&str> = uuid.split('-').collect();
+            assert_eq!(
+                groups.iter().map(|g| g.len()).collect::<Vec<_>>(),
+                [8, 4, 4, 4, 12]
+            );
+            assert!(groups[2].starts_with('4'));
+            assert!("89ab".contains(&groups[3][..1]));
+        }
+        assert_eq!(
+            TokenEncoding::Uuid.encode(&[0xff; 16]),
+            "ffffffff-ffff-4fff-bfff-ffffffffffff"
+        );
+    }
+
+    #[test]
+    fn test_token_invalid_sizes() {
+        let config = |bytes, encoding| TokenConfig { bytes, encoding };
+        assert_eq!(
+            config(0, TokenEncoding::Hex).validate(),
+            Err(RpgError::InvalidTokenSize(0))
+        );
+        assert_eq!(
+            config(2048, TokenEncoding::Base58).validate(),
+            Err(RpgError::InvalidTokenSize(2048))
+        );
+        assert_eq!(
+            config(32, TokenEncoding::Uuid).validate(),
+            Err(RpgError::UuidSizeFixed(32))
+        );
+    }
+}
@@ -0,0 +1,126 @@
+//! 重複のない生成モジュール
+//!
+//! 同じ値を引いたら引き直し、異なる値を生成順に集めます。
+//! 総数（2^エントロピー）が正確に分かっている場合は、それより多くは要求できません。
+
+use crate::error::{Result, RpgError};
+use std::collections::HashSet;
+
+/// 連続して重複を引いてよい回数の、要求された個数に対する倍率
+const MAX_DUPLICATE_STREAK_PER_ITEM: usize = 100;
+
+/// エントロピー（ビット）から計算した、生成され得る値の総数
+pub fn space_size(entropy_bits: f64) -> f64 {
+    2f64.powf(entropy_bits).round()
+}
+
+/// `generate`を繰り返し呼び、重複のない`count`個の値を生成順に集める
+///
+/// エントロピーが正確な値（`exact_entropy_bits`がSome）で、`count`が総数を超える場合は
+/// すぐに`NotEnoughUniqueValues`を返します。繰り返し・連続文字の制約や曖昧な正規表現などで
+/// エントロピーが推定値の場合（None）は総数が分からないため、連続して`count`の100倍引いても
+/// 新しい値が出なければ諦めます。
+pub fn collect_unique(
+    count: usize,
+    exact_entropy_bits: Option<f64>,
+    mut generate: impl FnMut() -> String,
+) -> Result<Vec<String>> {
+    if let Some(bits) = exact_entropy_bits {
+        let available = space_size(bits);
+        if count as f64 > available {
+            return Err(RpgError::NotEnoughUniqueValues {
+                requested: count,
+                available,
+            });
+        }
+    }
+
+    let mut seen = HashSet::with_capacity(count);
+    let mut values = Vec::with_capacity(count);
+    let mut duplicates = 0;
+    while values.len() < count {
+        let value = generate();
+        if seen.insert(value.clone()) {
+            values.push(value);
+            duplicates = 0;
+            continue;
+        }
+        duplicates += 1;
+        if exact_entropy_bits.is_none()
+            && duplicates > count.saturating_mul(MAX_DUPLICATE_STREAK_PER_ITEM)
+        {
+            return Err(RpgError::NotEnoughUniqueValues {
+                requested: count,
+                available: values.len() as f64,
+            });
+        }
+    }
+    Ok(values)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_space_size() {
+        assert_eq!(space_size(0.0), 1.0);
+        assert_eq!(space_size(10.0), 1024.0);
+        assert_eq!(space_size(5040f64.log2()), 5040.0);
+    }
+
+    #[test]
+    fn test_collect_unique_exhausts_small_space() {
+        let mut next = 0;
+        let values = collect_unique(10, Some(10f64.log2()), || {
+            next = (next + 7) % 10;
+            next.to_string()
+        })
+        .unwrap();
+        let mut sorted = values.clone();
+        sorted.sort();
+        assert_eq!(sorted, ["0", "1", "2", "3", "4", "5", "6", "7", "8", "9"]);
+        // 生成順のまま
+        assert_eq!(values[..3], ["7", "4", "1"]);
+    }
+
+    #[test]
+    fn test_collect_unique_count_exceeds_space() {
+        assert_eq!(
+            collect_unique(11, Some(10f64.log2()), || unreachable!()),
+            Err(RpgError::NotEnoughUniqueValues {
+                requested: 11,
+                available: 10.0,
+            })
+        );
+    }
+
+    #[test]
+    fn test_collect_unique_estimated_space_is_not_a_limit() {
+        // 推定値では総数で弾かないので、実際に足りていれば集められる
+        let mut next = 0;
+        let values = collect_unique(3, None, || {
+            next = (next + 1) % 3;
+            next.to_string()
+        })
+        .unwrap();
+        assert_eq!(values, ["1", "2", "0"]);
+    }
+
+    #[test]
+    fn test_collect_unique_gives_up_when_estimate_is_too_high() {
+        // 推定値では総数で弾けないが、実際には2通りしかない
+        let mut flip = false;
+        let result = collect_unique(3, None, || {
+            flip = !flip;
+            flip.to_string()
+        });
+        assert_eq!(
+            result,
+            Err(RpgError::NotEnoughUniqueValues {
+                requested: 3,
+                available: 2.0,
+            })
+        );
+    }
+}
@@ -26,6 +26,251 @@ pub const WORDLIST: &[&str] = &[
     "grin", "grip", "grow", "gulf", "guru", "half", "hall",
 ];
 
+/// よく使われるパスワード（流出したパスワードの上位から抜粋）
+///
+/// `rpg check`の辞書照合で使用します。すべて小文字で、出現頻度の高い順に並んでいます。
+/// 123456・qwerty・111111のように、連続文字・キーボード配列・繰り返しとして
+/// 検出されるもの（とその組み合わせ）は含めていません。
+pub const COMMON_PASSWORDS: &[&str] = &[
+    "password",
+    "password1",
+    "password123",
+    "iloveyou",
+    "dragon",
+    "sunshine",
+    "princess",
+    "letmein",
+    "monkey",
+    "superman",
+    "football",
+    "baseball",
+    "welcome",
+    "admin",
+    "login",
+    "master",
+    "hello",
+    "freedom",
+    "whatever",
+    "trustno1",
+    "starwars",
+    "passw0rd",
+    "shadow",
+    "michael",
+    "jennifer",
+    "hunter",
+    "ashley",
+    "bailey",
+    "mustang",
+    "access",
+    "batman",
+    "flower",
+    "charlie",
+    "donald",
+    "aa123456",
+    "solo",
+    "112233",
+    "159753",
+    "147258369",
+    "a123456",
+    "admin123",
+    "administrator",
+    "root",
+    "toor",
+    "pass",
+    "test",
+    "test123",
+    "guest",
+    "changeme",
+    "secret",
+    "letmein1",
+    "welcome1",
+    "football1",
+    "iloveyou1",
+    "monkey1",
+    "dragon1",
+    "master1",
+    "love",
+    "lovely",
+    "loveme",
+    "hottie",
+    "angel",
+    "angels",
+    "babygirl",
+    "princess1",
+    "jessica",
+    "nicole",
+    "daniel",
+    "jordan",
+    "michelle",
+    "andrew",
+    "joshua",
+    "thomas",
+    "robert",
+    "matthew",
+    "anthony",
+    "william",
+    "jasmine",
+    "tigger",
+    "soccer",
+    "hockey",
+    "killer",
+    "george",
+    "harley",
+    "ranger",
+    "buster",
+    "pepper",
+    "ginger",
+    "summer",
+    "cookie",
+    "cheese",
+    "chocolate",
+    "butterfly",
+    "purple",
+    "orange",
+    "banana",
+    "computer",
+    "internet",
+    "maggie",
+    "jordan23",
+    "superman1",
+    "batman1",
+    "pokemon",
+    "naruto",
+    "qwerty1",
+    "qwerty12",
+    "q1w2e3r4",
+    "q1w2e3r4t5",
+    "password12",
+    "password!",
+    "p@ssw0rd",
+    "pa55word",
+    "passport",
+    "password2",
+    "mypassword",
+    "blink182",
+    "michael1",
+    "samsung",
+    "apple",
+    "google",
+    "yankees",
+    "lakers",
+    "cowboys",
+    "eagles",
+    "steelers",
+    "chelsea",
+    "arsenal",
+    "liverpool",
+    "barcelona",
+    "hello123",
+    "hellokitty",
+    "iloveu",
+    "lovers",
+    "forever",
+    "friends",
+    "family",
+    "jesus",
+    "christ",
+    "heaven",
+    "blessed",
+    "god",
+    "matrix",
+    "hacker",
+    "ninja",
+    "dragon123",
+    "monster",
+    "rainbow",
+    "silver",
+    "golden",
+    "diamond",
+    "mickey",
+    "minnie",
+    "snoopy",
+    "garfield",
+    "scooby",
+    "tinkerbell",
+    "barbie",
+    "sparky",
+    "shadow1",
+    "hotmail",
+    "yahoo",
+    "gmail",
+    "facebook",
+    "myspace",
+    "linkedin",
+    "twitter",
+    "azerty",
+    "qwertz",
+    "159357",
+    "147852",
+    "258456",
+    "741852963",
+    "963852741",
+    "batman123",
+    "spiderman",
+    "ironman",
+    "pokemon1",
+    "starwars1",
+    "whatever1",
+    "trustno1!",
+    "letmein!",
+    "welcome123",
+    "admin1",
+    "root123",
+    "user",
+    "user123",
+    "default",
+    "system",
+    "server",
+    "oracle",
+    "mysql",
+    "postgres",
+    "database",
+    "backup",
+    "temp",
+    "temp123",
+    "demo",
+    "sample",
+    "changeit",
+    "secret123",
+    "pass123",
+    "pass1234",
+    "passwd",
+    "michelle1",
+    "jessica1",
+    "ashley1",
+    "charlie1",
+    "jordan1",
+    "hunter2",
+    "thunder",
+    "phoenix",
+    "merlin",
+    "falcon",
+    "eagle",
+    "tiger",
+    "lion",
+    "wolf",
+    "bear",
+    "chicken",
+    "123456a",
+    "12345a",
+    "a12345",
+    "1a2b3c",
+    "letmein123",
+    "iloveyou2",
+    "sunshine1",
+    "princess12",
+    "flower1",
+    "cookie1",
+    "summer1",
+    "winter",
+    "spring",
+    "autumn",
+    "january",
+    "december",
+    "monday",
+    "friday",
+];
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -42,6 +287,19 @@ mod tests {
         assert_eq!(unique.len(), WORDLIST.len(), "Wordlist contains duplicates");
     }
 
+    #[test]
+    fn test_common_passwords_uniqueness() {
+        let unique: HashSet<_> = COMMON_PASSWORDS.iter().collect();
+        assert_eq!(
+            unique.len(),
+            COMMON_PASSWORDS.len(),
+            "Common password list contains duplicates"
+        );
+        assert!(COMMON_PASSWORDS
+            .iter()
+            .all(|p| p.len() >= 3 && *p == p.to_lowercase()));
+    }
+
     #[test]
     fn test_wordlist_word_length() {
         for word in WORDLIST.iter() {
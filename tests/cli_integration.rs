@@ -1,3 +1,7 @@
+// 既存のテストは`Command::cargo_bin`と`args(&[..])`のまま残している
+#![allow(deprecated, clippy::needless_borrows_for_generic_args)]
+
+use assert_cmd::cargo::cargo_bin_cmd;
 use assert_cmd::Command;
 use predicates::prelude::*;
 
@@ -115,3 +119,840 @@ fn test_version_option() {
         .success()
         .stdout(predicate::str::contains("rpg 0.2.0"));
 }
+
+#[test]
+fn test_min_digits_and_max_symbols() {
+    cargo_bin_cmd!("rpg")
+        .args(["-l", "12", "--min-digits", "5", "--max-symbols", "1"])
+        .assert()
+        .success()
+        .stdout(predicate::function(|out: &str| {
+            let password = out.trim_end();
+            let digits = password.chars().filter(|c| c.is_ascii_digit()).count();
+            let symbols = password
+                .chars()
+                .filter(|c| !c.is_ascii_alphanumeric())
+                .count();
+            password.len() == 12 && digits >= 5 && symbols == 1
+        }));
+}
+
+#[test]
+fn test_impossible_minimums() {
+    cargo_bin_cmd!("rpg")
+        .args(["-l", "6", "--min-digits", "4", "--min-symbols", "3"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("exceeds the password length"));
+}
+
+#[test]
+fn test_no_ambiguous_option() {
+    cargo_bin_cmd!("rpg")
+        .args(["--no-ambiguous", "-l", "64"])
+        .assert()
+        .success()
+        .stdout(predicate::str::is_match(r#"^[^0Oo1lI|5S`'"]{64}\n$"#).unwrap());
+}
+
+#[test]
+fn test_custom_charset_option() {
+    cargo_bin_cmd!("rpg")
+        .args(["--charset", "abc", "-l", "24"])
+        .assert()
+        .success()
+        .stdout(predicate::str::is_match(r"^[abc]{24}\n$").unwrap());
+}
+
+#[test]
+fn test_symbols_and_exclude_options() {
+    cargo_bin_cmd!("rpg")
+        .args(["--symbols", "-_", "--exclude", "aeiou", "-l", "40"])
+        .assert()
+        .success()
+        .stdout(predicate::str::is_match(r"^[A-Zb-df-hj-np-tv-z0-9_\-]{40}\n$").unwrap());
+}
+
+#[test]
+fn test_exclude_entire_class() {
+    cargo_bin_cmd!("rpg")
+        .args(["--exclude", "0123456789"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("All digits were removed"));
+}
+
+#[test]
+fn test_pattern_option() {
+    cargo_bin_cmd!("rpg")
+        .args(["--pattern", "Cvccv-d{4}-ss", "-n", "2"])
+        .assert()
+        .success()
+        .stdout(
+            predicate::str::is_match(
+                r"(?m)^[b-df-hj-np-tv-zB-DF-HJ-NP-TV-Z][aeiou][b-df-hj-np-tv-z]{2}[aeiou]-\d{4}-[!@#$%^&*()_+\-=\[\]{}|;:,.<>?]{2}$",
+            )
+            .unwrap(),
+        );
+}
+
+#[test]
+fn test_pattern_parse_error() {
+    cargo_bin_cmd!("rpg")
+        .args(["--pattern", "dd{x}"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("Invalid pattern at position 4"));
+}
+
+#[test]
+fn test_regex_option() {
+    cargo_bin_cmd!("rpg")
+        .args(["--regex", "(sk|pk)_[a-f0-9]{32}", "-n", "5"])
+        .assert()
+        .success()
+        .stdout(predicate::str::is_match(r"^((sk|pk)_[a-f0-9]{32}\n){5}$").unwrap());
+}
+
+#[test]
+fn test_regex_unsupported_construct() {
+    cargo_bin_cmd!("rpg")
+        .args(["--regex", "[a-z]+"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains(
+            "Invalid regex at position 6: unbounded repetition '+'",
+        ));
+
+    cargo_bin_cmd!("rpg")
+        .args(["--regex", "[a-z]{4}", "--no-symbols"])
+        .assert()
+        .failure();
+}
+
+#[test]
+fn test_pronounceable_option() {
+    cargo_bin_cmd!("rpg")
+        .args([
+            "--pronounceable",
+            "-l",
+            "10",
+            "--no-digits",
+            "--no-uppercase",
+        ])
+        .assert()
+        .success()
+        .stdout(predicate::str::is_match(r"^[a-z]{10}\n$").unwrap());
+}
+
+#[test]
+fn test_pronounceable_conflicts_with_passphrase() {
+    cargo_bin_cmd!("rpg")
+        .args(["--pronounceable", "--passphrase"])
+        .assert()
+        .failure();
+}
+
+#[test]
+fn test_insecure_seed_known_answer_password() {
+    // 乱数の消費順序を固定する既知解テスト
+    cargo_bin_cmd!("rpg")
+        .args(["--insecure-seed", "42", "-n", "2"])
+        .assert()
+        .success()
+        .stdout("=*op5Q_Lo]Ne<PH#\nFG+83Xdu=*UrpPJL\n")
+        .stderr(predicate::str::contains("WARNING"));
+}
+
+#[test]
+fn test_insecure_seed_known_answer_passphrase() {
+    cargo_bin_cmd!("rpg")
+        .args(["--insecure-seed", "42", "--passphrase", "-n", "2"])
+        .assert()
+        .success()
+        .stdout("free-down-exam-deal\nable-blow-folk-crab\n");
+}
+
+#[test]
+fn test_threads_bulk_generation() {
+    let output = cargo_bin_cmd!("rpg")
+        .args(["-n", "5000", "-l", "12", "--threads", "3"])
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    assert_eq!(stdout.lines().count(), 5000);
+    assert!(stdout.lines().all(|line| line.chars().count() == 12));
+
+    // 固定シードならスレッド数が同じ限り出力は再現される
+    let seeded = || {
+        cargo_bin_cmd!("rpg")
+            .args(["--insecure-seed", "7", "-n", "3000", "--threads", "2"])
+            .output()
+            .unwrap()
+            .stdout
+    };
+    assert_eq!(seeded(), seeded());
+}
+
+#[test]
+fn test_threads_invalid() {
+    cargo_bin_cmd!("rpg")
+        .args(["--threads", "0"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("Invalid thread count: 0"));
+
+    cargo_bin_cmd!("rpg")
+        .args(["--threads", "2", "--copy"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("cannot be used with"));
+}
+
+#[test]
+fn test_unique_exhausts_small_space() {
+    let output = cargo_bin_cmd!("rpg")
+        .args(["-l", "2", "--charset", "ab", "--unique", "-n", "4"])
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+    let mut lines: Vec<String> = String::from_utf8(output.stdout)
+        .unwrap()
+        .lines()
+        .map(str::to_string)
+        .collect();
+    lines.sort();
+    assert_eq!(lines, ["aa", "ab", "ba", "bb"]);
+
+    cargo_bin_cmd!("rpg")
+        .args(["-l", "2", "--charset", "ab", "--unique", "-n", "5"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains(
+            "Cannot generate 5 unique values: only 4 are possible",
+        ));
+}
+
+#[test]
+fn test_unique_passphrase() {
+    cargo_bin_cmd!("rpg")
+        .args(["--passphrase", "--words", "1", "--unique", "-n", "256"])
+        .assert()
+        .success();
+
+    cargo_bin_cmd!("rpg")
+        .args(["--passphrase", "--words", "1", "--unique", "-n", "257"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("only 256 are possible"));
+}
+
+#[test]
+fn test_unique_with_pattern_and_regex() {
+    let output = cargo_bin_cmd!("rpg")
+        .args(["--pattern", "d", "--unique", "-n", "10"])
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+    let mut lines: Vec<String> = String::from_utf8(output.stdout)
+        .unwrap()
+        .lines()
+        .map(str::to_string)
+        .collect();
+    lines.sort();
+    assert_eq!(lines, ["0", "1", "2", "3", "4", "5", "6", "7", "8", "9"]);
+
+    cargo_bin_cmd!("rpg")
+        .args(["--pattern", "d", "--unique", "-n", "11"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains(
+            "Cannot generate 11 unique values: only 10 are possible",
+        ));
+
+    // 曖昧な正規表現のエントロピー（2通りと数える）は総数ではないため、重複が続いて諦める
+    cargo_bin_cmd!("rpg")
+        .args(["--regex", "a|a", "--unique", "-n", "2"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("Cannot generate 2 unique values"));
+
+    cargo_bin_cmd!("rpg")
+        .args(["--pronounceable", "--unique", "-n", "20"])
+        .assert()
+        .success()
+        .stdout(predicate::str::is_match(r"^(\S+\n){20}$").unwrap());
+}
+
+#[test]
+fn test_insecure_seed_hidden_from_help() {
+    cargo_bin_cmd!("rpg")
+        .arg("--help")
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("insecure-seed").not());
+}
+
+#[test]
+fn test_show_entropy_option() {
+    cargo_bin_cmd!("rpg")
+        .args(["--show-entropy", "-l", "10", "--charset", "ab"])
+        .assert()
+        .success()
+        .stdout(predicate::str::is_match(r"^[ab]{10}  \(10\.0 bits, very weak\)\n$").unwrap());
+}
+
+#[test]
+fn test_show_entropy_passphrase() {
+    cargo_bin_cmd!("rpg")
+        .args(["--show-entropy", "--passphrase", "--words", "1"])
+        .assert()
+        .success()
+        .stdout(predicate::str::ends_with("  (8.0 bits, very weak)\n"));
+}
+
+#[test]
+fn test_entropy_target_password() {
+    // 小文字のみ: 26^9 ≈ 2^42.3 が40ビット以上となる最小の長さ
+    cargo_bin_cmd!("rpg")
+        .args(["--entropy", "40", "--charset", "abcdefghijklmnopqrstuvwxyz"])
+        .assert()
+        .success()
+        .stdout(predicate::str::is_match(r"^[a-z]{9}\n$").unwrap());
+}
+
+#[test]
+fn test_entropy_target_passphrase() {
+    cargo_bin_cmd!("rpg")
+        .args(["--entropy", "40", "--passphrase"])
+        .assert()
+        .success()
+        .stdout(predicate::str::is_match(r"^\w+(-\w+){5}\n$").unwrap());
+}
+
+#[test]
+fn test_entropy_target_unreachable() {
+    cargo_bin_cmd!("rpg")
+        .args(["--entropy", "500", "--passphrase"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("unreachable"));
+}
+
+#[test]
+fn test_min_entropy_rejects_weak_config() {
+    cargo_bin_cmd!("rpg")
+        .args(["--min-entropy", "80", "-l", "8"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("below the required minimum"));
+}
+
+#[test]
+fn test_min_entropy_accepts_strong_config() {
+    cargo_bin_cmd!("rpg")
+        .args(["--min-entropy", "80", "-l", "20"])
+        .assert()
+        .success();
+}
+
+#[test]
+fn test_min_entropy_rejects_invalid_values() {
+    for value in ["NaN", "-1", "inf"] {
+        cargo_bin_cmd!("rpg")
+            .arg(format!("--min-entropy={}", value))
+            .assert()
+            .failure()
+            .stderr(predicate::str::contains("Invalid entropy target"));
+    }
+}
+
+#[test]
+fn test_min_entropy_with_ambiguous_regex() {
+    // 1025通りしかないが、選び方の数では1024ビットになる
+    cargo_bin_cmd!("rpg")
+        .args(["--regex", "(a|){1024}", "--min-entropy", "80"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("only an upper bound"));
+
+    cargo_bin_cmd!("rpg")
+        .args(["--regex", "(a|){1024}", "--show-entropy"])
+        .assert()
+        .success()
+        .stdout(predicate::str::ends_with(
+            "  (≤ 1024.0 bits, upper bound)\n",
+        ));
+}
+
+#[test]
+fn test_check_reports_weak_patterns() {
+    cargo_bin_cmd!("rpg")
+        .args(["check"])
+        .write_stdin("D4rk-qwerty123\n")
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Password: D4rk-qwerty123"))
+        .stdout(predicate::str::contains("keyboard walk"))
+        .stdout(predicate::str::contains("sequence"))
+        .stdout(predicate::str::contains("dictionary word"));
+}
+
+#[test]
+fn test_check_custom_wordlist() {
+    let dir = std::env::temp_dir().join(format!("rpg-check-{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    let wordlist = dir.join("words.txt");
+    std::fs::write(&wordlist, "zorblax\n").unwrap();
+
+    cargo_bin_cmd!("rpg")
+        .args(["check", "--wordlist", wordlist.to_str().unwrap()])
+        .write_stdin("Zorblax!\n")
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("dictionary word \"zorblax\""));
+
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn test_check_rejects_generation_options() {
+    cargo_bin_cmd!("rpg")
+        .args(["-l", "5", "check"])
+        .write_stdin("password\n")
+        .assert()
+        .failure();
+}
+
+#[test]
+fn test_policy_file() {
+    let dir = std::env::temp_dir().join(format!("rpg-policy-{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    let policy = dir.join("policy.toml");
+    std::fs::write(
+        &policy,
+        "length = 20\nrequired = [\"lower\", \"digits\"]\nallowed = [\"lower\", \"digits\"]\n\
+         [position]\nfirst = [\"letters\"]\n",
+    )
+    .unwrap();
+
+    cargo_bin_cmd!("rpg")
+        .args(["--policy", policy.to_str().unwrap(), "-n", "5"])
+        .assert()
+        .success()
+        .stdout(predicate::str::is_match(r"^([a-z][a-z0-9]{19}\n){5}$").unwrap());
+
+    std::fs::write(&policy, "min_length = 30\nmax_length = 20\n").unwrap();
+    cargo_bin_cmd!("rpg")
+        .args(["--policy", policy.to_str().unwrap()])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("Invalid policy rule 'min_length'"));
+
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn test_policy_conflicts_with_class_flags() {
+    cargo_bin_cmd!("rpg")
+        .args(["--policy", "policy.toml", "--no-symbols"])
+        .assert()
+        .failure();
+}
+
+#[test]
+fn test_pin_subcommand() {
+    cargo_bin_cmd!("rpg")
+        .args(["pin", "-l", "4", "-n", "50"])
+        .assert()
+        .success()
+        .stdout(predicate::str::is_match(r"^([0-9]{4}\n){50}$").unwrap())
+        .stdout(predicate::str::contains("1234\n").not())
+        .stdout(predicate::str::contains("0000\n").not());
+
+    cargo_bin_cmd!("rpg")
+        .args(["pin", "--show-entropy"])
+        .assert()
+        .success()
+        .stdout(predicate::str::is_match(r"^[0-9]{6}  \(19\.\d bits, very weak\)\n$").unwrap());
+}
+
+#[test]
+fn test_pin_unique_exhausts_space() {
+    // 4桁（最小の桁数）から推測されやすいPINを除くと9118通り
+    let output = cargo_bin_cmd!("rpg")
+        .args(["pin", "-l", "4", "-n", "9118", "--unique"])
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    let pins: std::collections::HashSet<&str> = stdout.lines().collect();
+    assert_eq!(pins.len(), 9118);
+    assert!(!pins.contains("1234"));
+
+    cargo_bin_cmd!("rpg")
+        .args(["pin", "-l", "4", "-n", "9119", "--unique"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains(
+            "Cannot generate 9119 unique values: only 9118 are possible",
+        ));
+}
+
+#[test]
+fn test_pin_invalid_length() {
+    cargo_bin_cmd!("rpg")
+        .args(["pin", "--length", "3"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("Invalid PIN length: 3"));
+}
+
+#[test]
+fn test_token_encodings() {
+    let cases = [
+        ("hex", r"^[0-9a-f]{64}\n$"),
+        ("base64url", r"^[A-Za-z0-9_-]{43}\n$"),
+        ("base32", r"^[A-Z2-7]{52}\n$"),
+        ("base58", r"^[1-9A-HJ-NP-Za-km-z]{40,44}\n$"),
+        (
+            "uuid",
+            r"^[0-9a-f]{8}-[0-9a-f]{4}-4[0-9a-f]{3}-[89ab][0-9a-f]{3}-[0-9a-f]{12}\n$",
+        ),
+    ];
+    for (encoding, pattern) in cases {
+        cargo_bin_cmd!("rpg")
+            .args(["token", "--encoding", encoding])
+            .assert()
+            .success()
+            .stdout(predicate::str::is_match(pattern).unwrap());
+    }
+}
+
+#[test]
+fn test_token_bytes() {
+    cargo_bin_cmd!("rpg")
+        .args(["token", "--bytes", "16", "-e", "base64url", "-n", "3"])
+        .assert()
+        .success()
+        .stdout(predicate::str::is_match(r"^([A-Za-z0-9_-]{22}\n){3}$").unwrap());
+
+    cargo_bin_cmd!("rpg")
+        .args(["token", "--bytes", "32", "-e", "uuid"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("UUIDs are always 16 bytes"));
+}
+
+#[test]
+fn test_key_generate_and_verify() {
+    let output = cargo_bin_cmd!("rpg")
+        .args(["key", "--prefix", "acme", "--length", "24"])
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+    let key = String::from_utf8(output.stdout).unwrap();
+    assert!(predicate::str::is_match(r"^acme_[0-9A-Za-z]{30}\n$")
+        .unwrap()
+        .eval(&key));
+
+    cargo_bin_cmd!("rpg")
+        .args(["verify-key", "--prefix", "acme", "--length", "24"])
+        .write_stdin(key.clone())
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(
+            "Valid key (prefix 'acme', 24-character body)",
+        ));
+
+    // 本体の1文字を変えるとチェックサムで検出される
+    let last_body = key.len() - 1 - 6 - 1;
+    let mut typo = key.clone().into_bytes();
+    typo[last_body] = if typo[last_body] == b'x' { b'y' } else { b'x' };
+    cargo_bin_cmd!("rpg")
+        .args(["verify-key"])
+        .write_stdin(typo)
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("Invalid key: checksum mismatch"));
+
+    cargo_bin_cmd!("rpg")
+        .args(["verify-key", "--prefix", "live"])
+        .write_stdin(key)
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("expected 'live'"));
+}
+
+#[test]
+fn test_key_invalid_prefix() {
+    cargo_bin_cmd!("rpg")
+        .args(["key", "--prefix", "my_co"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("Invalid key format"));
+}
+
+#[test]
+fn test_presets_list_and_show() {
+    cargo_bin_cmd!("rpg")
+        .args(["presets", "list"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("aws-iam"))
+        .stdout(predicate::str::contains("wifi-wpa2"));
+
+    cargo_bin_cmd!("rpg")
+        .args(["presets", "show", "pci-dss"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("min_length = 12"));
+}
+
+#[test]
+fn test_preset_generation() {
+    cargo_bin_cmd!("rpg")
+        .args(["--preset", "wifi-wpa2", "-n", "3"])
+        .assert()
+        .success()
+        .stdout(predicate::str::is_match(r"^([A-Za-z0-9]{20}\n){3}$").unwrap());
+}
+
+#[test]
+fn test_unknown_preset() {
+    cargo_bin_cmd!("rpg")
+        .args(["--preset", "no-such-preset"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("Unknown preset 'no-such-preset'"));
+
+    cargo_bin_cmd!("rpg")
+        .args(["presets", "show", "no-such-preset"])
+        .assert()
+        .failure();
+}
+
+#[test]
+fn test_password_rules() {
+    cargo_bin_cmd!("rpg")
+        .args([
+            "--rules",
+            "minlength: 10; maxlength: 10; required: lower; required: digit; required: [-];",
+            "-n",
+            "5",
+        ])
+        .assert()
+        .success()
+        .stdout(predicate::str::is_match(r"^([a-z0-9-]{10}\n){5}$").unwrap());
+}
+
+#[test]
+fn test_password_rules_parse_error() {
+    cargo_bin_cmd!("rpg")
+        .args(["--rules", "minlength: 8; colour: red"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains(
+            "Invalid password rules at position 15: unknown rule 'colour'",
+        ));
+}
+
+#[test]
+fn test_no_repeats_or_sequences() {
+    let output = cargo_bin_cmd!("rpg")
+        .args([
+            "--charset",
+            "0123456789",
+            "-l",
+            "8",
+            "--max-consecutive",
+            "1",
+            "--no-sequences",
+            "-n",
+            "50",
+        ])
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+
+    for line in String::from_utf8(output.stdout).unwrap().lines() {
+        let digits: Vec<i32> = line.bytes().map(|b| (b - b'0') as i32).collect();
+        assert_eq!(digits.len(), 8);
+        assert!(digits.windows(2).all(|w| w[0] != w[1]), "{}", line);
+        assert!(
+            digits
+                .windows(3)
+                .all(|w| !((w[1] - w[0]).abs() == 1 && w[2] - w[1] == w[1] - w[0])),
+            "{}",
+            line
+        );
+    }
+}
+
+#[test]
+fn test_max_occurrences_without_repeats() {
+    cargo_bin_cmd!("rpg")
+        .args(["-l", "40", "--max-occurrences", "1", "-n", "5"])
+        .assert()
+        .success()
+        .stdout(predicate::function(|out: &str| {
+            out.lines().count() == 5
+                && out.lines().all(|line| {
+                    let mut chars: Vec<char> = line.chars().collect();
+                    chars.sort_unstable();
+                    chars.dedup();
+                    line.len() == 40 && chars.len() == 40
+                })
+        }));
+}
+
+#[test]
+fn test_max_occurrences_too_strict() {
+    cargo_bin_cmd!("rpg")
+        .args(["--charset", "ab", "-l", "6", "--max-occurrences", "2"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("repetition and sequence rules"));
+}
+
+#[test]
+fn test_start_and_end_with() {
+    cargo_bin_cmd!("rpg")
+        .args(["--start-with", "letter", "--end-with", "digit", "-n", "20"])
+        .assert()
+        .success()
+        .stdout(predicate::str::is_match(r"^([A-Za-z].{14}[0-9]\n){20}$").unwrap());
+}
+
+#[test]
+fn test_no_symbol_at_ends() {
+    cargo_bin_cmd!("rpg")
+        .args(["--no-symbol-at-ends", "-l", "6", "-n", "20"])
+        .assert()
+        .success()
+        .stdout(predicate::str::is_match(r"^([A-Za-z0-9].{4}[A-Za-z0-9]\n){20}$").unwrap());
+}
+
+#[test]
+fn test_safe_for_url() {
+    cargo_bin_cmd!("rpg")
+        .args(["--safe-for", "url", "-n", "20"])
+        .assert()
+        .success()
+        .stdout(predicate::str::is_match(r"^([A-Za-z0-9._~-]{16}\n){20}$").unwrap());
+}
+
+#[test]
+fn test_safe_for_removes_every_symbol() {
+    cargo_bin_cmd!("rpg")
+        .args(["--safe-for", "url", "--symbols", "!@"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("All symbols were removed"));
+
+    cargo_bin_cmd!("rpg")
+        .args(["--safe-for", "html"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("possible values"));
+}
+
+#[test]
+fn test_unicode_sets_count_length_in_characters() {
+    let output = cargo_bin_cmd!("rpg")
+        .args(["--unicode", "greek,kana", "-l", "20", "-n", "10"])
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+    for line in String::from_utf8(output.stdout).unwrap().lines() {
+        assert_eq!(line.chars().count(), 20, "{:?}", line);
+    }
+}
+
+#[test]
+fn test_max_bytes() {
+    let output = cargo_bin_cmd!("rpg")
+        .args([
+            "--unicode",
+            "cyrillic",
+            "-l",
+            "12",
+            "--max-bytes",
+            "16",
+            "-n",
+            "20",
+        ])
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+    for line in String::from_utf8(output.stdout).unwrap().lines() {
+        assert_eq!(line.chars().count(), 12);
+        assert!(line.len() <= 16, "{:?} is {} bytes", line, line.len());
+    }
+
+    cargo_bin_cmd!("rpg")
+        .args([
+            "--unicode",
+            "kana",
+            "--no-uppercase",
+            "--no-digits",
+            "--no-symbols",
+        ])
+        .args([
+            "--exclude",
+            "abcdefghijklmnopqrstuvwxyz",
+            "-l",
+            "8",
+            "--max-bytes",
+            "20",
+        ])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("never fit in 20 bytes"));
+}
+
+#[test]
+fn test_position_rule_contradiction() {
+    cargo_bin_cmd!("rpg")
+        .args(["--start-with", "symbol", "--no-symbols"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("first character rule"));
+
+    cargo_bin_cmd!("rpg")
+        .args(["--start-with", "emoji"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("unknown character class 'emoji'"));
+}
+
+#[test]
+fn test_check_flags_common_password() {
+    cargo_bin_cmd!("rpg")
+        .args(["check"])
+        .write_stdin("password123\n")
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("dictionary word \"password"))
+        .stdout(predicate::str::contains("very weak"));
+}
+
+#[test]
+fn test_rules_with_rare_valid_passwords() {
+    cargo_bin_cmd!("rpg")
+        .args([
+            "--rules",
+            "allowed: [abc]; minlength: 30; maxlength: 30; max-consecutive: 1",
+            "-n",
+            "5",
+        ])
+        .assert()
+        .success()
+        .stdout(predicate::function(|out: &str| {
+            out.lines().count() == 5
+                && out.lines().all(|line| {
+                    line.len() == 30 && line.as_bytes().windows(2).all(|p| p[0] != p[1])
+                })
+        }));
+}